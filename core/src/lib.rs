@@ -0,0 +1,6668 @@
+//! The transformation behind the `type-macro-derive-tricks` attributes, for use in other
+//! procedural macros
+//!
+//! Derive macros cannot see through macro invocations in type positions, such as
+//! `Pair![T]`. This crate moves every such invocation into a generated type alias and
+//! rewrites the item to refer to the alias instead, operating on [`proc_macro2`] and [`syn`]
+//! types.
+//!
+//! The functions named after the attributes, like [`macro_derive`], perform the complete
+//! expansion of an attribute. The building blocks let other macros embed the transformation:
+//! [`collect_macro_types_from_type`] gathers the macro types of a type into
+//! [`MacroAliases`], [`emit_aliases`] generates the alias items and [`transform_type`]
+//! rewrites a type to refer to them.
+//!
+//! ```
+//! use type_macro_derive_tricks_core::{
+//!     collect_macro_types_from_type, emit_aliases, transform_type, MacroAliases,
+//!     MacroDeriveArgs,
+//! };
+//!
+//! let args = MacroDeriveArgs::default();
+//! let generics: syn::Generics = syn::parse_quote!(<T>);
+//! let mut ty: syn::Type = syn::parse_quote!(Vec<Pair![T]>);
+//!
+//! let mut aliases = MacroAliases::default();
+//! collect_macro_types_from_type(&ty, &generics, &args, &mut aliases);
+//! let items = emit_aliases(&aliases, &syn::Visibility::Inherited);
+//! transform_type(&mut ty, &aliases, &generics);
+//!
+//! assert_eq!(items.len(), 1);
+//! assert!(matches!(&ty, syn::Type::Path(_)));
+//! ```
+#![cfg_attr(feature = "nightly-diagnostics", feature(proc_macro_diagnostic))]
+#![cfg_attr(feature = "nightly-tracked-env", feature(proc_macro_tracked_env))]
+
+#[cfg(any(feature = "nightly-diagnostics", feature = "nightly-tracked-env"))]
+extern crate proc_macro;
+
+use proc_macro2::TokenStream as TokenStream2;
+use rand::{distributions::Alphanumeric, Rng};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use syn::{
+    ext::IdentExt,
+    parse::{discouraged::Speculative, Parse, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    visit::Visit,
+    visit_mut::VisitMut,
+    Data, DeriveInput, Generics, Ident, Meta, Token, Type,
+};
+use template_quote::quote;
+
+/// Expansion of `#[macro_derive(args)]` applied to `item`, for testing and inspecting the
+/// generated code
+///
+/// The output is exactly what the attribute emits, including the errors, which are returned
+/// as `compile_error!` invocations next to the item. Alias names are random unless the
+/// arguments ask for deterministic names, so tests comparing the output should pass
+/// `stable_names = true`.
+///
+/// ```
+/// use proc_macro2::TokenStream;
+///
+/// let expand = || {
+///     let args: TokenStream = r#"Debug, prefix = "Alias", stable_names = true"#
+///         .parse()
+///         .unwrap();
+///     let item: TokenStream = "struct Point<T> { coords: Pair![T] }".parse().unwrap();
+///     type_macro_derive_tricks_core::expand(args, item).to_string()
+/// };
+/// assert!(expand().contains("struct Point < T > { coords : Alias"));
+/// assert_eq!(expand(), expand());
+/// ```
+pub fn expand(args: TokenStream2, item: TokenStream2) -> TokenStream2 {
+    macro_derive(args, item.clone()).unwrap_or_else(|err| recover(err, item))
+}
+
+/// Expansion of `#[macro_derive(args)]` applied to `input`
+pub fn macro_derive(args: TokenStream2, input: TokenStream2) -> syn::Result<TokenStream2> {
+    if let Some(call) = derive_group_call("macro_derive", &args, &input) {
+        return call;
+    }
+    if let Some(call) = callback_call(&args, &input) {
+        return call;
+    }
+    expand_macro_derive(syn::parse2(args)?, input)
+}
+
+fn expand_macro_derive(
+    mut args: MacroDeriveArgs,
+    input: TokenStream2,
+) -> syn::Result<TokenStream2> {
+    let mut input = parse_adt("macro_derive", input)?;
+
+    // Stacked `#[macro_derive]` attributes are merged into this invocation; otherwise
+    // they would re-transform the already rewritten item
+    merge_stacked_attrs(&mut args, &mut input.attrs)?;
+
+    impl_type_macro_derive_tricks(&args, &input)
+}
+
+/// Expansion of `#[type_macro_expand(args)]` applied to `input`
+pub fn type_macro_expand(args: TokenStream2, input: TokenStream2) -> syn::Result<TokenStream2> {
+    let args: MacroDeriveArgs = syn::parse2(args)?;
+    match syn::parse2::<syn::Item>(input.clone()) {
+        Ok(syn::Item::Const(item)) => impl_const_macro_expand(&args, item),
+        Ok(syn::Item::Static(item)) => impl_static_macro_expand(&args, item),
+        _ => impl_type_macro_expand(&args, &parse_adt("type_macro_expand", input)?),
+    }
+}
+
+/// Expansion of `#[macro_derive_mod(args)]` applied to `input`
+pub fn macro_derive_mod(args: TokenStream2, input: TokenStream2) -> syn::Result<TokenStream2> {
+    if let Some(call) = derive_group_call("macro_derive_mod", &args, &input) {
+        return call;
+    }
+    let args: MacroDeriveArgs = syn::parse2(args)?;
+    impl_macro_derive_mod(&args, syn::parse2(input)?)
+}
+
+/// Expansion of `#[macro_impl(args)]` applied to `input`
+pub fn macro_impl(args: TokenStream2, input: TokenStream2) -> syn::Result<TokenStream2> {
+    let args: MacroDeriveArgs = syn::parse2(args)?;
+    impl_macro_impl(&args, &syn::parse2(input)?)
+}
+
+/// Expansion of `#[macro_trait(args)]` applied to `input`
+pub fn macro_trait(args: TokenStream2, input: TokenStream2) -> syn::Result<TokenStream2> {
+    let args: MacroDeriveArgs = syn::parse2(args)?;
+    impl_macro_trait(&args, &syn::parse2(input)?)
+}
+
+/// Expansion of `#[macro_fn(args)]` applied to `input`
+pub fn macro_fn(args: TokenStream2, input: TokenStream2) -> syn::Result<TokenStream2> {
+    let args: MacroDeriveArgs = syn::parse2(args)?;
+    impl_macro_fn(&args, &syn::parse2(input)?)
+}
+
+/// Expansion of `type_aliased! { input }`
+pub fn type_aliased(input: TokenStream2) -> syn::Result<TokenStream2> {
+    let items = syn::parse::Parser::parse2(parse_items, input)?;
+    expand_items(&MacroDeriveArgs::default(), items)
+}
+
+/// Expansion of `define_derive_group! { input }`
+///
+/// The group is a `macro_rules!` macro re-emitting the attribute it is given with the entries
+/// of the group prepended to its arguments. See [`derive_group_call`].
+pub fn define_derive_group(input: TokenStream2) -> syn::Result<TokenStream2> {
+    let group: DeriveGroup = syn::parse2(input)?;
+    let DeriveGroup {
+        attrs,
+        vis,
+        name,
+        entries,
+    } = &group;
+    let macro_name = derive_group_macro_name(name);
+    let export = match vis {
+        syn::Visibility::Inherited => None,
+        vis => Some(quote!(#vis use #macro_name;)),
+    };
+    // `$` cannot be quoted, as it would start an interpolation
+    let dollar = proc_macro2::Punct::new('$', proc_macro2::Spacing::Alone);
+    Ok(quote! {
+        #(#attrs)*
+        macro_rules! #macro_name {
+            ({ #dollar(#dollar attribute:tt)* } { #dollar(#dollar args:tt)* } #dollar(#dollar item:tt)*) => {
+                #[#dollar(#dollar attribute)*(#(#entries),* #dollar(#dollar args)*)]
+                #dollar(#dollar item)*
+            };
+        }
+        #export
+    })
+}
+
+/// Expansion of `define_type_macro! { input }`
+///
+/// The `macro_rules!` definitions in `input` are emitted, each together with a derive group
+/// named after the macro, whose only entry is `inline_rules(...)` with the definition.
+/// `@Name` thereby expands the invocations of `Name` in place. The group also invokes `Name`
+/// through a hidden first rule expanding to nothing, so that the compiler counts the
+/// invocations expanded in place as uses.
+pub fn define_type_macro(input: TokenStream2) -> syn::Result<TokenStream2> {
+    let defs = syn::parse::Parser::parse2(parse_macro_rules_defs, input)?;
+    // `$` cannot be quoted, as it would start an interpolation
+    let dollar = proc_macro2::Punct::new('$', proc_macro2::Spacing::Alone);
+    let items = defs.iter().map(|def| {
+        let MacroRulesDef {
+            attrs,
+            keyword,
+            name,
+            body,
+            ..
+        } = def;
+        let rules = body.stream();
+        let macro_name = derive_group_macro_name(name);
+        // The transcriber would take the `$` of the rules for its own metavariables, so they
+        // are written as `$dollar`, which the group binds to a `$` passed to itself
+        let escaped_dollar = quote!(#dollar dollar);
+        let semi = (body.delimiter() != proc_macro2::Delimiter::Brace).then(|| quote!(;));
+        let inline_rules = escape_dollars(&quote!(#keyword! #name #body #semi), &escaped_dollar);
+        quote! {
+            #(#attrs)*
+            #keyword! #name {
+                (@__macro_derive_used) => {};
+                #rules
+            }
+            macro_rules! #macro_name {
+                ({ #dollar(#dollar attribute:tt)* } #dollar(#dollar rest:tt)*) => {
+                    #macro_name! { @dollar(#dollar) { #dollar(#dollar attribute)* } #dollar(#dollar rest)* }
+                };
+                (@dollar(#dollar dollar:tt) { #dollar(#dollar attribute:tt)* } { #dollar(#dollar args:tt)* } #dollar(#dollar item:tt)*) => {
+                    #name! { @__macro_derive_used }
+                    #[#dollar(#dollar attribute)*(inline_rules(#inline_rules) #dollar(#dollar args)*)]
+                    #dollar(#dollar item)*
+                };
+            }
+        }
+    });
+    Ok(quote!(#(#items)*))
+}
+
+/// Replace every `$` in `tokens` with `replacement`
+fn escape_dollars(tokens: &TokenStream2, replacement: &TokenStream2) -> TokenStream2 {
+    use proc_macro2::{Group, TokenTree};
+
+    tokens
+        .clone()
+        .into_iter()
+        .flat_map(|token| match token {
+            TokenTree::Punct(punct) if punct.as_char() == '$' => replacement.clone(),
+            TokenTree::Group(group) => {
+                let mut escaped = Group::new(
+                    group.delimiter(),
+                    escape_dollars(&group.stream(), replacement),
+                );
+                escaped.set_span(group.span());
+                TokenTree::Group(escaped).into()
+            }
+            other => other.into(),
+        })
+        .collect()
+}
+
+/// `define_derive_group!(#[attrs] vis name = entries)`
+struct DeriveGroup {
+    attrs: Vec<syn::Attribute>,
+    vis: syn::Visibility,
+    name: Ident,
+    entries: Vec<TokenStream2>,
+}
+
+impl Parse for DeriveGroup {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        // `macro_rules!` macros can only leave the crate through `#[macro_export]`
+        if let syn::Visibility::Public(_) = vis {
+            return Err(syn::Error::new_spanned(
+                &vis,
+                "derive groups cannot be `pub`, use `pub(crate)` or a narrower visibility",
+            ));
+        }
+        let name = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let mut errors = Vec::new();
+        let entries = parse_entries(input, parse_arg_entry, &mut errors)?;
+        combine_errors(errors)?;
+        if entries.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &name,
+                "a derive group needs at least one entry",
+            ));
+        }
+
+        // Check the entries now rather than wherever the group is used
+        let mut args = MacroDeriveArgs::default();
+        for entry in entries.iter() {
+            if let ArgEntry::Other(meta) = entry {
+                args.apply_entry((**meta).clone())?;
+            }
+        }
+        Ok(DeriveGroup {
+            attrs,
+            vis,
+            name,
+            entries: entries.iter().map(ArgEntry::to_token_stream).collect(),
+        })
+    }
+}
+
+/// An argument of `macro_derive`, which is either a derive group or any other entry
+enum ArgEntry {
+    /// `@name`
+    Group(syn::Path),
+    // Boxed, since a `Meta` is several times as large as a path
+    Other(Box<Meta>),
+}
+
+impl ArgEntry {
+    /// The entry if it is the option `name = value`
+    fn name_value(&self, name: &str) -> Option<&syn::MetaNameValue> {
+        match self {
+            ArgEntry::Other(meta) => match &**meta {
+                Meta::NameValue(name_value) if name_value.path.is_ident(name) => Some(name_value),
+                _ => None,
+            },
+            ArgEntry::Group(_) => None,
+        }
+    }
+
+    fn to_token_stream(&self) -> TokenStream2 {
+        match self {
+            ArgEntry::Group(path) => quote!(@#path),
+            ArgEntry::Other(meta) => quote!(#meta),
+        }
+    }
+}
+
+fn parse_arg_entry(input: ParseStream) -> syn::Result<ArgEntry> {
+    if input.peek(Token![@]) {
+        input.parse::<Token![@]>()?;
+        return Ok(ArgEntry::Group(input.call(syn::Path::parse_mod_style)?));
+    }
+    parse_meta_entry(input).map(|meta| ArgEntry::Other(Box::new(meta)))
+}
+
+/// Name of the `macro_rules!` macro defined for the derive group `name`
+fn derive_group_macro_name(name: &Ident) -> Ident {
+    Ident::new(&format!("__macro_derive_group_{}", name), name.span())
+}
+
+/// Invocation of the first derive group `@name` in `args` of the attribute named `attribute`,
+/// passing the other arguments and `input` along, if there is a group
+///
+/// The group expands to the attribute again, with its entries in place of `@name`, so that
+/// groups nested in the group or given later are expanded in turn. The attribute is named
+/// through the path given by `crate = path`, for crates re-exporting this one.
+fn derive_group_call(
+    attribute: &str,
+    args: &TokenStream2,
+    input: &TokenStream2,
+) -> Option<syn::Result<TokenStream2>> {
+    let mut entries = parse_arg_entries(args)?;
+    let index = entries
+        .iter()
+        .position(|entry| matches!(entry, ArgEntry::Group(_)))?;
+    let mut group = match entries.remove(index) {
+        ArgEntry::Group(path) => path,
+        ArgEntry::Other(_) => unreachable!(),
+    };
+    if let Some(last) = group.segments.last_mut() {
+        last.ident = derive_group_macro_name(&last.ident);
+    }
+    let mut attribute_path = match crate_path(&entries) {
+        Ok(krate) => krate,
+        Err(err) => return Some(Err(err)),
+    };
+    let attribute = Ident::new(attribute, proc_macro2::Span::call_site());
+    attribute_path.segments.push(attribute.into());
+    let rest = entries.iter().map(ArgEntry::to_token_stream);
+    Some(Ok(quote! {
+        #group! {
+            { #attribute_path } { #(, #rest)* } #input
+        }
+    }))
+}
+
+/// The path of this crate given by the last `crate = path` in `entries`, or
+/// `::type_macro_derive_tricks`
+fn crate_path(entries: &[ArgEntry]) -> syn::Result<syn::Path> {
+    let krate = entries
+        .iter()
+        .rev()
+        .find_map(|entry| entry.name_value("crate"));
+    match krate {
+        Some(name_value) => parse_option_value(&name_value.value),
+        None => Ok(syn::parse_quote!(::type_macro_derive_tricks)),
+    }
+}
+
+/// Invocation of the macro given by `callback = path` in `args` of `macro_derive`, if any,
+/// with `input` carrying the attribute with the other arguments
+///
+/// The callback continues the expansion by emitting the item again, after rewriting the
+/// macro types it handles itself. The attribute is named like in [`derive_group_call`].
+fn callback_call(args: &TokenStream2, input: &TokenStream2) -> Option<syn::Result<TokenStream2>> {
+    let mut entries = parse_arg_entries(args)?;
+    let index = entries
+        .iter()
+        .position(|entry| entry.name_value("callback").is_some())?;
+    let entry = entries.remove(index);
+    let callback = match parse_option_value::<syn::Path>(&entry.name_value("callback")?.value) {
+        Ok(callback) => callback,
+        Err(err) => return Some(Err(err)),
+    };
+    let krate = match crate_path(&entries) {
+        Ok(krate) => krate,
+        Err(err) => return Some(Err(err)),
+    };
+    let rest = entries.iter().map(ArgEntry::to_token_stream);
+    Some(Ok(quote! {
+        #callback! {
+            #[#krate::macro_derive(#(#rest),*)]
+            #input
+        }
+    }))
+}
+
+/// The entries of `args`, or `None` if some of them are malformed
+fn parse_arg_entries(args: &TokenStream2) -> Option<Vec<ArgEntry>> {
+    let parser = |input: ParseStream| {
+        let mut errors = Vec::new();
+        let entries = parse_entries(input, parse_arg_entry, &mut errors)?;
+        combine_errors(errors)?;
+        Ok(entries)
+    };
+    // Malformed arguments are reported when they are parsed as options
+    syn::parse::Parser::parse2(parser, args.clone()).ok()
+}
+
+/// The error of a failed expansion together with the items of `input`
+///
+/// Keeping the items lets the rest of the crate, and IDEs, still resolve them, so the
+/// failure does not cascade into errors about missing types. The options of this crate are
+/// removed since nothing would consume them anymore. If `input` is not made of items, only
+/// the error is emitted, as the compiler would report the same syntax error again.
+pub fn recover(err: syn::Error, input: TokenStream2) -> TokenStream2 {
+    struct OptionAttrStripper;
+
+    impl VisitMut for OptionAttrStripper {
+        fn visit_attributes_mut(&mut self, attrs: &mut Vec<syn::Attribute>) {
+            attrs.retain(|attr| {
+                !attr.path().is_ident("macro_derive") && !attr.path().is_ident("macro_alias")
+            });
+        }
+    }
+
+    let error = err.to_compile_error();
+    match syn::parse::Parser::parse2(parse_items, input) {
+        Ok(mut items) => {
+            for item in &mut items {
+                OptionAttrStripper.visit_item_mut(item);
+            }
+            quote! {
+                #error
+                #(#items)*
+            }
+        }
+        Err(_) => error,
+    }
+}
+
+fn parse_items(input: ParseStream) -> syn::Result<Vec<syn::Item>> {
+    let mut items = Vec::new();
+    while !input.is_empty() {
+        items.push(input.parse()?);
+    }
+    Ok(items)
+}
+
+/// Rewrite every item of `items` with the expansion for its kind, reporting the errors of
+/// all items together
+fn expand_items(args: &MacroDeriveArgs, items: Vec<syn::Item>) -> syn::Result<TokenStream2> {
+    let mut expanded = TokenStream2::new();
+    let mut errors = Vec::new();
+    for item in items {
+        match expand_item(args, item) {
+            Ok(tokens) => expanded.extend(tokens),
+            Err(err) => errors.push(err),
+        }
+    }
+    combine_errors(errors)?;
+    Ok(expanded)
+}
+
+fn expand_item(args: &MacroDeriveArgs, item: syn::Item) -> syn::Result<TokenStream2> {
+    let (type_aliases, item) = match item {
+        syn::Item::Struct(_) | syn::Item::Enum(_) | syn::Item::Union(_) => {
+            let mut input: DeriveInput = syn::parse2(quote!(#item))?;
+            let mut aliases = MacroAliases::default();
+            rewrite_item(args, &mut input, &mut aliases)?;
+            let alias_vis = args.vis.as_ref().unwrap_or(&input.vis);
+            (emit_aliases(&aliases, alias_vis), quote!(#input))
+        }
+        syn::Item::Fn(item) => {
+            let (type_aliases, transformed) = expand_fn_macros(args, &item)?;
+            (type_aliases, quote!(#transformed))
+        }
+        syn::Item::Impl(item) => {
+            let (type_aliases, transformed) = expand_impl_macros(args, &item)?;
+            (type_aliases, quote!(#transformed))
+        }
+        syn::Item::Trait(item) => {
+            let (type_aliases, transformed) = expand_trait_macros(args, &item)?;
+            (type_aliases, quote!(#transformed))
+        }
+        syn::Item::Const(mut item) => {
+            let type_aliases =
+                expand_annotation_macros(args, "const", &item.ident, &item.vis, &mut item.ty)?;
+            (type_aliases, quote!(#item))
+        }
+        syn::Item::Static(mut item) => {
+            let type_aliases =
+                expand_annotation_macros(args, "static", &item.ident, &item.vis, &mut item.ty)?;
+            (type_aliases, quote!(#item))
+        }
+        syn::Item::Mod(mut item) => {
+            if let Some((_, items)) = &mut item.content {
+                let expanded = expand_items(args, std::mem::take(items))?;
+                items.push(syn::Item::Verbatim(expanded));
+            }
+            (Vec::new(), quote!(#item))
+        }
+        other => (Vec::new(), quote!(#other)),
+    };
+    Ok(quote! {
+        #(#type_aliases)*
+
+        #item
+    })
+}
+
+/// Parse the item `attribute` is applied to, explaining which kinds of items are supported
+/// if it is not a struct, enum or union
+fn parse_adt(attribute: &str, input: TokenStream2) -> syn::Result<DeriveInput> {
+    let item = match syn::parse2::<syn::Item>(input.clone()) {
+        Ok(item) => item,
+        Err(_) => return syn::parse2(input),
+    };
+    let kind = match &item {
+        syn::Item::Struct(_) | syn::Item::Enum(_) | syn::Item::Union(_) => {
+            return syn::parse2(input)
+        }
+        syn::Item::Fn(_) => "functions, use `#[macro_fn]` instead",
+        syn::Item::Impl(_) => "impl blocks, use `#[macro_impl]` instead",
+        syn::Item::Type(_) => "type aliases",
+        syn::Item::Trait(_) => "traits, use `#[macro_trait]` instead",
+        syn::Item::TraitAlias(_) => "trait aliases",
+        syn::Item::Const(_) => "constants, use `#[type_macro_expand]` instead",
+        syn::Item::Static(_) => "statics, use `#[type_macro_expand]` instead",
+        syn::Item::Mod(_) => "modules, use `#[macro_derive_mod]` instead",
+        _ => "this kind of item",
+    };
+    Err(syn::Error::new_spanned(
+        item,
+        format!(
+            "`#[{}]` can only be applied to structs, enums and unions, not to {}",
+            attribute, kind
+        ),
+    ))
+}
+
+/// Prefix of the generated type alias names
+const DEFAULT_ALIAS_PREFIX: &str = "__TypeMacroAlias";
+
+/// Parsed arguments of `#[macro_derive(...)]` and the other attributes
+#[derive(Clone)]
+pub struct MacroDeriveArgs {
+    derive_traits: Vec<syn::Path>,
+    vis: Option<syn::Visibility>,
+    prefix: String,
+    derive_position: DerivePosition,
+    cfg_derives: Vec<CfgDerive>,
+    extra_attrs: Vec<Meta>,
+    /// Attributes added to every generated type alias
+    alias_attrs: Vec<Meta>,
+    /// `repr(...)` attribute emitted right before the generated derive
+    repr: Option<Meta>,
+    /// Helper attributes of other derives whose macro types are replaced with aliases
+    rewritten_attrs: Vec<syn::Path>,
+    /// Also alias type macros nested in the arguments of other type macros
+    deep: bool,
+    /// Macro types replaced with the given expansion instead of an alias, keyed like aliases
+    inlined_types: Vec<(String, Type)>,
+    /// Macros given by `inline_rules(...)`, whose invocations are expanded in place
+    inline_macros: Vec<MacroRulesDef>,
+    /// Types describing the JSON schema of fields of the given macro types, keyed like aliases
+    schema_types: Vec<(String, Type)>,
+    /// Derive the generated names deterministically from the item and the invocation
+    stable_names: bool,
+    /// Derive the generated names only from the canonical rendering of the item and the
+    /// invocation, with a derivation which stays the same within a major version
+    stable_api: bool,
+    /// Name the generated items after the item and the field using them
+    readable_names: bool,
+    /// Module path recorded by bevy_reflect's `TypePath`, emitted as `#[type_path = "..."]`
+    /// after the derive; implies readable names unless `stable_api` is set
+    type_path: Option<syn::LitStr>,
+    /// Document the generated items instead of hiding them
+    document_aliases: bool,
+    /// Print the generated items to stderr
+    debug: bool,
+    /// Write the generated items to a file per item
+    dump: bool,
+    /// Fail if a macro type could not be replaced
+    strict: bool,
+    /// Generate a trait naming the rewritten types of the fields with macro types
+    expose_types: bool,
+    /// Generate a constant listing the fields with macro types and their original types
+    introspect: bool,
+    /// Traits every alias used by the fields is checked to implement, at the field using it
+    asserted_traits: Vec<syn::Path>,
+    /// Implement the standard traits by hand, bounding the field types instead of the
+    /// generic parameters
+    perfect_bounds: bool,
+    /// Predicates replacing the inferred bounds of the impls written by hand and of serde
+    /// derives
+    bounds: Option<Punctuated<syn::WherePredicate, Token![,]>>,
+    /// Bound serde derives by the field types instead of the generic parameters
+    serde_bounds: bool,
+    /// Mark the fields referring to aliases with lifetime parameters `#[serde(borrow)]`
+    serde_borrow: bool,
+    /// Predicates appended to the where clause of the item, whose macro types are aliased
+    where_predicates: Vec<syn::WherePredicate>,
+    /// Keep generic parameters no longer used by the rewritten fields with a `PhantomData`
+    /// field
+    phantom: bool,
+    /// Emit a copy of the item with the original field types for rustdoc, in place of the
+    /// rewritten item
+    doc_original: bool,
+    /// Name of a copy of the item with the original field types emitted next to it
+    keep_original: Option<Ident>,
+    /// Derive the standard traits named by a single identifier through their `::core` path
+    qualify_derives: bool,
+    /// Emit one `#[derive]` attribute per trait instead of a combined one
+    split_derives: bool,
+}
+
+/// Traits derived under a `cfg` predicate: `cfg(feature = "serde", serde::Serialize)`
+#[derive(Clone)]
+struct CfgDerive {
+    predicate: Meta,
+    derive_traits: Vec<syn::Path>,
+}
+
+impl Parse for CfgDerive {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let predicate = input.parse()?;
+        let mut derive_traits = Vec::new();
+        let mut errors = Vec::new();
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            derive_traits.extend(parse_entries(input, parse_derive_path, &mut errors)?);
+        }
+        combine_errors(errors)?;
+        dedup_derive_traits(&mut derive_traits);
+
+        Ok(CfgDerive {
+            predicate,
+            derive_traits,
+        })
+    }
+}
+
+/// Parse the entries of `inline(...)` or `schemars_with(...)`, keyed like aliases
+fn parse_type_mapping(list: &syn::MetaList) -> syn::Result<Vec<(String, Type)>> {
+    let entries = list.parse_args_with(Punctuated::<InlinedType, Token![,]>::parse_terminated)?;
+    Ok(entries
+        .into_iter()
+        .map(|entry| (alias_key(&Type::Macro(entry.invocation)), entry.expansion))
+        .collect())
+}
+
+/// Entry of `inline(...)` and `schemars_with(...)`: `Macro![x] = "Vec<String>"`
+struct InlinedType {
+    invocation: syn::TypeMacro,
+    expansion: Type,
+}
+
+impl Parse for InlinedType {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let invocation = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let expansion = if input.peek(syn::LitStr) {
+            input.parse::<syn::LitStr>()?.parse()?
+        } else {
+            input.parse()?
+        };
+        Ok(InlinedType {
+            invocation,
+            expansion,
+        })
+    }
+}
+
+/// Placement of the generated `#[derive]` among the attributes of the item
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DerivePosition {
+    First,
+    Last,
+    Preserve,
+}
+
+impl Parse for DerivePosition {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident: Ident = input.parse()?;
+        match ident.to_string().as_str() {
+            "first" => Ok(DerivePosition::First),
+            "last" => Ok(DerivePosition::Last),
+            "preserve" => Ok(DerivePosition::Preserve),
+            _ => Err(syn::Error::new_spanned(
+                ident,
+                "expected one of `first`, `last`, or `preserve`",
+            )),
+        }
+    }
+}
+
+impl Default for MacroDeriveArgs {
+    fn default() -> Self {
+        MacroDeriveArgs {
+            derive_traits: Vec::new(),
+            vis: None,
+            prefix: DEFAULT_ALIAS_PREFIX.to_string(),
+            derive_position: DerivePosition::First,
+            cfg_derives: Vec::new(),
+            extra_attrs: Vec::new(),
+            alias_attrs: Vec::new(),
+            repr: None,
+            rewritten_attrs: Vec::new(),
+            deep: false,
+            inlined_types: Vec::new(),
+            inline_macros: Vec::new(),
+            schema_types: Vec::new(),
+            stable_names: false,
+            stable_api: false,
+            readable_names: false,
+            type_path: None,
+            document_aliases: false,
+            debug: false,
+            dump: false,
+            strict: false,
+            expose_types: false,
+            introspect: false,
+            asserted_traits: Vec::new(),
+            perfect_bounds: false,
+            bounds: None,
+            serde_bounds: false,
+            serde_borrow: false,
+            where_predicates: Vec::new(),
+            phantom: false,
+            doc_original: false,
+            keep_original: None,
+            qualify_derives: false,
+            split_derives: false,
+        }
+    }
+}
+
+impl Parse for MacroDeriveArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = MacroDeriveArgs::default();
+        args.parse_into(input)?;
+        Ok(args)
+    }
+}
+
+impl MacroDeriveArgs {
+    /// Parse arguments on top of the already parsed ones, so that options given later
+    /// override earlier ones and trait lists are concatenated
+    ///
+    /// All invalid arguments are reported together rather than only the first one.
+    fn parse_into(&mut self, input: ParseStream) -> syn::Result<()> {
+        let mut errors = Vec::new();
+        for meta in parse_entries(input, parse_meta_entry, &mut errors)? {
+            if let Err(err) = self.apply_entry(meta) {
+                errors.push(err);
+            }
+        }
+
+        dedup_derive_traits(&mut self.derive_traits);
+        if self.stable_api && self.readable_names {
+            errors.push(syn::Error::new(
+                proc_macro2::Span::call_site(),
+                "`readable_names` depend on the order of the fields, which `stable_api` must \
+                 not; use one or the other",
+            ));
+        }
+        combine_errors(errors)
+    }
+
+    /// Apply a single parsed argument
+    fn apply_entry(&mut self, meta: Meta) -> syn::Result<()> {
+        match meta {
+            // Flags are lowercase, so they cannot be mistaken for derive traits
+            Meta::Path(path) if path.is_ident("debug") => self.debug = true,
+            Meta::Path(path) if path.is_ident("dump") => self.dump = true,
+            Meta::Path(path) if path.is_ident("strict") => self.strict = true,
+            // Bare trait paths are kept for backwards compatibility
+            Meta::Path(path) => self.derive_traits.push(path),
+            Meta::List(list) if list.path.is_ident("derive") => {
+                let mut errors = Vec::new();
+                let entries = list.parse_args_with(|input: ParseStream| {
+                    parse_entries(input, parse_derive_entry, &mut errors)
+                })?;
+                for (path, helper) in entries {
+                    self.derive_traits.push(path);
+                    self.extra_attrs.extend(helper);
+                }
+                combine_errors(errors)?;
+            }
+            // Derives cannot take arguments, so they go to the helper attribute of the trait
+            Meta::List(list) if is_trait_path(&list.path) => {
+                let (path, helper) = split_derive_arguments(list);
+                self.derive_traits.push(path);
+                self.extra_attrs.push(helper);
+            }
+            Meta::List(list) if list.path.is_ident("cfg") => {
+                self.cfg_derives.push(list.parse_args()?);
+            }
+            Meta::List(list) if list.path.is_ident("where_clause") => {
+                self.where_predicates.extend(list.parse_args_with(
+                    Punctuated::<syn::WherePredicate, Token![,]>::parse_terminated,
+                )?);
+            }
+            Meta::List(list) if list.path.is_ident("inline") => {
+                self.inlined_types.extend(parse_type_mapping(&list)?);
+            }
+            Meta::List(list) if list.path.is_ident("inline_rules") => {
+                // A later definition of a macro replaces the earlier one, as in a module
+                for def in list.parse_args_with(parse_macro_rules_defs)? {
+                    self.inline_macros.retain(|other| other.name != def.name);
+                    self.inline_macros.push(def);
+                }
+            }
+            Meta::List(list) if list.path.is_ident("schemars_with") => {
+                self.schema_types.extend(parse_type_mapping(&list)?);
+            }
+            Meta::List(list) if list.path.is_ident("rewrite_attrs") => {
+                self.rewritten_attrs.extend(
+                    list.parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)?,
+                );
+            }
+            Meta::List(list) if list.path.is_ident("assert_traits") => {
+                self.asserted_traits.extend(
+                    list.parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)?,
+                );
+            }
+            Meta::List(list) if list.path.is_ident("repr") => {
+                self.repr = Some(Meta::List(list));
+            }
+            Meta::List(list) if list.path.is_ident("attr") => {
+                self.extra_attrs
+                    .extend(list.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?);
+            }
+            Meta::List(list) if list.path.is_ident("alias_attrs") => {
+                self.alias_attrs
+                    .extend(list.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?);
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("vis") => {
+                self.vis = Some(parse_lit_str(&name_value.value)?.parse()?);
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("prefix") => {
+                let lit = parse_lit_str(&name_value.value)?;
+                if syn::parse_str::<Ident>(&lit.value()).is_err() {
+                    return Err(syn::Error::new_spanned(
+                        lit,
+                        "`prefix` must be a valid identifier",
+                    ));
+                }
+                self.prefix = lit.value();
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("derive_position") => {
+                self.derive_position = parse_option_value(&name_value.value)?;
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("deep") => {
+                self.deep = parse_option_value::<syn::LitBool>(&name_value.value)?.value;
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("stable_names") => {
+                self.stable_names = parse_option_value::<syn::LitBool>(&name_value.value)?.value;
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("stable_api") => {
+                self.stable_api = parse_option_value::<syn::LitBool>(&name_value.value)?.value;
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("readable_names") => {
+                self.readable_names = parse_option_value::<syn::LitBool>(&name_value.value)?.value;
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("type_path") => {
+                let lit = parse_lit_str(&name_value.value)?;
+                // bevy_reflect takes a module path without leading colons
+                lit.parse_with(syn::Path::parse_mod_style)
+                    .ok()
+                    .filter(|path| path.leading_colon.is_none())
+                    .ok_or_else(|| {
+                        syn::Error::new_spanned(
+                            &lit,
+                            "`type_path` must be a module path such as \"my_crate::module\"",
+                        )
+                    })?;
+                self.type_path = Some(lit);
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("document_aliases") => {
+                self.document_aliases =
+                    parse_option_value::<syn::LitBool>(&name_value.value)?.value;
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("bound") => {
+                let predicates = parse_lit_str(&name_value.value)?
+                    .parse_with(Punctuated::<syn::WherePredicate, Token![,]>::parse_terminated)?;
+                self.bounds
+                    .get_or_insert_with(Punctuated::new)
+                    .extend(predicates);
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("serde_bounds") => {
+                self.serde_bounds = parse_option_value::<syn::LitBool>(&name_value.value)?.value;
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("serde_borrow") => {
+                self.serde_borrow = parse_option_value::<syn::LitBool>(&name_value.value)?.value;
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("perfect_bounds") => {
+                self.perfect_bounds = parse_option_value::<syn::LitBool>(&name_value.value)?.value;
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("phantom") => {
+                self.phantom = parse_option_value::<syn::LitBool>(&name_value.value)?.value;
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("keep_original") => {
+                self.keep_original = Some(parse_option_value(&name_value.value)?);
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("doc_original") => {
+                self.doc_original = parse_option_value::<syn::LitBool>(&name_value.value)?.value;
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("qualify_derives") => {
+                self.qualify_derives = parse_option_value::<syn::LitBool>(&name_value.value)?.value;
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("split_derives") => {
+                self.split_derives = parse_option_value::<syn::LitBool>(&name_value.value)?.value;
+            }
+            // Only used to name the attribute when it is invoked again, see `crate_path`
+            Meta::NameValue(name_value) if name_value.path.is_ident("crate") => {
+                parse_option_value::<syn::Path>(&name_value.value)?;
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("callback") => {
+                return Err(syn::Error::new_spanned(
+                    name_value,
+                    "`callback` can only be given to the outer `macro_derive` attribute",
+                ))
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("introspect") => {
+                self.introspect = parse_option_value::<syn::LitBool>(&name_value.value)?.value;
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("expose_types") => {
+                self.expose_types = parse_option_value::<syn::LitBool>(&name_value.value)?.value;
+            }
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "unsupported `macro_derive` argument",
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Remove `#[macro_derive(...)]` attributes from `attrs`, merging them into `args`
+fn merge_stacked_attrs(
+    args: &mut MacroDeriveArgs,
+    attrs: &mut Vec<syn::Attribute>,
+) -> syn::Result<()> {
+    let mut retained = Vec::new();
+
+    for attr in attrs.drain(..) {
+        if attr.path().is_ident("cfg_attr") {
+            retained.extend(merge_conditional_attr(args, attr)?);
+        } else if !is_macro_derive_path(attr.path()) {
+            retained.push(attr);
+        } else if let Meta::Path(_) = attr.meta {
+            // `#[macro_derive]` without arguments adds nothing
+        } else {
+            attr.parse_args_with(|input: ParseStream| args.parse_into(input))?;
+        }
+    }
+
+    *attrs = retained;
+    Ok(())
+}
+
+fn is_macro_derive_path(path: &syn::Path) -> bool {
+    matches!(path.segments.last(), Some(segment) if segment.ident == "macro_derive")
+}
+
+/// Turn the `macro_derive(...)` entries of `#[cfg_attr(predicate, ...)]` into derives under
+/// `predicate`, returning the attribute with its other entries if there are any
+///
+/// The compiler expands `cfg_attr` on the annotated item before invoking the attribute, but
+/// not on the items of a module, where the entry would otherwise transform the item a second
+/// time. Options cannot be conditional, so only derives are accepted.
+fn merge_conditional_attr(
+    args: &mut MacroDeriveArgs,
+    attr: syn::Attribute,
+) -> syn::Result<Option<syn::Attribute>> {
+    let parsed = attr.parse_args_with(|input: ParseStream| {
+        let predicate: Meta = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let entries = Punctuated::<Meta, Token![,]>::parse_terminated(input)?;
+        Ok((predicate, entries))
+    });
+    let (predicate, entries) = match parsed {
+        Ok((predicate, entries))
+            if entries.iter().any(|meta| is_macro_derive_path(meta.path())) =>
+        {
+            (predicate, entries)
+        }
+        // Malformed attributes are left for the compiler to report
+        _ => return Ok(Some(attr)),
+    };
+
+    let mut remaining = Vec::new();
+    for entry in entries {
+        let list = match entry {
+            Meta::List(list) if is_macro_derive_path(&list.path) => list,
+            Meta::Path(path) if is_macro_derive_path(&path) => continue,
+            other if !is_macro_derive_path(other.path()) => {
+                remaining.push(other);
+                continue;
+            }
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "expected `macro_derive(...)`",
+                ))
+            }
+        };
+        let mut derive_traits = Vec::new();
+        let mut errors = Vec::new();
+        let metas = list.parse_args_with(|input: ParseStream| {
+            parse_entries(input, parse_meta_entry, &mut errors)
+        })?;
+        for meta in metas {
+            match meta {
+                Meta::Path(path) if is_trait_path(&path) => derive_traits.push(path),
+                Meta::List(list) if list.path.is_ident("derive") => derive_traits.extend(
+                    list.parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)?,
+                ),
+                other => errors.push(syn::Error::new_spanned(
+                    other,
+                    "only derives of `macro_derive` can be applied under `cfg_attr`; give \
+                     options to an unconditional `macro_derive`",
+                )),
+            }
+        }
+        combine_errors(errors)?;
+        dedup_derive_traits(&mut derive_traits);
+        args.cfg_derives.push(CfgDerive {
+            predicate: predicate.clone(),
+            derive_traits,
+        });
+    }
+
+    Ok(if remaining.is_empty() {
+        None
+    } else {
+        Some(syn::parse_quote!(#[cfg_attr(#predicate, #(#remaining),*)]))
+    })
+}
+
+/// Remove repeated traits, keeping the first occurrence
+///
+/// Repeating a trait would make the emitted `#[derive]` fail with a conflicting impl.
+fn dedup_derive_traits(derive_traits: &mut Vec<syn::Path>) {
+    let mut seen = Vec::new();
+    derive_traits.retain(|path| {
+        if seen.contains(path) {
+            emit_warning(
+                path.span(),
+                &format!("`{}` is derived more than once", quote!(#path)),
+            );
+            false
+        } else {
+            seen.push(path.clone());
+            true
+        }
+    });
+}
+
+/// Parse comma-separated entries with `parser`, collecting the errors of malformed entries
+/// into `errors` instead of stopping at the first one
+///
+/// `parser` must consume a malformed entry before failing.
+fn parse_entries<T>(
+    input: ParseStream,
+    parser: fn(ParseStream) -> syn::Result<T>,
+    errors: &mut Vec<syn::Error>,
+) -> syn::Result<Vec<T>> {
+    let mut entries = Vec::new();
+    while !input.is_empty() {
+        match parser(input) {
+            Ok(entry) => entries.push(entry),
+            Err(err) => errors.push(err),
+        }
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+        }
+    }
+    Ok(entries)
+}
+
+/// Combine `errors` into a single error reporting all of them
+fn combine_errors(errors: Vec<syn::Error>) -> syn::Result<()> {
+    let mut errors = errors.into_iter();
+    match errors.next() {
+        Some(mut combined) => {
+            combined.extend(errors);
+            Err(combined)
+        }
+        None => Ok(()),
+    }
+}
+
+/// Parse an entry of a `derive(...)` list, which may pass arguments to the helper attribute
+/// of the trait as in `derive_more::Display("{}", _0)`
+fn parse_derive_entry(input: ParseStream) -> syn::Result<(syn::Path, Option<Meta>)> {
+    let fork = input.fork();
+    if let Ok(Meta::List(list)) = parse_meta_entry(&fork) {
+        if is_trait_path(&list.path) {
+            input.advance_to(&fork);
+            let (path, helper) = split_derive_arguments(list);
+            return Ok((path, Some(helper)));
+        }
+    }
+    parse_derive_path(input).map(|path| (path, None))
+}
+
+/// Whether `path` names a trait rather than an option, which are lowercase
+fn is_trait_path(path: &syn::Path) -> bool {
+    matches!(path.segments.last(), Some(segment)
+        if segment.ident.to_string().starts_with(|c: char| c.is_ascii_uppercase()))
+}
+
+/// Split `Trait(args)` into the derived trait and the helper attribute `trait(args)` named
+/// after it in snake case, as derive_more names its helpers
+fn split_derive_arguments(list: syn::MetaList) -> (syn::Path, Meta) {
+    let ident = &list.path.segments.last().unwrap().ident;
+    let mut helper_name = String::new();
+    for (i, c) in ident.unraw().to_string().chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            helper_name.push('_');
+        }
+        helper_name.push(c.to_ascii_lowercase());
+    }
+    let helper = Meta::List(syn::MetaList {
+        path: Ident::new(&helper_name, ident.span()).into(),
+        delimiter: list.delimiter,
+        tokens: list.tokens,
+    });
+    (list.path, helper)
+}
+
+/// Parse an entry of a derive list, which must be a path with optional generic arguments
+fn parse_derive_path(input: ParseStream) -> syn::Result<syn::Path> {
+    match parse_meta_entry(input)? {
+        Meta::Path(path) => Ok(path),
+        other => Err(syn::Error::new_spanned(
+            &other,
+            format!("expected a derive trait path, found `{}`", quote!(#other)),
+        )),
+    }
+}
+
+/// Parse a single comma-separated argument, reporting the whole entry on failure
+fn parse_meta_entry(input: ParseStream) -> syn::Result<Meta> {
+    if input.peek(Token![@]) {
+        let tokens = parse_entry_tokens(input)?;
+        return Err(syn::Error::new_spanned(
+            &tokens,
+            format!(
+                "derive group `{}` can only be given to the outer `macro_derive` or \
+                 `macro_derive_mod` attribute",
+                tokens
+            ),
+        ));
+    }
+
+    let fork = input.fork();
+    if let Ok(meta) = fork.parse::<Meta>() {
+        if fork.is_empty() || fork.peek(Token![,]) {
+            input.advance_to(&fork);
+            return Ok(meta);
+        }
+    }
+
+    // `Meta` only accepts mod-style paths, so retry for paths with generic arguments
+    // such as `serde::Deserialize<'de>`
+    let fork = input.fork();
+    if let Ok(path) = fork.parse::<syn::Path>() {
+        if fork.is_empty() || fork.peek(Token![,]) {
+            input.advance_to(&fork);
+            return Ok(Meta::Path(path));
+        }
+    }
+
+    // Consume the malformed entry so the error spans all of its tokens
+    let tokens = parse_entry_tokens(input)?;
+    Err(syn::Error::new_spanned(
+        &tokens,
+        format!("expected a derive trait path, found `{}`", tokens),
+    ))
+}
+
+/// Consume the tokens of an entry up to the next comma
+fn parse_entry_tokens(input: ParseStream) -> syn::Result<TokenStream2> {
+    let mut tokens = TokenStream2::new();
+    while !input.is_empty() && !input.peek(Token![,]) {
+        tokens.extend(std::iter::once(input.parse::<proc_macro2::TokenTree>()?));
+    }
+    Ok(tokens)
+}
+
+/// Parse the value of a `key = value` option, given either bare or as a string literal
+fn parse_option_value<T: Parse>(expr: &syn::Expr) -> syn::Result<T> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(lit),
+            ..
+        }) => lit.parse(),
+        _ => syn::parse2(quote!(#expr)),
+    }
+}
+
+/// Extract the string literal of a `key = "value"` option
+fn parse_lit_str(expr: &syn::Expr) -> syn::Result<syn::LitStr> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(lit),
+            ..
+        }) => Ok(lit.clone()),
+        _ => Err(syn::Error::new_spanned(expr, "expected a string literal")),
+    }
+}
+
+fn impl_type_macro_derive_tricks(
+    args: &MacroDeriveArgs,
+    input: &DeriveInput,
+) -> syn::Result<TokenStream2> {
+    // Steps 1-2: Generate the type aliases and rewrite the item to use them
+    let (type_aliases, mut transformed_input) = expand_type_macros(args, input)?;
+
+    // Step 3: Add the derives to the transformed item
+    let manual_impls = insert_derive_attrs(args, &mut transformed_input)?;
+    let transformed_input = with_original_copies(args, input, &transformed_input);
+
+    // Step 4: Combine everything
+    let transformed = quote! {
+        #transformed_input
+
+        #(#manual_impls)*
+    };
+    dump_expansion(
+        args,
+        "macro_derive",
+        &input.ident,
+        &type_aliases,
+        &transformed,
+    );
+    Ok(quote! {
+        #(#type_aliases)*
+
+        #transformed
+    })
+}
+
+/// The transformed item, followed by the copy of it rendered by rustdoc instead with
+/// `doc_original` and by the copy named by `keep_original`
+fn with_original_copies(
+    args: &MacroDeriveArgs,
+    original: &DeriveInput,
+    transformed: &DeriveInput,
+) -> TokenStream2 {
+    let kept = args.keep_original.as_ref().map(|ident| {
+        let mut kept = doc_original_item(original, transformed);
+        kept.ident = ident.clone();
+        kept
+    });
+    if !args.doc_original {
+        return quote!(#transformed #kept);
+    }
+    let documented = doc_original_item(original, transformed);
+    quote! {
+        #[cfg(not(doc))]
+        #transformed
+        #[cfg(doc)]
+        #documented
+        #kept
+    }
+}
+
+/// Copy of `transformed` with the generics and field types written in `original`, so that
+/// the documentation shows the types the macros expand to rather than the hidden aliases
+///
+/// Items with macro types cannot be derived, so the copy has no derives and keeps only the
+/// attributes of the compiler, which need no derive to be understood. Fields added by the
+/// transformation, such as the one of `phantom`, are kept.
+fn doc_original_item(original: &DeriveInput, transformed: &DeriveInput) -> DeriveInput {
+    const BUILTIN: &[&str] = &[
+        "doc",
+        "cfg",
+        "allow",
+        "warn",
+        "deny",
+        "forbid",
+        "expect",
+        "must_use",
+        "non_exhaustive",
+        "deprecated",
+        "repr",
+    ];
+    let is_builtin = |attr: &syn::Attribute| BUILTIN.iter().any(|name| attr.path().is_ident(name));
+
+    let mut documented = transformed.clone();
+    documented.generics = original.generics.clone();
+    documented.attrs.retain(is_builtin);
+    if let Data::Enum(data_enum) = &mut documented.data {
+        for variant in data_enum.variants.iter_mut() {
+            variant.attrs.retain(is_builtin);
+        }
+    }
+    let original_fields = all_fields(&original.data);
+    for (index, field) in all_fields_mut(&mut documented.data).into_iter().enumerate() {
+        if let Some(original) = original_fields.get(index) {
+            field.ty = original.ty.clone();
+        }
+        field.attrs.retain(is_builtin);
+    }
+    documented
+}
+
+/// Add the derives and attributes requested by `args` to the transformed item, returning
+/// the impls written by hand in place of derives
+fn insert_derive_attrs(
+    args: &MacroDeriveArgs,
+    transformed_input: &mut DeriveInput,
+) -> syn::Result<Vec<TokenStream2>> {
+    // Merge existing `#[derive]` attributes into the generated one, since they would
+    // otherwise be applied to the item before it is transformed
+    let mut derive_traits = args.derive_traits.clone();
+    let (existing_traits, existing_position) = take_derive_attrs(&mut transformed_input.attrs)?;
+    derive_traits.extend(existing_traits);
+    dedup_derive_traits(&mut derive_traits);
+    let manual_impls = if args.perfect_bounds {
+        perfect_derive_impls(&mut derive_traits, transformed_input, args.bounds.as_ref())
+    } else {
+        Vec::new()
+    };
+
+    // Insert the derive attributes among the retained attributes, each followed by the
+    // `bound` helper of the serde derives it contains
+    let mut derive_attrs: Vec<syn::Attribute> = Vec::new();
+    // Layout derives such as zerocopy's and bytemuck's check the representation
+    if let Some(repr) = &args.repr {
+        derive_attrs.push(syn::parse_quote!(#[#repr]));
+    }
+    let mut bounds_used = !manual_impls.is_empty();
+    let mut serde_derived = false;
+    if args.qualify_derives {
+        derive_traits = derive_traits.iter().map(qualified_derive_path).collect();
+    }
+    if !derive_traits.is_empty() {
+        for group in derive_groups(args, &derive_traits) {
+            derive_attrs.push(syn::parse_quote!(#[derive(#(#group),*)]));
+        }
+        serde_derived |= derives_serde(&derive_traits);
+        if let Some(meta) = serde_bound_meta(args, transformed_input, &derive_traits) {
+            derive_attrs.push(syn::parse_quote!(#[#meta]));
+        }
+    }
+    for cfg_derive in args.cfg_derives.iter() {
+        if cfg_derive.derive_traits.is_empty() {
+            continue;
+        }
+        let predicate = &cfg_derive.predicate;
+        let cfg_traits = &if args.qualify_derives {
+            cfg_derive
+                .derive_traits
+                .iter()
+                .map(qualified_derive_path)
+                .collect()
+        } else {
+            cfg_derive.derive_traits.clone()
+        };
+        for group in derive_groups(args, cfg_traits) {
+            derive_attrs.push(syn::parse_quote! {
+                #[cfg_attr(#predicate, derive(#(#group),*))]
+            });
+        }
+        serde_derived |= derives_serde(cfg_traits);
+        if let Some(meta) = serde_bound_meta(args, transformed_input, cfg_traits) {
+            derive_attrs.push(syn::parse_quote!(#[cfg_attr(#predicate, #meta)]));
+        }
+    }
+    bounds_used |= serde_derived;
+    if args.bounds.is_some() && !bounds_used {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "`bound` only applies to the impls written by `perfect_bounds = true` and to \
+             serde derives",
+        ));
+    }
+    if args.serde_bounds && !serde_derived {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "`serde_bounds` requires deriving `Serialize` or `Deserialize`",
+        ));
+    }
+    for meta in args.extra_attrs.iter() {
+        derive_attrs.push(syn::parse_quote!(#[#meta]));
+    }
+    if let Some(type_path) = &args.type_path {
+        derive_attrs.push(syn::parse_quote!(#[type_path = #type_path]));
+    }
+
+    let position = match args.derive_position {
+        DerivePosition::First => leading_passive_attrs(&transformed_input.attrs),
+        DerivePosition::Last => transformed_input.attrs.len(),
+        DerivePosition::Preserve => existing_position.unwrap_or(0),
+    };
+    transformed_input
+        .attrs
+        .splice(position..position, derive_attrs);
+    Ok(manual_impls)
+}
+
+/// Number of doc comments and lint attributes at the start of `attrs`
+///
+/// These describe the item rather than feed a derive, so `derive_position = first` keeps them
+/// above the generated derive as they would be written by hand. Attributes after the first
+/// other attribute keep their place, so their order relative to each other never changes.
+fn leading_passive_attrs(attrs: &[syn::Attribute]) -> usize {
+    const PASSIVE: &[&str] = &[
+        "doc",
+        "allow",
+        "warn",
+        "deny",
+        "forbid",
+        "expect",
+        "must_use",
+        "non_exhaustive",
+    ];
+    attrs
+        .iter()
+        .take_while(|attr| PASSIVE.iter().any(|name| attr.path().is_ident(name)))
+        .count()
+}
+
+/// Whether `derive_traits` contains the serde derive `name`, written bare or as a path into
+/// `serde`
+fn derives_serde_trait(derive_traits: &[syn::Path], name: &str) -> bool {
+    derive_traits.iter().any(|path| {
+        let is_serde = path.segments.len() == 1 || path.segments[0].ident == "serde";
+        is_serde && path.segments.last().unwrap().ident == name
+    })
+}
+
+/// Whether `derive_traits` contains a serde derive
+fn derives_serde(derive_traits: &[syn::Path]) -> bool {
+    derives_serde_trait(derive_traits, "Serialize")
+        || derives_serde_trait(derive_traits, "Deserialize")
+}
+
+/// `serde(bound ...)` helper for the serde derives among `derive_traits`, if any is needed
+///
+/// The helper passes the `bound` option as is, or with `serde_bounds` bounds the field types
+/// of `input` as `perfect_bounds` does. Without either, serde derives infer their bounds.
+fn serde_bound_meta(
+    args: &MacroDeriveArgs,
+    input: &DeriveInput,
+    derive_traits: &[syn::Path],
+) -> Option<Meta> {
+    if !derives_serde(derive_traits) {
+        return None;
+    }
+    if let Some(bounds) = &args.bounds {
+        let bounds = quote!(#bounds).to_string();
+        return Some(syn::parse_quote!(serde(bound = #bounds)));
+    }
+    if !args.serde_bounds {
+        return None;
+    }
+
+    let mut entries = Vec::new();
+    if derives_serde_trait(derive_traits, "Serialize") {
+        let bounds = serde_field_bounds(input, "serialize", quote!(::serde::Serialize));
+        entries.push(quote!(serialize = #bounds));
+    }
+    if derives_serde_trait(derive_traits, "Deserialize") {
+        let bounds = serde_field_bounds(input, "deserialize", quote!(::serde::Deserialize<'de>));
+        entries.push(quote!(deserialize = #bounds));
+    }
+    Some(syn::parse_quote!(serde(bound(#(#entries),*))))
+}
+
+/// Predicates bounding the field types of `input` which mention generic parameters by
+/// `trait_path`, for `serde_bounds`
+///
+/// Like serde, fields skipped in `direction` or handled by custom functions are not bounded.
+fn serde_field_bounds(input: &DeriveInput, direction: &str, trait_path: TokenStream2) -> String {
+    let mut bounded = HashSet::new();
+    let replacing = match direction {
+        "serialize" => ["skip", "with", "skip_serializing", "serialize_with"],
+        _ => ["skip", "with", "skip_deserializing", "deserialize_with"],
+    };
+    let predicates: Vec<TokenStream2> = all_fields(&input.data)
+        .into_iter()
+        .filter(|field| !has_serde_option(&field.attrs, &replacing))
+        .map(|field| &field.ty)
+        .filter(|ty| !get_used_generic_params(ty, &input.generics).is_empty())
+        .filter(|ty| bounded.insert(quote!(#ty).to_string()))
+        .map(|ty| quote!(#ty: #trait_path))
+        .collect();
+    quote!(#(#predicates),*).to_string()
+}
+
+/// Whether the `#[serde(...)]` attributes in `attrs` contain one of the options `names`
+fn has_serde_option(attrs: &[syn::Attribute], names: &[&str]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("serde"))
+        .any(|attr| {
+            let mut found = false;
+            // Malformed attributes are left for serde to report
+            let _ = attr.parse_nested_meta(|meta| {
+                found |= names.iter().any(|&name| meta.path.is_ident(name));
+                if meta.input.peek(Token![=]) {
+                    meta.value()?.parse::<syn::Expr>()?;
+                } else if meta.input.peek(syn::token::Paren) {
+                    meta.input.parse::<proc_macro2::Group>()?;
+                }
+                Ok(())
+            });
+            found
+        })
+}
+
+/// Remove the derives of standard traits from `derive_traits` and implement them by hand for
+/// `input`, for `perfect_bounds`
+///
+/// The impls are bounded by the field types mentioning generic parameters, as in
+/// `where Alias<T>: Clone`, rather than by every parameter as `#[derive]` does, unless
+/// `bounds` are given in their place. `Default` is only implemented by hand for structs, and
+/// unions and empty enums keep all their derives.
+fn perfect_derive_impls(
+    derive_traits: &mut Vec<syn::Path>,
+    input: &DeriveInput,
+    bounds: Option<&Punctuated<syn::WherePredicate, Token![,]>>,
+) -> Vec<TokenStream2> {
+    // The impls match on the value, which unions and empty enums do not allow
+    match &input.data {
+        Data::Union(_) => return Vec::new(),
+        Data::Enum(data_enum) if data_enum.variants.is_empty() => return Vec::new(),
+        _ => {}
+    }
+    let mut impls = Vec::new();
+    derive_traits.retain(|path| match perfect_trait(path) {
+        Some("Default") if !matches!(input.data, Data::Struct(_)) => true,
+        Some(name) => {
+            impls.push(perfect_impl(name, input, bounds));
+            false
+        }
+        None => true,
+    });
+    impls
+}
+
+/// The lists of traits derived by each emitted `#[derive]` attribute: one per trait with
+/// `split_derives`, or all of them together
+fn derive_groups<'a>(
+    args: &MacroDeriveArgs,
+    derive_traits: &'a [syn::Path],
+) -> Vec<&'a [syn::Path]> {
+    if args.split_derives {
+        derive_traits.chunks(1).collect()
+    } else {
+        vec![derive_traits]
+    }
+}
+
+/// `path` with the derive macros of the standard library named by a single identifier replaced
+/// by their `::core` path, so that items of the same name in scope are not derived instead
+fn qualified_derive_path(path: &syn::Path) -> syn::Path {
+    let ident = match path.get_ident() {
+        Some(ident) if path.leading_colon.is_none() => ident,
+        _ => return path.clone(),
+    };
+    let module = match ident.to_string().as_str() {
+        "Clone" => quote!(clone),
+        "Copy" => quote!(marker),
+        "Debug" => quote!(fmt),
+        "Default" => quote!(default),
+        "PartialEq" | "Eq" | "PartialOrd" | "Ord" => quote!(cmp),
+        "Hash" => quote!(hash),
+        _ => return path.clone(),
+    };
+    syn::parse_quote_spanned!(ident.span()=> ::core::#module::#ident)
+}
+
+/// Name of the standard trait `path` refers to, if `perfect_bounds` implements it
+fn perfect_trait(path: &syn::Path) -> Option<&'static str> {
+    let first = path.segments.first()?;
+    let is_std = path.segments.len() == 1 || first.ident == "std" || first.ident == "core";
+    let last = path.segments.last()?;
+    if !is_std || !last.arguments.is_none() {
+        return None;
+    }
+    ["Clone", "Debug", "PartialEq", "Eq", "Hash", "Default"]
+        .into_iter()
+        .find(|name| last.ident == name)
+}
+
+/// Implement the standard trait `name` for `input`, bounding the types of its fields or by
+/// `bounds` if given
+fn perfect_impl(
+    name: &str,
+    input: &DeriveInput,
+    bounds: Option<&Punctuated<syn::WherePredicate, Token![,]>>,
+) -> TokenStream2 {
+    let trait_path = match name {
+        "Clone" => quote!(::core::clone::Clone),
+        "Debug" => quote!(::core::fmt::Debug),
+        "PartialEq" => quote!(::core::cmp::PartialEq),
+        "Eq" => quote!(::core::cmp::Eq),
+        "Hash" => quote!(::core::hash::Hash),
+        _ => quote!(::core::default::Default),
+    };
+
+    let mut generics = input.generics.clone();
+    let mut bounded = HashSet::new();
+    if let Some(bounds) = bounds {
+        generics
+            .make_where_clause()
+            .predicates
+            .extend(bounds.iter().cloned());
+    }
+    for field in all_fields(&input.data) {
+        let ty = &field.ty;
+        if bounds.is_none()
+            && !get_used_generic_params(ty, &input.generics).is_empty()
+            && bounded.insert(quote!(#ty).to_string())
+        {
+            generics
+                .make_where_clause()
+                .predicates
+                .push(syn::parse_quote!(#ty: #trait_path));
+        }
+    }
+
+    let variants = adt_variants(input);
+    let body = match name {
+        "Clone" => {
+            let arms = variants.iter().map(|(path, _, fields)| {
+                let (pattern, bindings) = fields_pattern(path, fields, "self");
+                let values = bindings
+                    .iter()
+                    .map(|binding| quote!(::core::clone::Clone::clone(#binding)));
+                let constructed = fields_constructor(path, fields, values);
+                quote!(#pattern => #constructed,)
+            });
+            quote! {
+                #[inline]
+                fn clone(&self) -> Self {
+                    match self {
+                        #(#arms)*
+                    }
+                }
+            }
+        }
+        "Debug" => {
+            let arms = variants.iter().map(|(path, name, fields)| {
+                let (pattern, bindings) = fields_pattern(path, fields, "self");
+                let written = match fields {
+                    syn::Fields::Named(named) => {
+                        let names = named
+                            .named
+                            .iter()
+                            .map(|field| field.ident.as_ref().unwrap().unraw().to_string());
+                        quote!(f.debug_struct(#name)#(.field(#names, #bindings))*.finish())
+                    }
+                    syn::Fields::Unnamed(_) => {
+                        quote!(f.debug_tuple(#name)#(.field(#bindings))*.finish())
+                    }
+                    syn::Fields::Unit => quote!(f.write_str(#name)),
+                };
+                quote!(#pattern => #written,)
+            });
+            quote! {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    match self {
+                        #(#arms)*
+                    }
+                }
+            }
+        }
+        "PartialEq" => {
+            let arms = variants.iter().map(|(path, _, fields)| {
+                let (pattern, bindings) = fields_pattern(path, fields, "self");
+                let (other_pattern, other_bindings) = fields_pattern(path, fields, "other");
+                quote! {
+                    (#pattern, #other_pattern) => true
+                        #(&& ::core::cmp::PartialEq::eq(#bindings, #other_bindings))*,
+                }
+            });
+            // Different variants are never equal
+            let fallback = if variants.len() > 1 {
+                quote!(_ => false,)
+            } else {
+                quote!()
+            };
+            quote! {
+                #[inline]
+                fn eq(&self, other: &Self) -> bool {
+                    match (self, other) {
+                        #(#arms)*
+                        #fallback
+                    }
+                }
+            }
+        }
+        "Eq" => quote!(),
+        "Hash" => {
+            let arms = variants.iter().map(|(path, _, fields)| {
+                let (pattern, bindings) = fields_pattern(path, fields, "self");
+                quote!(#pattern => { #(::core::hash::Hash::hash(#bindings, state);)* })
+            });
+            let discriminant = match input.data {
+                Data::Enum(_) => {
+                    quote!(::core::hash::Hash::hash(&::core::mem::discriminant(self), state);)
+                }
+                _ => quote!(),
+            };
+            quote! {
+                fn hash<__H: ::core::hash::Hasher>(&self, state: &mut __H) {
+                    #discriminant
+                    match self {
+                        #(#arms)*
+                    }
+                }
+            }
+        }
+        _ => {
+            let (path, _, fields) = &variants[0];
+            let values = fields
+                .iter()
+                .map(|_| quote!(::core::default::Default::default()));
+            let constructed = fields_constructor(path, fields, values);
+            quote! {
+                #[inline]
+                fn default() -> Self {
+                    #constructed
+                }
+            }
+        }
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    // `Eq` has no method checking the fields, and overriding the hidden one of the standard
+    // derive is being phased out, so the fields the bounds leave out are checked beside it
+    let checks = if name == "Eq" {
+        let types = all_fields(&input.data).into_iter().map(|field| &field.ty);
+        quote! {
+            const _: () = {
+                struct __AssertParamIsEq<__T: ::core::cmp::Eq + ?::core::marker::Sized>(
+                    ::core::marker::PhantomData<__T>,
+                );
+                impl #impl_generics #ident #ty_generics #where_clause {
+                    #[allow(dead_code)]
+                    fn __macro_derive_assert_fields_are_eq(&self) {
+                        #(let _: __AssertParamIsEq<#types>;)*
+                    }
+                }
+            };
+        }
+    } else {
+        quote!()
+    };
+    quote! {
+        #[automatically_derived]
+        impl #impl_generics #trait_path for #ident #ty_generics #where_clause {
+            #body
+        }
+        #checks
+    }
+}
+
+/// Path, name and fields of the struct or of every variant of the enum `input`
+fn adt_variants(input: &DeriveInput) -> Vec<(TokenStream2, String, &syn::Fields)> {
+    match &input.data {
+        Data::Struct(data_struct) => {
+            vec![(
+                quote!(Self),
+                input.ident.unraw().to_string(),
+                &data_struct.fields,
+            )]
+        }
+        Data::Enum(data_enum) => data_enum
+            .variants
+            .iter()
+            .map(|variant| {
+                let ident = &variant.ident;
+                (
+                    quote!(Self::#ident),
+                    ident.unraw().to_string(),
+                    &variant.fields,
+                )
+            })
+            .collect(),
+        Data::Union(_) => Vec::new(),
+    }
+}
+
+/// Pattern matching `fields` of the constructor `path`, binding them to identifiers
+/// starting with `prefix`, which are returned in field order
+fn fields_pattern(
+    path: &TokenStream2,
+    fields: &syn::Fields,
+    prefix: &str,
+) -> (TokenStream2, Vec<Ident>) {
+    let bindings: Vec<Ident> = (0..fields.len())
+        .map(|index| {
+            Ident::new(
+                &format!("__{}_{}", prefix, index),
+                proc_macro2::Span::call_site(),
+            )
+        })
+        .collect();
+    let pattern = fields_constructor(
+        path,
+        fields,
+        bindings.iter().map(|binding| quote!(#binding)),
+    );
+    (pattern, bindings)
+}
+
+/// Expression or pattern of the constructor `path` with `values` for `fields`
+fn fields_constructor(
+    path: &TokenStream2,
+    fields: &syn::Fields,
+    values: impl Iterator<Item = TokenStream2>,
+) -> TokenStream2 {
+    match fields {
+        syn::Fields::Named(named) => {
+            let names = named.named.iter().map(|field| &field.ident);
+            quote!(#path { #(#names: #values),* })
+        }
+        syn::Fields::Unnamed(_) => quote!(#path(#(#values),*)),
+        syn::Fields::Unit => quote!(#path),
+    }
+}
+
+fn impl_macro_derive_mod(
+    args: &MacroDeriveArgs,
+    mut module: syn::ItemMod,
+) -> syn::Result<TokenStream2> {
+    // Every item would be copied under the same name
+    if let Some(ident) = &args.keep_original {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "`keep_original` names a single item, give it to the items of the module instead",
+        ));
+    }
+    let items = match &mut module.content {
+        Some((_, items)) => items,
+        None => {
+            return Err(syn::Error::new_spanned(
+                &module,
+                "`#[macro_derive_mod]` requires an inline module: `mod name { ... }`",
+            ))
+        }
+    };
+
+    // Step 1: Rewrite every item, collecting its macro types into one set of aliases
+    let mut aliases = MacroAliases {
+        reserved_names: items
+            .iter()
+            .filter_map(item_ident)
+            .map(Ident::to_string)
+            .collect(),
+        ..MacroAliases::default()
+    };
+    for item in items.iter_mut() {
+        let mut input: DeriveInput = match item {
+            syn::Item::Struct(item) => item.clone().into(),
+            syn::Item::Enum(item) => item.clone().into(),
+            syn::Item::Union(item) => item.clone().into(),
+            _ => continue,
+        };
+        let mut item_args = args.clone();
+        merge_stacked_attrs(&mut item_args, &mut input.attrs)?;
+        let original = input.clone();
+        rewrite_item(&item_args, &mut input, &mut aliases)?;
+        let manual_impls = insert_derive_attrs(&item_args, &mut input)?;
+        let input = with_original_copies(&item_args, &original, &input);
+        *item = syn::Item::Verbatim(quote!(#input #(#manual_impls)*));
+    }
+
+    // Step 2: Generate the shared aliases
+    let alias_vis = args.vis.clone().unwrap_or(syn::Visibility::Inherited);
+    let type_aliases = emit_aliases(&aliases, &alias_vis);
+    items.splice(0..0, type_aliases.iter().cloned().map(syn::Item::Verbatim));
+
+    dump_expansion(args, "macro_derive_mod", &module.ident, &[], &module);
+    Ok(quote!(#module))
+}
+
+/// Name an item declares in its module, if any
+fn item_ident(item: &syn::Item) -> Option<&Ident> {
+    match item {
+        syn::Item::Const(item) => Some(&item.ident),
+        syn::Item::Enum(item) => Some(&item.ident),
+        syn::Item::Fn(item) => Some(&item.sig.ident),
+        syn::Item::Mod(item) => Some(&item.ident),
+        syn::Item::Static(item) => Some(&item.ident),
+        syn::Item::Struct(item) => Some(&item.ident),
+        syn::Item::Trait(item) => Some(&item.ident),
+        syn::Item::Type(item) => Some(&item.ident),
+        syn::Item::Union(item) => Some(&item.ident),
+        _ => None,
+    }
+}
+
+/// Emit a compiler warning at `span`
+///
+/// Warnings require the unstable `proc_macro::Diagnostic` API, so they are only emitted with
+/// the `nightly-diagnostics` feature and are silently dropped otherwise.
+#[cfg(feature = "nightly-diagnostics")]
+fn emit_warning(span: proc_macro2::Span, message: &str) {
+    // The compiler API is unavailable in unit tests
+    if proc_macro::is_available() {
+        proc_macro::Diagnostic::spanned(span.unwrap(), proc_macro::Level::Warning, message).emit();
+    }
+}
+
+#[cfg(not(feature = "nightly-diagnostics"))]
+fn emit_warning(_span: proc_macro2::Span, _message: &str) {}
+
+/// Environment variable enabling `debug` for every invocation
+const DEBUG_ENV_VAR: &str = "TYPE_MACRO_DERIVE_DEBUG";
+
+/// Environment variable enabling `dump` for every invocation, optionally naming the directory
+/// to write to
+const DUMP_ENV_VAR: &str = "TYPE_MACRO_DERIVE_DUMP";
+
+/// Read the environment variable `name`, telling the compiler to expand again when it changes
+///
+/// Tracking requires the unstable `proc_macro::tracked` API, so it is only done with the
+/// `nightly-tracked-env` feature; otherwise a change of the variable takes effect when the
+/// crate is rebuilt for another reason.
+#[cfg(feature = "nightly-tracked-env")]
+fn tracked_env_var(name: &str) -> Option<String> {
+    // The compiler API is unavailable in unit tests
+    if proc_macro::is_available() {
+        proc_macro::tracked::env_var(name).ok()
+    } else {
+        std::env::var(name).ok()
+    }
+}
+
+#[cfg(not(feature = "nightly-tracked-env"))]
+fn tracked_env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+/// Print the generated items to stderr if `debug` is set in `args` or the environment, and
+/// write them to a file if `dump` is
+fn dump_expansion(
+    args: &MacroDeriveArgs,
+    attribute: &str,
+    subject: &dyn template_quote::ToTokens,
+    type_aliases: &[TokenStream2],
+    transformed: &dyn template_quote::ToTokens,
+) {
+    let env_flag = |name: &str| match tracked_env_var(name) {
+        Some(value) if !value.is_empty() && value != "0" => Some(value),
+        _ => None,
+    };
+    let subject = subject.to_token_stream();
+    let subject_name = render_source_tokens(&subject);
+    let dump_env = env_flag(DUMP_ENV_VAR);
+    if args.dump || dump_env.is_some() {
+        let mut dir = match dump_env.as_deref() {
+            Some(dir) if dir != "1" => std::path::PathBuf::from(dir),
+            _ => default_dump_dir(),
+        };
+        dir.push(std::env::var("CARGO_CRATE_NAME").unwrap_or_else(|_| "crate".to_string()));
+        if let Some(source) = subject
+            .into_iter()
+            .next()
+            .and_then(|token| source_dir(token.span()))
+        {
+            dir.push(source);
+        }
+        let expansion = quote!(#(#type_aliases)* #transformed);
+        if let Err(err) = write_dump(&dir, attribute, &subject_name, &expansion) {
+            eprintln!(
+                "warning: could not write the expansion of `{}` to {}: {}",
+                subject_name,
+                dir.display(),
+                err
+            );
+        }
+    }
+    if !args.debug && env_flag(DEBUG_ENV_VAR).is_none() {
+        return;
+    }
+
+    eprintln!("#[{}] expansion of `{}`:", attribute, subject_name);
+    for alias in type_aliases {
+        eprintln!("{}", alias);
+    }
+    eprintln!("{}", transformed.to_token_stream());
+}
+
+/// `type_macro_derive` in the `OUT_DIR` of the crate being compiled if it has a build script,
+/// in the target directory if `CARGO_TARGET_DIR` names it, or else in the temporary directory
+///
+/// The target directory of a package in a workspace is not known otherwise, and guessing it
+/// would litter the package with a stray `target` directory.
+fn default_dump_dir() -> std::path::PathBuf {
+    let mut dir = std::env::var_os("OUT_DIR")
+        .or_else(|| std::env::var_os("CARGO_TARGET_DIR"))
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    dir.push("type_macro_derive");
+    dir
+}
+
+/// The source file containing `span` without its extension, as in `src/shapes/circle`, which
+/// stands for the module path the compiler does not tell macros
+///
+/// Returns `None` where the file is unknown, which is the case before Rust 1.88 and without the
+/// `dump-source-paths` feature, or lies outside the package.
+#[cfg(feature = "dump-source-paths")]
+fn source_dir(span: proc_macro2::Span) -> Option<std::path::PathBuf> {
+    use std::path::{Component, Path};
+
+    let file = span.file();
+    let path = Path::new(&file);
+    let is_relative = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    match path.extension() {
+        Some(extension) if extension == "rs" && is_relative => Some(path.with_extension("")),
+        _ => None,
+    }
+}
+
+#[cfg(not(feature = "dump-source-paths"))]
+fn source_dir(_span: proc_macro2::Span) -> Option<std::path::PathBuf> {
+    None
+}
+
+/// Write `expansion` of the item `subject`, formatted, to a file in `dir` named after the item,
+/// replacing the file written by an earlier compilation
+fn write_dump(
+    dir: &std::path::Path,
+    attribute: &str,
+    subject: &str,
+    expansion: &TokenStream2,
+) -> std::io::Result<std::path::PathBuf> {
+    let formatted = match syn::parse2::<syn::File>(expansion.clone()) {
+        Ok(file) => prettyplease::unparse(&file),
+        Err(_) => expansion.to_string(),
+    };
+    let contents = format!(
+        "// Expansion of `#[{}]` on `{}`\n\n{}",
+        attribute, subject, formatted
+    );
+    // Types such as the `Wrapper<T>` of `macro_impl` are not valid file names as written
+    let file_name: String = subject
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}.rs", file_name.trim_end_matches('_')));
+    std::fs::write(&path, contents)?;
+    Ok(path)
+}
+
+fn impl_type_macro_expand(
+    args: &MacroDeriveArgs,
+    input: &DeriveInput,
+) -> syn::Result<TokenStream2> {
+    reject_derive_options("type_macro_expand", args)?;
+
+    let (type_aliases, transformed_input) = expand_type_macros(args, input)?;
+    let transformed_input = with_original_copies(args, input, &transformed_input);
+    dump_expansion(
+        args,
+        "type_macro_expand",
+        &input.ident,
+        &type_aliases,
+        &transformed_input,
+    );
+    Ok(quote! {
+        #(#type_aliases)*
+
+        #transformed_input
+    })
+}
+
+fn impl_const_macro_expand(
+    args: &MacroDeriveArgs,
+    mut item: syn::ItemConst,
+) -> syn::Result<TokenStream2> {
+    reject_derive_options("type_macro_expand", args)?;
+    reject_adt_options("type_macro_expand", args)?;
+
+    let ident = item.ident.clone();
+    let type_aliases = expand_annotation_macros(args, "const", &ident, &item.vis, &mut item.ty)?;
+    dump_expansion(args, "type_macro_expand", &ident, &type_aliases, &item);
+    Ok(quote! {
+        #(#type_aliases)*
+
+        #item
+    })
+}
+
+fn impl_static_macro_expand(
+    args: &MacroDeriveArgs,
+    mut item: syn::ItemStatic,
+) -> syn::Result<TokenStream2> {
+    reject_derive_options("type_macro_expand", args)?;
+    reject_adt_options("type_macro_expand", args)?;
+
+    let ident = item.ident.clone();
+    let type_aliases = expand_annotation_macros(args, "static", &ident, &item.vis, &mut item.ty)?;
+    dump_expansion(args, "type_macro_expand", &ident, &type_aliases, &item);
+    Ok(quote! {
+        #(#type_aliases)*
+
+        #item
+    })
+}
+
+fn impl_macro_impl(args: &MacroDeriveArgs, input: &syn::ItemImpl) -> syn::Result<TokenStream2> {
+    reject_derive_options("macro_impl", args)?;
+    reject_adt_options("macro_impl", args)?;
+
+    let (type_aliases, transformed_input) = expand_impl_macros(args, input)?;
+    let self_ty = &input.self_ty;
+    let trait_path = input.trait_.as_ref().map(|(_, path, _)| quote!(#path for));
+    dump_expansion(
+        args,
+        "macro_impl",
+        &quote!(#trait_path #self_ty),
+        &type_aliases,
+        &transformed_input,
+    );
+    Ok(quote! {
+        #(#type_aliases)*
+
+        #transformed_input
+    })
+}
+
+fn impl_macro_fn(args: &MacroDeriveArgs, input: &syn::ItemFn) -> syn::Result<TokenStream2> {
+    reject_derive_options("macro_fn", args)?;
+    reject_adt_options("macro_fn", args)?;
+
+    let (type_aliases, transformed_input) = expand_fn_macros(args, input)?;
+    dump_expansion(
+        args,
+        "macro_fn",
+        &input.sig.ident,
+        &type_aliases,
+        &transformed_input,
+    );
+    Ok(quote! {
+        #(#type_aliases)*
+
+        #transformed_input
+    })
+}
+
+fn impl_macro_trait(args: &MacroDeriveArgs, input: &syn::ItemTrait) -> syn::Result<TokenStream2> {
+    reject_derive_options("macro_trait", args)?;
+    reject_adt_options("macro_trait", args)?;
+
+    let (type_aliases, transformed_input) = expand_trait_macros(args, input)?;
+    dump_expansion(
+        args,
+        "macro_trait",
+        &input.ident,
+        &type_aliases,
+        &transformed_input,
+    );
+    Ok(quote! {
+        #(#type_aliases)*
+
+        #transformed_input
+    })
+}
+
+/// Reject the options of `macro_derive` which only make sense when deriving
+fn reject_derive_options(attribute: &str, args: &MacroDeriveArgs) -> syn::Result<()> {
+    if let Some(path) = args.derive_traits.first() {
+        return Err(syn::Error::new_spanned(
+            path,
+            format!(
+                "`{}` does not derive traits, use `macro_derive` instead",
+                attribute
+            ),
+        ));
+    }
+    if !args.cfg_derives.is_empty()
+        || !args.extra_attrs.is_empty()
+        || args.type_path.is_some()
+        || args.repr.is_some()
+        || args.perfect_bounds
+        || args.bounds.is_some()
+        || args.serde_bounds
+        || args.qualify_derives
+        || args.split_derives
+    {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            format!(
+                "`{}` does not accept `cfg(...)`, `attr(...)`, `type_path`, `repr(...)`, \
+                 `perfect_bounds`, `bound`, `serde_bounds`, `qualify_derives` or \
+                 `split_derives`, use `macro_derive` instead",
+                attribute
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Reject the options of `macro_derive` which only apply to structs, enums and unions
+fn reject_adt_options(attribute: &str, args: &MacroDeriveArgs) -> syn::Result<()> {
+    if args.expose_types
+        || args.introspect
+        || !args.asserted_traits.is_empty()
+        || args.phantom
+        || args.doc_original
+        || args.keep_original.is_some()
+        || args.serde_borrow
+        || !args.where_predicates.is_empty()
+        || !args.rewritten_attrs.is_empty()
+        || !args.schema_types.is_empty()
+    {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            format!(
+                "`expose_types`, `introspect`, `assert_traits(...)`, `phantom`, \
+                 `doc_original`, `keep_original`, `serde_borrow`, `where_clause(...)`, \
+                 `rewrite_attrs(...)` and `schemars_with(...)` of `{}` only apply to structs, \
+                 enums and unions",
+                attribute
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Items generated for the macro invocations found in the input
+#[derive(Default)]
+pub struct MacroAliases {
+    /// Type aliases for macros in type position, keyed by [`scoped_alias_key`]
+    types: HashMap<String, TypeAlias>,
+    /// Constants for macros used as array lengths, e.g. `[u8; LEN!(FRAME)]`, with the number
+    /// of constants collected before each, which orders the emitted constants
+    lengths: HashMap<syn::Expr, (Ident, usize)>,
+    /// The annotated item with its generic arguments, substituted for `Self` in the aliases,
+    /// or `None` where `Self` is not in scope
+    self_ty: Option<TokenStream2>,
+    /// Distinguishes the stable names of aliases generated for different items
+    seed: TokenStream2,
+    /// Name of the item the aliases are generated for, which readable names start with
+    scope: String,
+    /// Names taken by the item or by already generated aliases
+    reserved_names: HashSet<String>,
+    /// Callbacks customizing the aliases
+    hooks: Hooks,
+    /// Items describing the rewritten field types of items, generated by `expose_types` and
+    /// `introspect`
+    field_items: Vec<TokenStream2>,
+    /// The invocation and the uses of the generated items documented with
+    /// `document_aliases`, keyed by their names
+    documented: HashMap<String, (String, Vec<String>)>,
+}
+
+/// Callbacks registered on a [`TypeMacroRewriter`]
+#[derive(Clone, Default)]
+struct Hooks {
+    name: Option<Hook<Type, Ident>>,
+    filter: Option<Hook<syn::TypeMacro, bool>>,
+    visibility: Option<Hook<Type, syn::Visibility>>,
+    attributes: Option<Hook<Type, Vec<syn::Attribute>>>,
+}
+
+type Hook<T, R> = Rc<dyn Fn(&T) -> R>;
+
+/// Configurable replacement of the macro types of a struct, enum or union
+///
+/// This is the transformation of `#[type_macro_expand]`, with callbacks deciding which macro
+/// types are replaced and how the aliases look:
+///
+/// ```
+/// use type_macro_derive_tricks_core::TypeMacroRewriter;
+///
+/// let input: syn::DeriveInput = syn::parse_quote! {
+///     struct Config<T> {
+///         values: Pair![T],
+///         raw: Keep![u8],
+///     }
+/// };
+/// let (aliases, transformed) = TypeMacroRewriter::new()
+///     .filter(|type_macro| !type_macro.mac.path.is_ident("Keep"))
+///     .name_aliases(|_| syn::parse_quote!(ConfigValues))
+///     .alias_visibility(|_| syn::parse_quote!(pub(crate)))
+///     .rewrite(&input)
+///     .unwrap();
+///
+/// assert_eq!(aliases.len(), 1);
+/// assert!(aliases[0].to_string().contains("pub (crate) type ConfigValues"));
+/// # let _ = transformed;
+/// ```
+#[derive(Clone, Default)]
+pub struct TypeMacroRewriter {
+    args: MacroDeriveArgs,
+    hooks: Hooks,
+}
+
+impl TypeMacroRewriter {
+    /// Rewriter with the default options of `#[type_macro_expand]` and no callbacks
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the options parsed from the arguments of an attribute, such as `prefix`, `vis`,
+    /// `deep` or `strict`
+    pub fn args(mut self, args: MacroDeriveArgs) -> Self {
+        self.args = args;
+        self
+    }
+
+    /// Name the alias of each aliased type with `name` instead of a generated name
+    ///
+    /// Names clashing with the item, its parameters or other aliases get a numeric suffix.
+    pub fn name_aliases(mut self, name: impl Fn(&Type) -> Ident + 'static) -> Self {
+        self.hooks.name = Some(Rc::new(name));
+        self
+    }
+
+    /// Only replace the macro types for which `filter` returns `true`, leaving the others as
+    /// written
+    pub fn filter(mut self, filter: impl Fn(&syn::TypeMacro) -> bool + 'static) -> Self {
+        self.hooks.filter = Some(Rc::new(filter));
+        self
+    }
+
+    /// Decide the visibility of the alias of each aliased type, overriding `vis`
+    pub fn alias_visibility(
+        mut self,
+        visibility: impl Fn(&Type) -> syn::Visibility + 'static,
+    ) -> Self {
+        self.hooks.visibility = Some(Rc::new(visibility));
+        self
+    }
+
+    /// Add the attributes returned by `attributes` to the alias of each aliased type
+    pub fn alias_attributes(
+        mut self,
+        attributes: impl Fn(&Type) -> Vec<syn::Attribute> + 'static,
+    ) -> Self {
+        self.hooks.attributes = Some(Rc::new(attributes));
+        self
+    }
+
+    /// Generate the aliases for `input`, returning them together with the item rewritten
+    /// to refer to them
+    pub fn rewrite(&self, input: &DeriveInput) -> syn::Result<(Vec<TokenStream2>, DeriveInput)> {
+        let aliases = MacroAliases {
+            hooks: self.hooks.clone(),
+            ..MacroAliases::default()
+        };
+        expand_type_macros_with(&self.args, input, aliases)
+    }
+}
+
+/// A type alias generated for a macro type
+struct TypeAlias {
+    /// The aliased type, i.e. the right-hand side of the alias
+    aliased: Type,
+    name: Ident,
+    /// The generics in scope at the macro type, from which the alias takes its parameters
+    generics: Generics,
+    /// The parameters of `generics` used by the aliased type, which the alias declares
+    params: Vec<syn::GenericParam>,
+    /// Number of aliases collected before this one, which orders the emitted aliases
+    position: usize,
+    /// The `cfg` predicates of each use of the alias, or `None` once it is used
+    /// unconditionally
+    conditions: Option<Vec<Vec<Meta>>>,
+    /// Attributes requested with `alias_attrs` by the items using the alias
+    attrs: Vec<Meta>,
+}
+
+impl TypeAlias {
+    fn new(aliased: Type, name: Ident, generics: &Generics, position: usize) -> Self {
+        let params = get_used_generic_params(&aliased, generics);
+        for (param, span) in filtered_generic_params(&aliased, generics, &params) {
+            emit_warning(
+                span,
+                &format!(
+                    "generic parameter `{}` is not passed to the alias generated for `{}`, \
+                     since it does not appear in a type position",
+                    param,
+                    render_source_tokens(&quote!(#aliased))
+                ),
+            );
+        }
+        TypeAlias {
+            aliased,
+            name,
+            generics: generics.clone(),
+            params,
+            position,
+            conditions: Some(Vec::new()),
+            attrs: Vec::new(),
+        }
+    }
+
+    /// Record a use of the alias under the `cfg` predicates `conditions`
+    fn require(&mut self, conditions: &[Meta]) {
+        if conditions.is_empty() {
+            self.conditions = None;
+        } else if let Some(uses) = &mut self.conditions {
+            if !uses
+                .iter()
+                .any(|existing| existing.as_slice() == conditions)
+            {
+                uses.push(conditions.to_vec());
+            }
+        }
+    }
+
+    /// Record the `alias_attrs` of an item using the alias, which items sharing it combine
+    fn add_attrs(&mut self, attrs: &[Meta]) {
+        for attr in attrs {
+            if !self.attrs.contains(attr) {
+                self.attrs.push(attr.clone());
+            }
+        }
+    }
+
+    /// The `#[cfg]` attribute under which the alias exists, if it is not used unconditionally
+    fn cfg_attr(&self) -> Option<syn::Attribute> {
+        let uses = self.conditions.as_ref().filter(|uses| !uses.is_empty())?;
+        let conjunctions = uses.iter().map(|conditions| match conditions.as_slice() {
+            [condition] => quote!(#condition),
+            conditions => quote!(all(#(#conditions),*)),
+        });
+        Some(if uses.len() == 1 {
+            syn::parse_quote!(#[cfg(#(#conjunctions)*)])
+        } else {
+            syn::parse_quote!(#[cfg(any(#(#conjunctions),*))])
+        })
+    }
+
+    /// Build the use-site type referring to the alias
+    ///
+    /// The reference is located at `span`, the span of the replaced type, so that errors
+    /// about the field point at the original invocation rather than at the attribute.
+    fn reference(&self, span: proc_macro2::Span) -> Type {
+        let mut alias = self.name.clone();
+        alias.set_span(alias.span().located_at(span));
+
+        if self.params.is_empty() {
+            syn::parse_quote!(#alias)
+        } else {
+            // Create filtered generics and use them
+            let filtered_generics = create_filtered_generics(&self.params);
+            let (_, ty_generics, _) = filtered_generics.split_for_impl();
+            syn::parse_quote!(#alias #ty_generics)
+        }
+    }
+}
+
+/// Generate the type aliases for all macro types in `input`, returning them together with
+/// the item rewritten to refer to the aliases
+pub fn expand_type_macros(
+    args: &MacroDeriveArgs,
+    input: &DeriveInput,
+) -> syn::Result<(Vec<TokenStream2>, DeriveInput)> {
+    expand_type_macros_with(args, input, MacroAliases::default())
+}
+
+fn expand_type_macros_with(
+    args: &MacroDeriveArgs,
+    input: &DeriveInput,
+    mut aliases: MacroAliases,
+) -> syn::Result<(Vec<TokenStream2>, DeriveInput)> {
+    let alias_vis = args.vis.as_ref().unwrap_or(&input.vis);
+
+    // Step 1: Replace the macro types with aliases, collecting the aliases on the way
+    let mut transformed_input = input.clone();
+    rewrite_item(args, &mut transformed_input, &mut aliases)?;
+    if aliases.types.is_empty() && aliases.lengths.is_empty() {
+        emit_warning(
+            input.ident.span(),
+            &format!(
+                "no macro invocations found in the types of `{}`, a plain `#[derive]` would \
+                 suffice",
+                input.ident
+            ),
+        );
+    }
+
+    // Step 2: Generate type aliases
+    let type_aliases = emit_aliases(&aliases, alias_vis);
+
+    Ok((type_aliases, transformed_input))
+}
+
+/// Rewrite the struct, enum or union `input` to refer to aliases of its macro types,
+/// collecting the aliases into `aliases`
+fn rewrite_item(
+    args: &MacroDeriveArgs,
+    input: &mut DeriveInput,
+    aliases: &mut MacroAliases,
+) -> syn::Result<()> {
+    validate_field_attrs(&input.data)?;
+
+    let self_ty = item_self_ty(input);
+    aliases.seed = self_ty.clone();
+    aliases.scope = input.ident.unraw().to_string();
+    aliases.self_ty = Some(self_ty);
+    aliases.reserved_names.extend(reserved_names(input));
+
+    let original_types: Vec<Type> = if args.expose_types || args.introspect {
+        all_fields(&input.data)
+            .into_iter()
+            .map(|field| field.ty.clone())
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    if !args.schema_types.is_empty() {
+        add_schema_overrides(args, &mut input.data);
+    }
+
+    // The parameters are rewritten in place, so the aliases take theirs from a copy
+    let generics = input.generics.clone();
+    if !args.where_predicates.is_empty() {
+        input
+            .generics
+            .make_where_clause()
+            .predicates
+            .extend(args.where_predicates.iter().cloned());
+    }
+    let field_names = field_type_names(input);
+    let field_locations = field_locations(input);
+    let mut visitor = MacroTypeVisitor::collecting(&generics, args, aliases);
+    let field_conditions = field_cfg_predicates(&input.data);
+    for (((field, conditions), name), location) in all_fields_mut(&mut input.data)
+        .into_iter()
+        .zip(field_conditions)
+        .zip(field_names)
+        .zip(field_locations)
+    {
+        visitor.conditions = conditions;
+        visitor.site = Some(name.unraw().to_string());
+        visitor.location = Some(location);
+        if is_field_aliased(field) {
+            // The whole field type is aliased, including any macros inside it
+            visitor.replace(&mut field.ty);
+        } else if !is_field_skipped(field) {
+            visitor.visit_type_mut(&mut field.ty);
+        }
+    }
+    visitor.conditions.clear();
+    visitor.site = None;
+    visitor.location = None;
+    visitor.visit_generics_mut(&mut input.generics);
+    for attr in all_attrs_mut(input) {
+        if let Meta::List(list) = &mut attr.meta {
+            if args.rewritten_attrs.contains(&list.path) {
+                list.tokens = rewrite_helper_tokens(&list.tokens, &mut visitor);
+            }
+        }
+    }
+
+    if args.serde_borrow {
+        mark_borrowing_fields(input, aliases);
+    }
+    if args.strict {
+        check_macro_types_replaced(input)?;
+    }
+    if args.expose_types {
+        // Only the fields whose types were rewritten are exposed
+        let exposed = field_type_names(input)
+            .into_iter()
+            .zip(all_fields(&input.data))
+            .zip(field_cfg_predicates(&input.data))
+            .zip(&original_types)
+            .filter(|(((_, field), _), original)| field.ty != **original)
+            .map(|(((name, field), conditions), _)| (name, field.ty.clone(), conditions))
+            .collect();
+        aliases.field_items.push(types_trait(input, exposed));
+    }
+    if args.introspect {
+        aliases
+            .field_items
+            .push(introspection_const(input, &original_types));
+    }
+    if !args.asserted_traits.is_empty() {
+        let assertions = trait_assertions(input, aliases, &args.asserted_traits);
+        aliases.field_items.extend(assertions);
+    }
+
+    let dropped = dropped_generic_params(input, &generics, aliases);
+    if !dropped.is_empty() {
+        if args.phantom {
+            let params: Vec<_> = dropped.into_iter().map(|(param, _)| param).collect();
+            add_phantom_field(input, &params)?;
+        } else {
+            combine_errors(dropped_params_errors(input, &dropped))?;
+        }
+    }
+
+    // Field options are consumed here and must not reach the emitted item
+    for field in all_fields_mut(&mut input.data) {
+        field.attrs.retain(|attr| {
+            !attr.path().is_ident("macro_derive") && !attr.path().is_ident("macro_alias")
+        });
+    }
+    Ok(())
+}
+
+/// Add `#[serde(borrow)]` to the fields of `input` referring to aliases with lifetime
+/// parameters, for `serde_borrow`
+///
+/// serde only borrows `&str` and `&[u8]` fields by itself, which it cannot recognize behind
+/// an alias. Fields already borrowing or not deserialized are left as they are.
+fn mark_borrowing_fields(input: &mut DeriveInput, aliases: &MacroAliases) {
+    let borrowing: Vec<String> = aliases
+        .types
+        .values()
+        .filter(|alias| {
+            alias
+                .params
+                .iter()
+                .any(|param| matches!(param, syn::GenericParam::Lifetime(_)))
+        })
+        .map(|alias| alias.name.to_string())
+        .collect();
+    for field in all_fields_mut(&mut input.data) {
+        let ty = &field.ty;
+        let tokens = quote!(#ty);
+        if borrowing
+            .iter()
+            .any(|name| is_generic_param_used_in_token_stream(&tokens, name))
+            && !has_serde_option(
+                &field.attrs,
+                &[
+                    "borrow",
+                    "skip",
+                    "skip_deserializing",
+                    "with",
+                    "deserialize_with",
+                ],
+            )
+        {
+            field.attrs.push(syn::parse_quote!(#[serde(borrow)]));
+        }
+    }
+}
+
+/// Type and lifetime parameters of `generics` which the rewritten fields of `input` no
+/// longer use, each with the aliased macro types mentioning it without taking it as a
+/// parameter
+///
+/// The parameters of an alias are found by parsing the macro arguments, so a parameter
+/// which only appears in a position that is not a type, as in `Id![name: T]`, is not passed
+/// to the alias. Parameters unused before the rewrite are left to the compiler to report.
+fn dropped_generic_params<'a>(
+    input: &DeriveInput,
+    generics: &Generics,
+    aliases: &'a MacroAliases,
+) -> Vec<(syn::GenericParam, Vec<&'a Type>)> {
+    let param_names: Vec<String> = generics.params.iter().map(generic_param_name).collect();
+    let mut visitor = GenericUsageVisitor::new(&param_names);
+    for field in all_fields(&input.data) {
+        visitor.visit_type(&field.ty);
+    }
+
+    let mut sorted_aliases: Vec<_> = aliases.types.values().collect();
+    sorted_aliases.sort_by_key(|alias| alias.position);
+    generics
+        .params
+        .iter()
+        // Unused const parameters are allowed
+        .filter(|param| !matches!(param, syn::GenericParam::Const(_)))
+        .filter(|param| !visitor.used_names.contains(&generic_param_name(param)))
+        .filter_map(|param| {
+            let name = generic_param_name(param);
+            let is_param = |param: &syn::GenericParam| generic_param_name(param) == name;
+            let mentioning: Vec<&Type> = sorted_aliases
+                .iter()
+                .filter(|alias| {
+                    let aliased = &alias.aliased;
+                    alias.generics.params.iter().any(is_param)
+                        && !alias.params.iter().any(is_param)
+                        && is_generic_param_used_in_token_stream(&quote!(#aliased), &name)
+                })
+                .map(|alias| &alias.aliased)
+                .collect();
+            if mentioning.is_empty() {
+                None
+            } else {
+                Some((param.clone(), mentioning))
+            }
+        })
+        .collect()
+}
+
+/// Errors explaining why the parameters in `dropped` are no longer used by `input`
+fn dropped_params_errors(
+    input: &DeriveInput,
+    dropped: &[(syn::GenericParam, Vec<&Type>)],
+) -> Vec<syn::Error> {
+    let mut errors = Vec::new();
+    for (param, mentioning) in dropped {
+        let name = generic_param_name(param);
+        errors.push(syn::Error::new_spanned(
+            param,
+            format!(
+                "`{}` is not used by the fields of `{}` once their macro types are replaced \
+                 with aliases; use it in a field type or add `phantom = true`",
+                name, input.ident
+            ),
+        ));
+        for ty in mentioning {
+            errors.push(syn::Error::new_spanned(
+                ty,
+                format!(
+                    "`{}` appears in this macro invocation, but not where a type is expected, \
+                     so its alias does not take `{}` as a parameter",
+                    name, name
+                ),
+            ));
+        }
+    }
+    errors
+}
+
+/// Add a hidden `PhantomData` field mentioning `params` to the struct or union `input`, for
+/// `phantom`
+fn add_phantom_field(input: &mut DeriveInput, params: &[syn::GenericParam]) -> syn::Result<()> {
+    let markers = params.iter().map(|param| match param {
+        syn::GenericParam::Lifetime(lifetime_param) => {
+            let lifetime = &lifetime_param.lifetime;
+            quote!(&#lifetime ())
+        }
+        syn::GenericParam::Type(type_param) => {
+            let ident = &type_param.ident;
+            quote!(#ident)
+        }
+        syn::GenericParam::Const(const_param) => {
+            let ident = &const_param.ident;
+            quote!([(); #ident])
+        }
+    });
+    let ty: Type = syn::parse_quote!(::core::marker::PhantomData<(#(#markers,)*)>);
+    let ident = Ident::new("__type_macro_phantom", proc_macro2::Span::call_site());
+    let named = || -> syn::Field {
+        syn::parse_quote! {
+            #[doc(hidden)]
+            #ident: #ty
+        }
+    };
+
+    match &mut input.data {
+        Data::Struct(data_struct) => match &mut data_struct.fields {
+            syn::Fields::Named(fields) => fields.named.push(named()),
+            syn::Fields::Unnamed(fields) => fields.unnamed.push(syn::parse_quote! {
+                #[doc(hidden)]
+                #ty
+            }),
+            syn::Fields::Unit => {
+                data_struct.fields = syn::Fields::Named(syn::parse_quote!({ #ident: #ty }));
+                data_struct.semi_token = None;
+            }
+        },
+        Data::Union(data_union) => data_union.fields.named.push(named()),
+        Data::Enum(_) => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "`phantom` cannot add a field to an enum; add a `PhantomData` field to one of \
+                 its variants instead",
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Descriptions of the fields of `input` in field order, as in ``the field `Shape::Line::to` ``
+fn field_locations(input: &DeriveInput) -> Vec<String> {
+    let item = input.ident.unraw();
+    let describe = |path: String, fields: &syn::Fields| -> Vec<String> {
+        fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| match &field.ident {
+                Some(ident) => format!("the field `{}::{}`", path, ident.unraw()),
+                None => format!("the field `{}::{}`", path, index),
+            })
+            .collect()
+    };
+    match &input.data {
+        Data::Struct(data_struct) => describe(item.to_string(), &data_struct.fields),
+        Data::Enum(data_enum) => data_enum
+            .variants
+            .iter()
+            .flat_map(|variant| {
+                describe(
+                    format!("{}::{}", item, variant.ident.unraw()),
+                    &variant.fields,
+                )
+            })
+            .collect(),
+        Data::Union(data_union) => describe(
+            item.to_string(),
+            &syn::Fields::Named(data_union.fields.clone()),
+        ),
+    }
+}
+
+/// Names of the associated types exposing the fields of `input`, in the order of
+/// [`all_fields`]
+///
+/// Named fields keep their name and unnamed fields are named after their index, as in
+/// `_0`. The fields of enum variants are prefixed with the variant name, as in `Ok_0`.
+fn field_type_names(input: &DeriveInput) -> Vec<Ident> {
+    fn field_names(fields: &syn::Fields, prefix: Option<&Ident>) -> Vec<Ident> {
+        fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let name = match &field.ident {
+                    Some(ident) if prefix.is_none() => return ident.clone(),
+                    Some(ident) => ident.unraw().to_string(),
+                    None => index.to_string(),
+                };
+                let name = match prefix {
+                    Some(variant) => format!("{}_{}", variant.unraw(), name),
+                    None => format!("_{}", name),
+                };
+                Ident::new(&name, field.span())
+            })
+            .collect()
+    }
+
+    match &input.data {
+        Data::Struct(data_struct) => field_names(&data_struct.fields, None),
+        Data::Enum(data_enum) => data_enum
+            .variants
+            .iter()
+            .flat_map(|variant| field_names(&variant.fields, Some(&variant.ident)))
+            .collect(),
+        Data::Union(data_union) => data_union
+            .fields
+            .named
+            .iter()
+            .filter_map(|field| field.ident.clone())
+            .collect(),
+    }
+}
+
+/// Generate the `expose_types` trait of `input`, named after it with a `Types` suffix, with
+/// one associated type per entry of `exposed`, gated by the `cfg` predicates of its field
+fn types_trait(input: &DeriveInput, exposed: Vec<(Ident, Type, Vec<Meta>)>) -> TokenStream2 {
+    let (vis, ident) = (&input.vis, &input.ident);
+    let trait_ident = Ident::new(&format!("{}Types", ident.unraw()), ident.span());
+    let mut names = Vec::new();
+    let mut types = Vec::new();
+    let mut cfgs = Vec::new();
+    for (name, ty, conditions) in exposed {
+        names.push(name);
+        types.push(ty);
+        cfgs.push(quote!(#(#[cfg(#conditions)])*));
+    }
+    let doc = format!(
+        " Types of the fields of [`{}`] which contain macro types",
+        ident
+    );
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote! {
+        #[doc = #doc]
+        #[allow(dead_code, non_camel_case_types)]
+        #vis trait #trait_ident {
+            #(#cfgs type #names;)*
+        }
+
+        #[automatically_derived]
+        impl #impl_generics #trait_ident for #ident #ty_generics #where_clause {
+            #(#cfgs type #names = #types;)*
+        }
+    }
+}
+
+/// Generate the `introspect` constant of `input`, named after it with a `_MACRO_TYPES` suffix,
+/// pairing the names of the rewritten fields with their types as written in `original_types`
+fn introspection_const(input: &DeriveInput, original_types: &[Type]) -> TokenStream2 {
+    fn field_labels(fields: &syn::Fields, variant: Option<&Ident>) -> Vec<String> {
+        fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let name = match &field.ident {
+                    Some(ident) => ident.unraw().to_string(),
+                    None => index.to_string(),
+                };
+                match variant {
+                    Some(variant) => format!("{}::{}", variant.unraw(), name),
+                    None => name,
+                }
+            })
+            .collect()
+    }
+
+    let labels = match &input.data {
+        Data::Struct(data_struct) => field_labels(&data_struct.fields, None),
+        Data::Enum(data_enum) => data_enum
+            .variants
+            .iter()
+            .flat_map(|variant| field_labels(&variant.fields, Some(&variant.ident)))
+            .collect(),
+        Data::Union(data_union) => field_labels(&data_union.fields.clone().into(), None),
+    };
+    let mut cfgs = Vec::new();
+    let mut entries = Vec::new();
+    for (((label, field), conditions), original) in labels
+        .into_iter()
+        .zip(all_fields(&input.data))
+        .zip(field_cfg_predicates(&input.data))
+        .zip(original_types)
+    {
+        if field.ty != *original {
+            let source = render_source_tokens(&quote!(#original));
+            cfgs.push(quote!(#(#[cfg(#conditions)])*));
+            entries.push(quote!((#label, #source)));
+        }
+    }
+    let (vis, ident) = (&input.vis, &input.ident);
+    let const_ident = Ident::new(
+        &format!(
+            "{}_MACRO_TYPES",
+            screaming_snake_case(&ident.unraw().to_string())
+        ),
+        ident.span(),
+    );
+    quote! {
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        #vis const #const_ident: &[(&str, &str)] = &[#(#cfgs #entries),*];
+    }
+}
+
+/// Generate a block for each trait of `traits` and each alias used by the fields of `input`,
+/// which fails to compile unless the alias implements the trait, for `assert_traits(...)`
+///
+/// The check is located at the first field using the alias, so that the error points at it
+/// rather than at the derive of the trait. Type parameters are assumed to implement all of
+/// `traits`, as derives would require, since `Arc<T>: Send` needs `T: Sync` as well. The
+/// check takes the item to assume the bounds its fields imply, such as `T: 'a` for a `&'a T`
+/// field.
+fn trait_assertions(
+    input: &DeriveInput,
+    aliases: &MacroAliases,
+    traits: &[syn::Path],
+) -> Vec<TokenStream2> {
+    let mut generics = input.generics.clone();
+    for param in &mut generics.params {
+        match param {
+            syn::GenericParam::Type(tp) => {
+                tp.eq_token = None;
+                tp.default = None;
+            }
+            syn::GenericParam::Const(cp) => {
+                cp.eq_token = None;
+                cp.default = None;
+            }
+            syn::GenericParam::Lifetime(_) => {}
+        }
+    }
+    let type_params: Vec<Ident> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let predicates = where_clause.map(|where_clause| &where_clause.predicates);
+    let self_ty = item_self_ty(input);
+    let assumed = quote!(#(#traits)+*);
+
+    let mut sorted_aliases: Vec<_> = aliases.types.values().collect();
+    sorted_aliases.sort_by_key(|alias| alias.position);
+    let mut assertions = Vec::new();
+    for alias in sorted_aliases {
+        let name = alias.name.to_string();
+        let use_site = all_fields(&input.data)
+            .into_iter()
+            .zip(field_cfg_predicates(&input.data))
+            .find(|(field, _)| {
+                let ty = &field.ty;
+                is_generic_param_used_in_token_stream(&quote!(#ty), &name)
+            });
+        let (field, conditions) = match use_site {
+            Some(use_site) => use_site,
+            None => continue,
+        };
+        let span = field.ty.span();
+        let reference = alias.reference(span);
+        let check = respan(&quote!(_assert::<#reference>();), span);
+        for path in traits {
+            assertions.push(quote! {
+                #(#[cfg(#conditions)])*
+                const _: () = {
+                    fn _assert<T: ?Sized + #path>() {}
+                    fn _check #impl_generics(_: &#self_ty)
+                    where
+                        #(#type_params: #assumed,)*
+                        #predicates
+                    {
+                        #check
+                    }
+                };
+            });
+        }
+    }
+    assertions
+}
+
+/// `name` in upper case with words separated by underscores, as in `HTTP_SERVER` for
+/// `HttpServer` or `HTTPServer`
+fn screaming_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut converted = String::new();
+    for (index, c) in chars.iter().enumerate() {
+        if index > 0 && c.is_uppercase() {
+            let previous = chars[index - 1];
+            let next_is_lower = matches!(chars.get(index + 1), Some(next) if next.is_lowercase());
+            if previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_uppercase() && next_is_lower)
+            {
+                converted.push('_');
+            }
+        }
+        converted.extend(c.to_uppercase());
+    }
+    converted
+}
+
+/// The type of `input` with its generic arguments, which `Self` refers to
+fn item_self_ty(input: &DeriveInput) -> TokenStream2 {
+    let ident = &input.ident;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    quote!(#ident #ty_generics)
+}
+
+/// Generate the items declared by `aliases`
+///
+/// The aliases are emitted next to the item rather than in a separate module, and the macro
+/// invocations are moved into them with their original spans. Paths such as
+/// `$crate::Macro!`, `self::`, `super::` and re-exported macro names therefore resolve in
+/// the alias exactly as they did in the field type. The alias names themselves resolve at
+/// `Span::call_site`, the same context as the item referring to them.
+pub fn emit_aliases(aliases: &MacroAliases, alias_vis: &syn::Visibility) -> Vec<TokenStream2> {
+    let mut type_aliases = Vec::new();
+
+    // Emit in a fixed order, so that the expansion is reproducible
+    let mut sorted_aliases: Vec<_> = aliases.types.values().collect();
+    sorted_aliases.sort_by_key(|alias| alias.position);
+    for alias in sorted_aliases {
+        let (macro_type, alias_name) = (&alias.aliased, &alias.name);
+        let alias_vis = match &aliases.hooks.visibility {
+            Some(visibility) => visibility(macro_type),
+            None => alias_vis.clone(),
+        };
+        let mut extra_attrs = match &aliases.hooks.attributes {
+            Some(attributes) => attributes(macro_type),
+            None => Vec::new(),
+        };
+        extra_attrs.extend(alias.cfg_attr());
+        extra_attrs.extend(
+            alias
+                .attrs
+                .iter()
+                .map(|meta| -> syn::Attribute { syn::parse_quote!(#[#meta]) }),
+        );
+        // Generate type aliases with only the specific generic parameters used by the macro
+        // and add #[doc(hidden)] to hide them from documentation, unless they are documented.
+        // They may be left unused once the fields using them are configured out
+        let used_generic_params = &alias.params;
+        let doc = alias_doc_attr(aliases, alias_name);
+
+        let alias = if used_generic_params.is_empty() {
+            quote! {
+                #(#extra_attrs)*
+                #doc
+                #[allow(dead_code)]
+                #alias_vis type #alias_name = #macro_type;
+            }
+        } else {
+            // Create a filtered Generics struct with only the used parameters
+            let filtered_generics = create_filtered_generics(used_generic_params)
+                .params
+                .into_iter()
+                .map(|mut param| {
+                    match &mut param {
+                        syn::GenericParam::Type(tp) => {
+                            tp.eq_token = None;
+                            tp.default = None;
+                            tp.bounds = std::mem::take(&mut tp.bounds)
+                                .into_iter()
+                                .filter(|bound| {
+                                    keep_alias_bound(
+                                        &quote!(#bound),
+                                        used_generic_params,
+                                        &alias.generics,
+                                    )
+                                })
+                                .collect();
+                            if tp.bounds.is_empty() {
+                                tp.colon_token = None;
+                            }
+                        }
+                        syn::GenericParam::Lifetime(lp) => {
+                            lp.bounds = std::mem::take(&mut lp.bounds)
+                                .into_iter()
+                                .filter(|bound| {
+                                    keep_alias_bound(
+                                        &quote!(#bound),
+                                        used_generic_params,
+                                        &alias.generics,
+                                    )
+                                })
+                                .collect();
+                            if lp.bounds.is_empty() {
+                                lp.colon_token = None;
+                            }
+                        }
+                        syn::GenericParam::Const(cp) => {
+                            cp.eq_token = None;
+                            cp.default = None;
+                        }
+                    }
+                    param
+                })
+                .collect::<Punctuated<_, syn::Token![,]>>();
+            // Bounds are kept because projections such as `T::Item` in the macro expansion
+            // need them to resolve, even though they are not enforced on aliases
+            quote! {
+                #(#extra_attrs)*
+                #doc
+                #[allow(dead_code, type_alias_bounds)]
+                #alias_vis type #alias_name <#filtered_generics> = #macro_type;
+            }
+        };
+        type_aliases.push(alias);
+    }
+
+    let mut sorted_lengths: Vec<_> = aliases.lengths.iter().collect();
+    sorted_lengths.sort_by_key(|(_, (_, position))| *position);
+    for (length, (const_name, _)) in sorted_lengths {
+        let doc = alias_doc_attr(aliases, const_name);
+        type_aliases.push(quote! {
+            #doc
+            #[allow(dead_code, non_upper_case_globals)]
+            #alias_vis const #const_name: usize = #length;
+        });
+    }
+    type_aliases.extend(aliases.field_items.iter().cloned());
+
+    type_aliases
+}
+
+/// Record a use of the generated item `name` at `location`, or in the item the aliases are
+/// generated for, for `document_aliases`
+fn document_use(
+    aliases: &mut MacroAliases,
+    name: &Ident,
+    invocation: String,
+    location: Option<&String>,
+) {
+    let location = match location {
+        Some(location) => location.clone(),
+        None if aliases.scope.is_empty() => String::new(),
+        None => format!("`{}`", aliases.scope),
+    };
+    let (_, uses) = aliases
+        .documented
+        .entry(name.to_string())
+        .or_insert_with(|| (invocation, Vec::new()));
+    if !location.is_empty() && !uses.contains(&location) {
+        uses.push(location);
+    }
+}
+
+/// `#[doc(hidden)]`, or the documentation of `name` describing its invocation and its uses
+/// with `document_aliases`
+fn alias_doc_attr(aliases: &MacroAliases, name: &Ident) -> syn::Attribute {
+    let (invocation, uses) = match aliases.documented.get(&name.to_string()) {
+        Some(documented) => documented,
+        None => return syn::parse_quote!(#[doc(hidden)]),
+    };
+    let mut doc = format!(" Alias of `{}`", invocation);
+    for (index, location) in uses.iter().enumerate() {
+        doc.push_str(match index {
+            0 => ", used by ",
+            index if index + 1 == uses.len() => " and ",
+            _ => ", ",
+        });
+        doc.push_str(location);
+    }
+    syn::parse_quote!(#[doc = #doc])
+}
+
+/// Report every macro type left in the rewritten `input`, except in fields marked with
+/// `#[macro_derive(skip)]`
+fn check_macro_types_replaced(input: &DeriveInput) -> syn::Result<()> {
+    let mut errors = Vec::new();
+
+    let mut finder = MacroTypeFinder::default();
+    finder.visit_generics(&input.generics);
+    for type_macro in finder.found {
+        errors.push(unreplaced_macro_error(
+            type_macro,
+            &format!("in the generics of `{}`", input.ident),
+        ));
+    }
+
+    for field in all_fields(&input.data) {
+        if is_field_skipped(field) {
+            continue;
+        }
+        let mut finder = MacroTypeFinder::default();
+        finder.visit_type(&field.ty);
+        for type_macro in finder.found {
+            let location = match &field.ident {
+                Some(ident) => format!("in field `{}`", ident),
+                None => "in an unnamed field".to_string(),
+            };
+            errors.push(unreplaced_macro_error(type_macro, &location));
+        }
+    }
+
+    combine_errors(errors)
+}
+
+/// Visitor collecting the macro types outside of blocks, which are left as written
+#[derive(Default)]
+struct MacroTypeFinder {
+    found: Vec<syn::TypeMacro>,
+}
+
+impl<'ast> Visit<'ast> for MacroTypeFinder {
+    fn visit_type_macro(&mut self, type_macro: &'ast syn::TypeMacro) {
+        self.found.push(type_macro.clone());
+    }
+
+    fn visit_block(&mut self, _block: &'ast syn::Block) {}
+}
+
+/// Report every macro type left in a rewritten node, which `visit` passes to the finder
+fn check_no_macro_types(
+    visit: impl FnOnce(&mut MacroTypeFinder),
+    location: &str,
+) -> syn::Result<()> {
+    let mut finder = MacroTypeFinder::default();
+    visit(&mut finder);
+    combine_errors(
+        finder
+            .found
+            .into_iter()
+            .map(|type_macro| unreplaced_macro_error(type_macro, location))
+            .collect(),
+    )
+}
+
+fn unreplaced_macro_error(type_macro: syn::TypeMacro, location: &str) -> syn::Error {
+    syn::Error::new_spanned(
+        type_macro,
+        format!(
+            "`strict`: this macro type {} could not be replaced with an alias",
+            location
+        ),
+    )
+}
+
+/// Generate the type aliases for all macro types in the signatures of an `impl` block,
+/// returning them together with the block rewritten to refer to the aliases
+fn expand_impl_macros(
+    args: &MacroDeriveArgs,
+    input: &syn::ItemImpl,
+) -> syn::Result<(Vec<TokenStream2>, syn::ItemImpl)> {
+    let alias_vis = args.vis.clone().unwrap_or(syn::Visibility::Inherited);
+    let self_ty = &input.self_ty;
+    let generics = &input.generics;
+    let trait_path = input.trait_.as_ref().map(|(_, path, _)| path);
+    let mut aliases = MacroAliases {
+        self_ty: Some(quote!(#self_ty)),
+        // Several blocks may implement traits for the same type
+        seed: quote!(impl #trait_path for #self_ty),
+        scope: match &**self_ty {
+            Type::Path(type_path) => last_ident_name(&type_path.path),
+            _ => String::new(),
+        },
+        reserved_names: impl_reserved_names(input),
+        ..MacroAliases::default()
+    };
+
+    let mut transformed = input.clone();
+    let mut visitor = MacroTypeVisitor::collecting(generics, args, &mut aliases);
+    visitor.visit_type_mut(&mut transformed.self_ty);
+    if let Some((_, path, _)) = &mut transformed.trait_ {
+        visitor.visit_path_mut(path);
+    }
+    visitor.visit_generics_mut(&mut transformed.generics);
+    for item in &mut transformed.items {
+        match item {
+            syn::ImplItem::Fn(item_fn) => {
+                let scope = merge_generics(generics, &item_fn.sig.generics);
+                MacroTypeVisitor::collecting(&scope, args, &mut aliases)
+                    .rewrite_signature(&mut item_fn.sig);
+            }
+            syn::ImplItem::Type(item_type) => {
+                let scope = merge_generics(generics, &item_type.generics);
+                let mut visitor = MacroTypeVisitor::collecting(&scope, args, &mut aliases);
+                visitor.visit_generics_mut(&mut item_type.generics);
+                visitor.visit_type_mut(&mut item_type.ty);
+            }
+            syn::ImplItem::Const(item_const) => {
+                MacroTypeVisitor::collecting(generics, args, &mut aliases)
+                    .visit_type_mut(&mut item_const.ty);
+            }
+            _ => {}
+        }
+    }
+
+    let type_aliases = emit_aliases(&aliases, &alias_vis);
+    if args.strict {
+        check_no_macro_types(
+            |finder| finder.visit_item_impl(&transformed),
+            "in the `impl` block",
+        )?;
+    }
+
+    Ok((type_aliases, transformed))
+}
+
+/// Replace the macro types in the type annotation `ty` of the `const` or `static` item `ident`
+/// with references to generated aliases, returning the aliases
+fn expand_annotation_macros(
+    args: &MacroDeriveArgs,
+    keyword: &str,
+    ident: &Ident,
+    vis: &syn::Visibility,
+    ty: &mut Type,
+) -> syn::Result<Vec<TokenStream2>> {
+    let alias_vis = args.vis.as_ref().unwrap_or(vis);
+    let generics = Generics::default();
+    let mut aliases = MacroAliases {
+        self_ty: None,
+        seed: {
+            let keyword = Ident::new(keyword, proc_macro2::Span::call_site());
+            quote!(#keyword #ident)
+        },
+        scope: ident.unraw().to_string(),
+        reserved_names: HashSet::from([ident.to_string()]),
+        ..MacroAliases::default()
+    };
+
+    MacroTypeVisitor::collecting(&generics, args, &mut aliases).visit_type_mut(ty);
+    let type_aliases = emit_aliases(&aliases, alias_vis);
+    if args.strict {
+        check_no_macro_types(
+            |finder| finder.visit_type(ty),
+            &format!("in the type of `{}`", ident),
+        )?;
+    }
+
+    Ok(type_aliases)
+}
+
+/// Generate the type aliases for all macro types in the signature of a free function,
+/// returning them together with the function rewritten to refer to the aliases
+fn expand_fn_macros(
+    args: &MacroDeriveArgs,
+    input: &syn::ItemFn,
+) -> syn::Result<(Vec<TokenStream2>, syn::ItemFn)> {
+    let alias_vis = args.vis.as_ref().unwrap_or(&input.vis);
+    let sig = &input.sig;
+    let mut reserved_names = generic_names([&sig.generics]);
+    reserved_names.insert(sig.ident.to_string());
+    let mut aliases = MacroAliases {
+        // `Self` is not in scope in a free function
+        self_ty: None,
+        seed: {
+            let ident = &sig.ident;
+            quote!(fn #ident)
+        },
+        scope: sig.ident.unraw().to_string(),
+        reserved_names,
+        ..MacroAliases::default()
+    };
+
+    let mut transformed = input.clone();
+    MacroTypeVisitor::collecting(&sig.generics, args, &mut aliases)
+        .rewrite_signature(&mut transformed.sig);
+
+    let type_aliases = emit_aliases(&aliases, alias_vis);
+    if args.strict {
+        check_no_macro_types(
+            |finder| finder.visit_signature(&transformed.sig),
+            &format!("in the signature of `{}`", sig.ident),
+        )?;
+    }
+
+    Ok((type_aliases, transformed))
+}
+
+/// Generate the type aliases for all macro types in the items of a trait definition,
+/// returning them together with the trait rewritten to refer to the aliases
+fn expand_trait_macros(
+    args: &MacroDeriveArgs,
+    input: &syn::ItemTrait,
+) -> syn::Result<(Vec<TokenStream2>, syn::ItemTrait)> {
+    let alias_vis = args.vis.as_ref().unwrap_or(&input.vis);
+    let generics = &input.generics;
+    let mut reserved_names = generic_names(
+        std::iter::once(generics).chain(input.items.iter().filter_map(trait_item_generics)),
+    );
+    reserved_names.insert(input.ident.to_string());
+    let mut aliases = MacroAliases {
+        // `Self` is the implementing type, which is unknown here
+        self_ty: None,
+        seed: {
+            let ident = &input.ident;
+            quote!(trait #ident)
+        },
+        scope: input.ident.unraw().to_string(),
+        reserved_names,
+        ..MacroAliases::default()
+    };
+
+    let mut transformed = input.clone();
+    let mut visitor = MacroTypeVisitor::collecting(generics, args, &mut aliases);
+    visitor.visit_generics_mut(&mut transformed.generics);
+    for bound in &mut transformed.supertraits {
+        visitor.visit_type_param_bound_mut(bound);
+    }
+    for item in &mut transformed.items {
+        match item {
+            syn::TraitItem::Fn(item_fn) => {
+                let scope = merge_generics(generics, &item_fn.sig.generics);
+                MacroTypeVisitor::collecting(&scope, args, &mut aliases)
+                    .rewrite_signature(&mut item_fn.sig);
+            }
+            syn::TraitItem::Type(item_type) => {
+                let scope = merge_generics(generics, &item_type.generics);
+                let mut visitor = MacroTypeVisitor::collecting(&scope, args, &mut aliases);
+                visitor.visit_generics_mut(&mut item_type.generics);
+                for bound in &mut item_type.bounds {
+                    visitor.visit_type_param_bound_mut(bound);
+                }
+                if let Some((_, default)) = &mut item_type.default {
+                    visitor.visit_type_mut(default);
+                }
+            }
+            syn::TraitItem::Const(item_const) => {
+                MacroTypeVisitor::collecting(generics, args, &mut aliases)
+                    .visit_type_mut(&mut item_const.ty);
+            }
+            _ => {}
+        }
+    }
+
+    let type_aliases = emit_aliases(&aliases, alias_vis);
+    if args.strict {
+        check_no_macro_types(
+            |finder| finder.visit_item_trait(&transformed),
+            &format!("in the trait `{}`", input.ident),
+        )?;
+    }
+
+    Ok((type_aliases, transformed))
+}
+
+fn trait_item_generics(item: &syn::TraitItem) -> Option<&Generics> {
+    match item {
+        syn::TraitItem::Fn(item_fn) => Some(&item_fn.sig.generics),
+        syn::TraitItem::Type(item_type) => Some(&item_type.generics),
+        _ => None,
+    }
+}
+
+/// Names of the generic parameters declared by any of `generics`
+fn generic_names<'a>(generics: impl IntoIterator<Item = &'a Generics>) -> HashSet<String> {
+    generics
+        .into_iter()
+        .flat_map(|generics| generics.params.iter().map(generic_param_name))
+        .collect()
+}
+
+/// Names which generated aliases must not shadow inside an `impl` block: the generic
+/// parameters of the block and of its items
+fn impl_reserved_names(input: &syn::ItemImpl) -> HashSet<String> {
+    let item_generics = input.items.iter().filter_map(|item| match item {
+        syn::ImplItem::Fn(item_fn) => Some(&item_fn.sig.generics),
+        syn::ImplItem::Type(item_type) => Some(&item_type.generics),
+        _ => None,
+    });
+    generic_names(std::iter::once(&input.generics).chain(item_generics))
+}
+
+/// Generics in scope inside an item with `inner` generics, nested in an item with `outer`
+/// generics
+fn merge_generics(outer: &Generics, inner: &Generics) -> Generics {
+    let mut merged = outer.clone();
+    merged.params.extend(inner.params.iter().cloned());
+    if let Some(inner_where) = &inner.where_clause {
+        merged
+            .make_where_clause()
+            .predicates
+            .extend(inner_where.predicates.iter().cloned());
+    }
+    merged
+}
+
+/// Remove `#[derive(...)]` attributes from `attrs`, returning the listed traits and the
+/// index of the first removed attribute among the retained ones
+fn take_derive_attrs(
+    attrs: &mut Vec<syn::Attribute>,
+) -> syn::Result<(Vec<syn::Path>, Option<usize>)> {
+    let mut derive_traits = Vec::new();
+    let mut position = None;
+    let mut retained = Vec::new();
+
+    for attr in attrs.drain(..) {
+        if attr.path().is_ident("derive") {
+            derive_traits.extend(
+                attr.parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)?,
+            );
+            position.get_or_insert(retained.len());
+        } else {
+            retained.push(attr);
+        }
+    }
+
+    *attrs = retained;
+    Ok((derive_traits, position))
+}
+
+/// Check whether the field requests an alias for its whole type with `#[macro_alias]`
+fn is_field_aliased(field: &syn::Field) -> bool {
+    field
+        .attrs
+        .iter()
+        .any(|attr| attr.path().is_ident("macro_alias"))
+}
+
+/// Check whether the field opts out of the transformation with `#[macro_derive(skip)]`
+fn is_field_skipped(field: &syn::Field) -> bool {
+    field.attrs.iter().any(|attr| {
+        attr.path().is_ident("macro_derive")
+            && matches!(attr.parse_args::<Ident>(), Ok(ident) if ident == "skip")
+    })
+}
+
+/// All fields of a struct, of every variant of an enum, or of a union
+fn all_fields(data: &Data) -> Vec<&syn::Field> {
+    match data {
+        Data::Struct(data_struct) => data_struct.fields.iter().collect(),
+        Data::Enum(data_enum) => data_enum
+            .variants
+            .iter()
+            .flat_map(|variant| variant.fields.iter())
+            .collect(),
+        Data::Union(data_union) => data_union.fields.named.iter().collect(),
+    }
+}
+
+fn all_fields_mut(data: &mut Data) -> Vec<&mut syn::Field> {
+    match data {
+        Data::Struct(data_struct) => data_struct.fields.iter_mut().collect(),
+        Data::Enum(data_enum) => data_enum
+            .variants
+            .iter_mut()
+            .flat_map(|variant| variant.fields.iter_mut())
+            .collect(),
+        Data::Union(data_union) => data_union.fields.named.iter_mut().collect(),
+    }
+}
+
+/// Add `#[schemars(with = "...")]` to the fields whose type is one of the macro types of
+/// `schemars_with(...)`, unless they already choose their schema
+fn add_schema_overrides(args: &MacroDeriveArgs, data: &mut Data) {
+    for field in all_fields_mut(data) {
+        if !matches!(field.ty, Type::Macro(_))
+            || has_schemars_override(&field.attrs)
+            || is_field_skipped(field)
+        {
+            continue;
+        }
+        let key = alias_key(&field.ty);
+        if let Some((_, schema_type)) = args.schema_types.iter().find(|(typed, _)| *typed == key) {
+            let schema_type = quote!(#schema_type).to_string();
+            field
+                .attrs
+                .push(syn::parse_quote!(#[schemars(with = #schema_type)]));
+        }
+    }
+}
+
+/// Whether `attrs` contain a `#[schemars(...)]` attribute choosing the schema of the field
+fn has_schemars_override(attrs: &[syn::Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("schemars"))
+        .any(|attr| {
+            let tokens = match &attr.meta {
+                Meta::List(list) => list.tokens.clone(),
+                _ => return false,
+            };
+            tokens.into_iter().any(|token| {
+                matches!(token, proc_macro2::TokenTree::Ident(ident)
+                    if ident == "with" || ident == "schema_with")
+            })
+        })
+}
+
+/// The predicates of the `#[cfg(...)]` attributes in `attrs`
+///
+/// Malformed predicates are left for the compiler to report on the field.
+fn cfg_predicates(attrs: &[syn::Attribute]) -> Vec<Meta> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("cfg"))
+        .filter_map(|attr| attr.parse_args().ok())
+        .collect()
+}
+
+/// The `cfg` predicates of each field of `data`, in the order of [`all_fields`], including
+/// those of the enclosing variant
+fn field_cfg_predicates(data: &Data) -> Vec<Vec<Meta>> {
+    match data {
+        Data::Enum(data_enum) => data_enum
+            .variants
+            .iter()
+            .flat_map(|variant| {
+                let variant_conditions = cfg_predicates(&variant.attrs);
+                variant.fields.iter().map(move |field| {
+                    let mut conditions = variant_conditions.clone();
+                    conditions.extend(cfg_predicates(&field.attrs));
+                    conditions
+                })
+            })
+            .collect(),
+        data => all_fields(data)
+            .into_iter()
+            .map(|field| cfg_predicates(&field.attrs))
+            .collect(),
+    }
+}
+
+/// Attributes of `input` and of all its variants and fields
+fn all_attrs_mut(input: &mut DeriveInput) -> Vec<&mut syn::Attribute> {
+    let mut attrs: Vec<_> = input.attrs.iter_mut().collect();
+    match &mut input.data {
+        Data::Enum(data_enum) => {
+            for variant in data_enum.variants.iter_mut() {
+                attrs.extend(variant.attrs.iter_mut());
+                attrs.extend(
+                    variant
+                        .fields
+                        .iter_mut()
+                        .flat_map(|field| field.attrs.iter_mut()),
+                );
+            }
+        }
+        data => attrs.extend(
+            all_fields_mut(data)
+                .into_iter()
+                .flat_map(|field| field.attrs.iter_mut()),
+        ),
+    }
+    attrs
+}
+
+/// Validate the `#[macro_derive(...)]` attributes placed on fields
+fn validate_field_attrs(data: &Data) -> syn::Result<()> {
+    for field in all_fields(data) {
+        for attr in field.attrs.iter() {
+            if attr.path().is_ident("macro_alias") {
+                attr.meta.require_path_only()?;
+                if is_field_skipped(field) {
+                    return Err(syn::Error::new_spanned(
+                        attr,
+                        "`#[macro_alias]` cannot be combined with `#[macro_derive(skip)]`",
+                    ));
+                }
+            }
+            if !attr.path().is_ident("macro_derive") {
+                continue;
+            }
+            let option: Ident = attr.parse_args()?;
+            if option != "skip" {
+                return Err(syn::Error::new_spanned(
+                    option,
+                    "unsupported field option, expected `skip`",
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Collect the macro types in `ty`, which appears in the scope of `generics`, into `aliases`
+pub fn collect_macro_types_from_type(
+    ty: &Type,
+    generics: &Generics,
+    args: &MacroDeriveArgs,
+    aliases: &mut MacroAliases,
+) {
+    MacroTypeVisitor::collecting(generics, args, aliases).visit_type_mut(&mut ty.clone());
+}
+
+/// Replace the macro types in `ty` with references to the aliases collected into `aliases`
+pub fn transform_type(ty: &mut Type, aliases: &MacroAliases, generics: &Generics) {
+    MacroTypeVisitor {
+        generics,
+        aliases: AliasSource::Lookup(aliases),
+        conditions: Vec::new(),
+        site: None,
+        location: None,
+    }
+    .visit_type_mut(ty);
+}
+
+/// Visitor replacing every macro type it reaches with a reference to its alias
+///
+/// This is the single description of the type positions the transformation looks into.
+/// When collecting, macro types without an alias get one as they are reached, so that an
+/// item is rewritten in one traversal. Macro invocations are not descended into, and the
+/// macro types nested in their arguments are only handled in `deep` mode.
+struct MacroTypeVisitor<'a> {
+    /// The generics in scope at the visited types
+    generics: &'a Generics,
+    aliases: AliasSource<'a>,
+    /// The `cfg` predicates of the visited field, which the aliases it uses are gated by
+    conditions: Vec<Meta>,
+    /// Name of the visited field, which readable names of the aliases it uses end with
+    site: Option<String>,
+    /// Description of the visited field, or of the item, for `document_aliases`
+    location: Option<String>,
+}
+
+/// The aliases a [`MacroTypeVisitor`] refers to
+enum AliasSource<'a> {
+    /// Macro types without an alias are aliased with the given options
+    Collect(&'a MacroDeriveArgs, &'a mut MacroAliases),
+    /// Only the aliases collected beforehand are used
+    Lookup(&'a MacroAliases),
+}
+
+impl<'a> MacroTypeVisitor<'a> {
+    fn collecting(
+        generics: &'a Generics,
+        args: &'a MacroDeriveArgs,
+        aliases: &'a mut MacroAliases,
+    ) -> Self {
+        MacroTypeVisitor {
+            generics,
+            aliases: AliasSource::Collect(args, aliases),
+            conditions: Vec::new(),
+            site: None,
+            location: None,
+        }
+    }
+
+    fn aliases(&self) -> &MacroAliases {
+        match &self.aliases {
+            AliasSource::Collect(_, aliases) => aliases,
+            AliasSource::Lookup(aliases) => aliases,
+        }
+    }
+
+    /// Rewrite the generics, parameters and return type of a function signature, leaving
+    /// the body of the function as is
+    fn rewrite_signature(&mut self, sig: &mut syn::Signature) {
+        self.visit_generics_mut(&mut sig.generics);
+        for input in &mut sig.inputs {
+            match input {
+                syn::FnArg::Receiver(receiver) => self.visit_type_mut(&mut receiver.ty),
+                syn::FnArg::Typed(pat_type) => self.visit_type_mut(&mut pat_type.ty),
+            }
+        }
+        self.visit_return_type_mut(&mut sig.output);
+    }
+
+    /// Replace `ty` as a whole with a reference to its alias, aliasing it first if needed
+    fn replace(&mut self, ty: &mut Type) {
+        let key = scoped_alias_key(ty, self.generics, self.aliases());
+        if let AliasSource::Collect(args, _) = self.aliases {
+            if !self.aliases().types.contains_key(&key) {
+                self.collect(key.clone(), ty, args);
+            }
+        }
+        if let AliasSource::Collect(args, aliases) = &mut self.aliases {
+            if let Some(alias) = aliases.types.get_mut(&key) {
+                alias.require(&self.conditions);
+                alias.add_attrs(&args.alias_attrs);
+                if args.document_aliases {
+                    let (name, aliased) = (alias.name.clone(), &alias.aliased);
+                    let invocation = render_source_tokens(&quote!(#aliased));
+                    document_use(aliases, &name, invocation, self.location.as_ref());
+                }
+            }
+        }
+        if let Some(alias) = self.aliases().types.get(&key) {
+            *ty = alias.reference(ty.span());
+        }
+    }
+
+    fn collect(&mut self, key: String, ty: &Type, args: &MacroDeriveArgs) {
+        let self_ty = self.aliases().self_ty.clone();
+        // `Self` cannot be used in a free-standing type alias
+        let aliased = match ty {
+            Type::Macro(type_macro) => {
+                let mut aliased = type_macro.clone();
+                if let Some(self_ty) = &self_ty {
+                    aliased.mac.tokens = substitute_self(&aliased.mac.tokens, self_ty);
+                }
+                if args.deep {
+                    aliased.mac.tokens = splice_nested_macros(&aliased.mac.tokens, self);
+                }
+                Type::Macro(aliased)
+            }
+            other => match &self_ty {
+                Some(self_ty) => syn::parse2(substitute_self(&quote!(#other), self_ty))
+                    .unwrap_or_else(|_| other.clone()),
+                None => other.clone(),
+            },
+        };
+        let generics = self.generics;
+        if let AliasSource::Collect(_, aliases) = &mut self.aliases {
+            let site = match (&self.site, ty) {
+                (Some(site), _) => site.clone(),
+                (None, Type::Macro(type_macro)) => last_ident_name(&type_macro.mac.path),
+                (None, _) => "type".to_string(),
+            };
+            let alias_name = generate_type_alias_name(&key, &site, ty, args, aliases);
+            let position = aliases.types.len();
+            aliases
+                .types
+                .insert(key, TypeAlias::new(aliased, alias_name, generics, position));
+        }
+    }
+
+    /// The expansion given by `inline(...)` for `type_macro`, located at the invocation
+    fn inlined(&self, type_macro: &syn::TypeMacro) -> Option<Type> {
+        let args = match &self.aliases {
+            AliasSource::Collect(args, _) => args,
+            AliasSource::Lookup(_) => return None,
+        };
+        let key = alias_key(&Type::Macro(type_macro.clone()));
+        let (_, expansion) = args
+            .inlined_types
+            .iter()
+            .find(|(inlined, _)| *inlined == key)?;
+        syn::parse2(respan(&quote!(#expansion), type_macro.span())).ok()
+    }
+
+    /// The expansion of `type_macro` by the rules given with `inline_rules(...)`
+    fn expanded(&self, type_macro: &syn::TypeMacro) -> Option<Type> {
+        match &self.aliases {
+            AliasSource::Collect(args, _) => {
+                rules_expansion(&type_macro.mac, &args.inline_macros, 0)
+            }
+            AliasSource::Lookup(_) => None,
+        }
+    }
+
+    /// Check whether the macro type may be moved out into an alias
+    fn is_aliasable(&self, type_macro: &syn::TypeMacro) -> bool {
+        let aliases = self.aliases();
+        // Without a type to substitute, `Self` cannot be moved out into an alias
+        if aliases.self_ty.is_none() && mentions_self(&type_macro.mac.tokens) {
+            return false;
+        }
+        match &aliases.hooks.filter {
+            Some(filter) => filter(type_macro),
+            None => true,
+        }
+    }
+}
+
+impl VisitMut for MacroTypeVisitor<'_> {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        match ty {
+            Type::Macro(type_macro) => {
+                // Inlined expansions are used as written, since visiting them could inline
+                // the same invocation again
+                if let Some(expansion) = self.inlined(type_macro) {
+                    *ty = expansion;
+                } else if let Some(expansion) = self.expanded(type_macro) {
+                    // Macros given by `inline_rules(...)` are expanded completely, so only the
+                    // other macros of the expansion are left to alias
+                    *ty = expansion;
+                    self.visit_type_mut(ty);
+                } else if self.is_aliasable(type_macro) {
+                    self.replace(ty);
+                }
+            }
+            _ => syn::visit_mut::visit_type_mut(self, ty),
+        }
+    }
+
+    fn visit_type_array_mut(&mut self, type_array: &mut syn::TypeArray) {
+        syn::visit_mut::visit_type_array_mut(self, type_array);
+        let generics = self.generics;
+        let site = self.site.clone();
+        let location = self.location.clone();
+        if let (AliasSource::Collect(args, aliases), syn::Expr::Macro(expr_macro)) =
+            (&mut self.aliases, &type_array.len)
+        {
+            // Constants cannot refer to the generic parameters of the item, so such lengths
+            // are left in place
+            let is_generic = generics.params.iter().any(|param| {
+                is_generic_param_used_in_token_stream(
+                    &expr_macro.mac.tokens,
+                    &generic_param_name(param),
+                )
+            });
+            if !is_generic && !aliases.lengths.contains_key(&type_array.len) {
+                let len = &type_array.len;
+                let key = if args.stable_api {
+                    let mut key = "[_; ".to_string();
+                    render_canonical_tokens(&quote!(#len), &mut key);
+                    key.push(']');
+                    key
+                } else {
+                    format!("[_; {}]", quote!(#len))
+                };
+                let site = site.unwrap_or_else(|| last_ident_name(&expr_macro.mac.path));
+                let const_name = generate_alias_name(&key, &site, args, aliases, len.span());
+                let position = aliases.lengths.len();
+                aliases.lengths.insert(len.clone(), (const_name, position));
+            }
+            if let (true, Some((const_name, _))) =
+                (args.document_aliases, aliases.lengths.get(&type_array.len))
+            {
+                let const_name = const_name.clone();
+                let invocation = render_source_tokens(&quote!(#expr_macro));
+                document_use(aliases, &const_name, invocation, location.as_ref());
+            }
+        }
+        if let Some((const_name, _)) = self.aliases().lengths.get(&type_array.len) {
+            let mut const_name = const_name.clone();
+            const_name.set_span(const_name.span().located_at(type_array.len.span()));
+            type_array.len = syn::parse_quote!(#const_name);
+        }
+    }
+}
+
+fn mentions_self(tokens: &TokenStream2) -> bool {
+    tokens.clone().into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => ident == "Self",
+        proc_macro2::TokenTree::Group(group) => mentions_self(&group.stream()),
+        _ => false,
+    })
+}
+
+/// Replace `Self` in `tokens` with `self_ty`
+fn substitute_self(tokens: &TokenStream2, self_ty: &TokenStream2) -> TokenStream2 {
+    use proc_macro2::{Group, TokenTree};
+
+    tokens
+        .clone()
+        .into_iter()
+        .flat_map(|token| match token {
+            TokenTree::Ident(ident) if ident == "Self" => self_ty.clone(),
+            TokenTree::Group(group) => {
+                let mut substituted =
+                    Group::new(group.delimiter(), substitute_self(&group.stream(), self_ty));
+                substituted.set_span(group.span());
+                TokenTree::Group(substituted).into()
+            }
+            other => other.into(),
+        })
+        .collect()
+}
+
+/// Locate all of `tokens` at `span`
+fn respan(tokens: &TokenStream2, span: proc_macro2::Span) -> TokenStream2 {
+    use proc_macro2::{Group, TokenTree};
+
+    tokens
+        .clone()
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Group(group) => {
+                let mut respanned = Group::new(group.delimiter(), respan(&group.stream(), span));
+                respanned.set_span(span);
+                TokenTree::Group(respanned)
+            }
+            mut other => {
+                other.set_span(other.span().located_at(span));
+                other
+            }
+        })
+        .collect()
+}
+
+/// Replace the type macros nested in `tokens` with references to their aliases, collecting
+/// them with `visitor`
+fn splice_nested_macros(tokens: &TokenStream2, visitor: &mut MacroTypeVisitor) -> TokenStream2 {
+    use proc_macro2::{Group, TokenTree};
+
+    let mut parser = |input: ParseStream| {
+        let mut spliced = TokenStream2::new();
+        while !input.is_empty() {
+            let fork = input.fork();
+            if let Ok(nested) = fork.parse::<syn::TypeMacro>() {
+                input.advance_to(&fork);
+                // Macro types which are not aliased are kept as written
+                let mut nested = Type::Macro(nested);
+                visitor.visit_type_mut(&mut nested);
+                spliced.extend(quote!(#nested));
+                continue;
+            }
+            match input.parse::<TokenTree>()? {
+                TokenTree::Group(group) => {
+                    let stream = splice_nested_macros(&group.stream(), visitor);
+                    let mut spliced_group = Group::new(group.delimiter(), stream);
+                    spliced_group.set_span(group.span());
+                    spliced.extend([TokenTree::Group(spliced_group)]);
+                }
+                other => spliced.extend([other]),
+            }
+        }
+        Ok(spliced)
+    };
+    syn::parse::Parser::parse2(&mut parser, tokens.clone()).unwrap_or_else(|_| tokens.clone())
+}
+
+/// Replace the macro types in the arguments of a helper attribute with references to their
+/// aliases, for `rewrite_attrs`
+///
+/// Besides macro types written as tokens, this covers string literals holding where
+/// predicates, as in `bound = "Pair![T]: Debug"`. Other literals are kept as written.
+fn rewrite_helper_tokens(tokens: &TokenStream2, visitor: &mut MacroTypeVisitor) -> TokenStream2 {
+    use proc_macro2::{Group, TokenTree};
+
+    splice_nested_macros(tokens, visitor)
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Group(group) => {
+                let stream = rewrite_helper_tokens(&group.stream(), visitor);
+                let mut rewritten = Group::new(group.delimiter(), stream);
+                rewritten.set_span(group.span());
+                TokenTree::Group(rewritten)
+            }
+            TokenTree::Literal(literal) => {
+                let lit =
+                    match syn::parse2::<syn::LitStr>(TokenTree::Literal(literal.clone()).into()) {
+                        Ok(lit) => lit,
+                        Err(_) => return TokenTree::Literal(literal),
+                    };
+                let parser = Punctuated::<syn::WherePredicate, Token![,]>::parse_terminated;
+                let mut predicates = match lit.parse_with(parser) {
+                    Ok(predicates) => predicates,
+                    Err(_) => return TokenTree::Literal(literal),
+                };
+                let original = predicates.clone();
+                for predicate in predicates.iter_mut() {
+                    visitor.visit_where_predicate_mut(predicate);
+                }
+                if predicates == original {
+                    // Keep the literal as written when there is nothing to replace
+                    TokenTree::Literal(literal)
+                } else {
+                    let rewritten = syn::LitStr::new(&quote!(#predicates).to_string(), lit.span());
+                    TokenTree::Literal(rewritten.token())
+                }
+            }
+            other => other,
+        })
+        .collect()
+}
+
+/// Key under which the alias generated for `ty` is stored
+///
+/// The delimiters of macro invocations are normalized, so that `Macro![T]`, `Macro!(T)` and
+/// `Macro!{T}` share one alias. The key is a rendering of the tokens which ignores spacing
+/// and invisible groups, so invocations written differently or produced by other macros
+/// are deduplicated as well.
+fn alias_key(ty: &Type) -> String {
+    struct DelimiterNormalizer;
+
+    impl VisitMut for DelimiterNormalizer {
+        fn visit_macro_mut(&mut self, mac: &mut syn::Macro) {
+            mac.delimiter = syn::MacroDelimiter::Bracket(Default::default());
+        }
+    }
+
+    let mut normalized = ty.clone();
+    DelimiterNormalizer.visit_type_mut(&mut normalized);
+    let mut key = String::new();
+    render_canonical_tokens(&quote!(#normalized), &mut key);
+    key
+}
+
+/// Key under which the alias generated for `ty` in the scope of `generics` is stored
+///
+/// An alias declares the generic parameters used by the macro type in the order of the scope
+/// it was generated in, and has `Self` substituted. It is therefore only shared with scopes
+/// which declare these parameters alike, in the same order, and have the same `Self` type.
+fn scoped_alias_key(ty: &Type, generics: &Generics, aliases: &MacroAliases) -> String {
+    let mut key = alias_key(ty);
+    let used_generic_params = get_used_generic_params(ty, generics);
+    if !used_generic_params.is_empty() {
+        let params = used_generic_params.iter().map(|param| match param {
+            syn::GenericParam::Const(const_param) => {
+                let (ident, ty) = (&const_param.ident, &const_param.ty);
+                quote!(const #ident: #ty)
+            }
+            other => {
+                let name = generic_param_name(other);
+                quote!(#name)
+            }
+        });
+        render_canonical_tokens(&quote!(for<#(#params),*>), &mut key);
+    }
+    if let Some(self_ty) = &aliases.self_ty {
+        if mentions_self(&quote!(#ty)) {
+            render_canonical_tokens(&quote!(where Self = #self_ty), &mut key);
+        }
+    }
+    key
+}
+
+/// Render `tokens` the way they are usually written, as in `Pair![&'a T]` rather than the
+/// `Pair ! [& 'a T]` of `to_string`
+fn render_source_tokens(tokens: &TokenStream2) -> String {
+    use proc_macro2::{Delimiter, Spacing, TokenTree};
+
+    let mut rendered = String::new();
+    // Whether the previous token ends a word, which the next word is separated from
+    let mut after_word = false;
+    // Whether the previous token is followed by a space whatever comes next
+    let mut after_separator = false;
+    // The previous punctuation if the current one continues it, as in `->`
+    let mut joint_with = None;
+    for token in tokens.clone() {
+        let is_word = matches!(token, TokenTree::Ident(_) | TokenTree::Literal(_));
+        if after_separator || (after_word && is_word) {
+            rendered.push(' ');
+        }
+        after_word = is_word;
+        after_separator = false;
+        if !matches!(token, TokenTree::Punct(_)) {
+            joint_with = None;
+        }
+        match token {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Brace => ("{ ", " }"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::None => ("", ""),
+                };
+                rendered.push_str(open);
+                rendered.push_str(&render_source_tokens(&group.stream()));
+                rendered.push_str(close);
+            }
+            TokenTree::Punct(punct) => {
+                let (ch, joint) = (punct.as_char(), punct.spacing() == Spacing::Joint);
+                match (ch, joint_with) {
+                    (',' | ';', _) => after_separator = true,
+                    // The second character of `->`, `=>` or `::`
+                    (_, Some(_)) => after_separator = !joint && ch != ':',
+                    ('-' | '=', None) if joint => rendered.push(' '),
+                    ('=' | '+' | '|', None) => {
+                        rendered.push(' ');
+                        after_separator = true;
+                    }
+                    (':', None) => after_separator = !joint,
+                    _ => {}
+                }
+                joint_with = if joint && ch != '\'' { Some(ch) } else { None };
+                rendered.push(ch);
+            }
+            other => rendered.push_str(&other.to_string()),
+        }
+    }
+    rendered
+}
+
+/// Render `tokens` into `rendered`, ignoring spacing and invisible groups
+fn render_canonical_tokens(tokens: &TokenStream2, rendered: &mut String) {
+    use proc_macro2::{Delimiter, TokenTree};
+
+    for token in tokens.clone() {
+        match token {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::None => {
+                        render_canonical_tokens(&group.stream(), rendered);
+                        continue;
+                    }
+                };
+                rendered.push_str(open);
+                rendered.push(' ');
+                render_canonical_tokens(&group.stream(), rendered);
+                rendered.push_str(close);
+                rendered.push(' ');
+            }
+            other => {
+                rendered.push_str(&other.to_string());
+                rendered.push(' ');
+            }
+        }
+    }
+}
+
+/// Generate the name of the item generated for the invocation identified by `key`, used at
+/// `site`
+///
+/// Names which are already taken are disambiguated with a numeric suffix, rather than
+/// relying on the hash or the random characters to be unique.
+fn generate_alias_name(
+    key: &str,
+    site: &str,
+    args: &MacroDeriveArgs,
+    aliases: &mut MacroAliases,
+    span: proc_macro2::Span,
+) -> Ident {
+    let base = if args.stable_api {
+        // The compiler renders the seed with a spacing which may change between releases
+        let mut seed = String::new();
+        render_canonical_tokens(&aliases.seed, &mut seed);
+        generate_stable_type_name(&args.prefix, &format!("{}{}", seed, key), span)
+    } else if args.readable_names || args.type_path.is_some() {
+        // Reflected types are registered by name, which should read well and never change
+        generate_readable_type_name(&args.prefix, &aliases.scope, site, span)
+    } else if args.stable_names {
+        generate_stable_type_name(&args.prefix, &format!("{} {}", aliases.seed, key), span)
+    } else {
+        generate_random_type_name(&args.prefix, span)
+    };
+    reserve_name(base, aliases)
+}
+
+/// Generate the name of the alias for `ty`, using the naming callback if one is registered
+fn generate_type_alias_name(
+    key: &str,
+    site: &str,
+    ty: &Type,
+    args: &MacroDeriveArgs,
+    aliases: &mut MacroAliases,
+) -> Ident {
+    match &aliases.hooks.name {
+        Some(name) => {
+            let base = name(ty);
+            reserve_name(base, aliases)
+        }
+        None => generate_alias_name(key, site, args, aliases, ty.span()),
+    }
+}
+
+/// Take `base`, or `base` followed by the smallest number making it unique
+fn reserve_name(base: Ident, aliases: &mut MacroAliases) -> Ident {
+    let mut name = base.clone();
+    let mut suffix = 0;
+    while !aliases.reserved_names.insert(name.to_string()) {
+        suffix += 1;
+        name = Ident::new(&format!("{}{}", base, suffix), base.span());
+    }
+    name
+}
+
+/// Names which generated aliases must not take: the item, its generic parameters and its
+/// fields
+fn reserved_names(input: &DeriveInput) -> HashSet<String> {
+    let mut names: HashSet<String> = input
+        .generics
+        .params
+        .iter()
+        .map(generic_param_name)
+        .collect();
+    names.insert(input.ident.to_string());
+    names.extend(
+        all_fields(&input.data)
+            .into_iter()
+            .filter_map(|field| field.ident.as_ref())
+            .map(Ident::to_string),
+    );
+    names
+}
+
+/// Generate a name made of `prefix`, the item `scope` and the `site` of the invocation, as in
+/// `__TypeMacroAlias_Line_from`
+fn generate_readable_type_name(
+    prefix: &str,
+    scope: &str,
+    site: &str,
+    span: proc_macro2::Span,
+) -> Ident {
+    let mut name = prefix.to_string();
+    for part in [scope, site] {
+        let part = part.trim_matches('_');
+        if !part.is_empty() {
+            name.push('_');
+            name.push_str(part);
+        }
+    }
+    Ident::new(&name, proc_macro2::Span::call_site().located_at(span))
+}
+
+/// Last identifier of `path`, such as the name of an invoked macro without its module
+fn last_ident_name(path: &syn::Path) -> String {
+    match path.segments.last() {
+        Some(segment) => segment.ident.unraw().to_string(),
+        None => String::new(),
+    }
+}
+
+/// Generate a name starting with `prefix` which only depends on `seed`
+///
+/// The seed is hashed with FNV-1a rather than the std hasher, whose output may change
+/// between Rust releases.
+fn generate_stable_type_name(prefix: &str, seed: &str, span: proc_macro2::Span) -> Ident {
+    let hash = seed.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+
+    Ident::new(
+        &format!("{}{:016x}", prefix, hash),
+        proc_macro2::Span::call_site().located_at(span),
+    )
+}
+
+/// Generate a unique name starting with `prefix`
+///
+/// The name resolves at the call site, but is located at `span` so that diagnostics about
+/// the alias point at the macro invocation it was generated for.
+fn generate_random_type_name(prefix: &str, span: proc_macro2::Span) -> Ident {
+    let random_suffix: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(12)
+        .map(char::from)
+        .collect();
+
+    Ident::new(
+        &format!("{}{}", prefix, random_suffix),
+        proc_macro2::Span::call_site().located_at(span),
+    )
+}
+
+/// The parameters of `generics` used by `macro_type`, in their declaration order
+pub fn get_used_generic_params(macro_type: &Type, generics: &Generics) -> Vec<syn::GenericParam> {
+    // Analyze which specific generic parameters are used in the macro type
+    let param_names: Vec<String> = generics.params.iter().map(generic_param_name).collect();
+
+    let used_names = match macro_type {
+        Type::Macro(type_macro) => used_names_in_macro_tokens(&type_macro.mac.tokens, &param_names),
+        // Field types aliased with `#[macro_alias]` are searched as a whole
+        other => {
+            let mut visitor = GenericUsageVisitor::new(&param_names);
+            visitor.visit_type(other);
+            visitor.used_names
+        }
+    };
+
+    generics
+        .params
+        .iter()
+        .filter(|param| used_names.contains(&generic_param_name(param)))
+        .cloned()
+        .collect()
+}
+
+/// Find the generic parameters used in the tokens of a macro invocation
+///
+/// Most type macros take types as arguments, so the tokens are first parsed as a
+/// comma-separated list of types and searched structurally. This keeps identifiers in
+/// non-type positions (trailing path segments, argument names of `fn` types, ...) from
+/// being mistaken for parameters. Other tokens fall back to plain token scanning.
+fn used_names_in_macro_tokens(tokens: &TokenStream2, param_names: &[String]) -> Vec<String> {
+    let parser = Punctuated::<Type, Token![,]>::parse_terminated;
+    match syn::parse::Parser::parse2(parser, tokens.clone()) {
+        Ok(types) => {
+            let mut visitor = GenericUsageVisitor::new(param_names);
+            for ty in &types {
+                visitor.visit_type(ty);
+            }
+            visitor.used_names
+        }
+        Err(_) => param_names
+            .iter()
+            .filter(|name| is_generic_param_used_in_token_stream(tokens, name))
+            .cloned()
+            .collect(),
+    }
+}
+
+/// Visitor recording which generic parameters a parsed type refers to
+struct GenericUsageVisitor<'a> {
+    param_names: &'a [String],
+    used_names: Vec<String>,
+    /// Lifetimes introduced by enclosing `for<...>` binders
+    bound_lifetimes: Vec<String>,
+}
+
+impl<'a> GenericUsageVisitor<'a> {
+    fn new(param_names: &'a [String]) -> Self {
+        GenericUsageVisitor {
+            param_names,
+            used_names: Vec::new(),
+            bound_lifetimes: Vec::new(),
+        }
+    }
+
+    /// Visit a node with the lifetimes of its `for<...>` binder in scope
+    fn with_bound_lifetimes(
+        &mut self,
+        binder: Option<&syn::BoundLifetimes>,
+        visit: impl FnOnce(&mut Self),
+    ) {
+        let scope_len = self.bound_lifetimes.len();
+        if let Some(binder) = binder {
+            for param in &binder.lifetimes {
+                self.bound_lifetimes.push(generic_param_name(param));
+            }
+        }
+        visit(self);
+        self.bound_lifetimes.truncate(scope_len);
+    }
+
+    fn mark_used(&mut self, name: String) {
+        if self.param_names.contains(&name) && !self.used_names.contains(&name) {
+            self.used_names.push(name);
+        }
+    }
+
+    /// Mark the parameter a possibly qualified path starts with
+    ///
+    /// In `<X as Trait>::Item` the self type `X` is visited on its own, and the path holds
+    /// `Trait::Item` where only the trait part may start with a parameter (`<X as T::Assoc>`).
+    /// In `<X>::Item` the path consists of associated items only.
+    fn mark_qualified_path(&mut self, qself: Option<&syn::QSelf>, path: &syn::Path) {
+        match qself {
+            Some(qself) if qself.position == 0 => {}
+            _ => self.mark_path(path),
+        }
+    }
+
+    /// A parameter can only be referred to by the first segment of a relative path
+    fn mark_path(&mut self, path: &syn::Path) {
+        if path.leading_colon.is_none() {
+            if let Some(segment) = path.segments.first() {
+                self.mark_used(segment.ident.to_string());
+            }
+        }
+    }
+}
+
+impl<'ast, 'a> Visit<'ast> for GenericUsageVisitor<'a> {
+    fn visit_type_path(&mut self, type_path: &'ast syn::TypePath) {
+        self.mark_qualified_path(type_path.qself.as_ref(), &type_path.path);
+        syn::visit::visit_type_path(self, type_path);
+    }
+
+    fn visit_expr_path(&mut self, expr_path: &'ast syn::ExprPath) {
+        // Const parameters used in expressions such as array lengths
+        self.mark_qualified_path(expr_path.qself.as_ref(), &expr_path.path);
+        syn::visit::visit_expr_path(self, expr_path);
+    }
+
+    fn visit_lifetime(&mut self, lifetime: &'ast syn::Lifetime) {
+        let name = lifetime.to_string();
+        if !self.bound_lifetimes.contains(&name) {
+            self.mark_used(name);
+        }
+    }
+
+    fn visit_type_bare_fn(&mut self, bare_fn: &'ast syn::TypeBareFn) {
+        self.with_bound_lifetimes(bare_fn.lifetimes.as_ref(), |visitor| {
+            syn::visit::visit_type_bare_fn(visitor, bare_fn)
+        });
+    }
+
+    fn visit_trait_bound(&mut self, bound: &'ast syn::TraitBound) {
+        self.with_bound_lifetimes(bound.lifetimes.as_ref(), |visitor| {
+            syn::visit::visit_trait_bound(visitor, bound)
+        });
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        // Nested macro invocations are opaque, so search their tokens
+        for name in used_names_in_macro_tokens(&mac.tokens, self.param_names) {
+            self.mark_used(name);
+        }
+    }
+}
+
+fn is_generic_param_used_in_token_stream(
+    tokens: &proc_macro2::TokenStream,
+    identifier: &str,
+) -> bool {
+    use proc_macro2::TokenTree;
+
+    // Scan the flattened token tree, so that a lifetime is recognized regardless of how
+    // deeply it is nested in groups (brackets, braces, parentheses)
+    let tokens_vec = flatten_token_stream(tokens);
+
+    // Lifetimes introduced by `for<...>` binders are not generic parameters of the item.
+    // Without parsing their scope is unknown, so they are excluded everywhere.
+    if identifier.starts_with('\'') && hrtb_lifetimes(&tokens_vec).iter().any(|l| l == identifier) {
+        return false;
+    }
+
+    let mut tokens_iter = tokens_vec.iter().peekable();
+
+    while let Some(token) = tokens_iter.next() {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '\'' => {
+                // Handle lifetimes: ' followed by an identifier. The identifier is consumed
+                // so that `'a` is never mistaken for a type parameter named `a`.
+                if let Some(TokenTree::Ident(ident)) = tokens_iter.peek() {
+                    if format!("'{}", ident) == identifier {
+                        return true;
+                    }
+                    tokens_iter.next();
+                }
+            }
+            // Handle regular type parameters and const parameters
+            TokenTree::Ident(ident) if *ident == identifier => return true,
+            // Literals and other punctuation don't contain type parameters
+            _ => {}
+        }
+    }
+
+    false
+}
+
+/// Collect the lifetimes declared by `for<...>` binders in flattened tokens
+fn hrtb_lifetimes(tokens: &[proc_macro2::TokenTree]) -> Vec<String> {
+    use proc_macro2::TokenTree;
+
+    let mut lifetimes = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let is_binder = matches!(&tokens[i], TokenTree::Ident(ident) if ident == "for")
+            && matches!(tokens.get(i + 1), Some(TokenTree::Punct(p)) if p.as_char() == '<');
+        if !is_binder {
+            i += 1;
+            continue;
+        }
+
+        i += 2;
+        while i < tokens.len() {
+            match (&tokens[i], tokens.get(i + 1)) {
+                (TokenTree::Punct(p), _) if p.as_char() == '>' => break,
+                (TokenTree::Punct(p), Some(TokenTree::Ident(ident))) if p.as_char() == '\'' => {
+                    lifetimes.push(format!("'{}", ident));
+                    i += 1;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+    lifetimes
+}
+
+/// Flatten a token stream into its leaf tokens, dropping group delimiters
+fn flatten_token_stream(tokens: &TokenStream2) -> Vec<proc_macro2::TokenTree> {
+    use proc_macro2::TokenTree;
+
+    let mut flattened = Vec::new();
+    for token in tokens.clone() {
+        match token {
+            TokenTree::Group(group) => flattened.extend(flatten_token_stream(&group.stream())),
+            other => flattened.push(other),
+        }
+    }
+    flattened
+}
+
+/// The names of the parameters of `generics` mentioned in `aliased` but not declared on its
+/// alias, because they only appear in a non-type position as in `Id![name: T]`, each with
+/// the span of its first mention
+fn filtered_generic_params(
+    aliased: &Type,
+    generics: &Generics,
+    used_params: &[syn::GenericParam],
+) -> Vec<(String, proc_macro2::Span)> {
+    let tokens = quote!(#aliased);
+    let flattened = flatten_token_stream(&tokens);
+    generics
+        .params
+        .iter()
+        .filter(|param| !used_params.contains(param))
+        .map(generic_param_name)
+        .filter(|name| is_generic_param_used_in_token_stream(&tokens, name))
+        .map(|name| {
+            let ident = name.trim_start_matches('\'');
+            let span = flattened
+                .iter()
+                .find(|token| matches!(token, proc_macro2::TokenTree::Ident(i) if i == ident))
+                .map_or_else(|| aliased.span(), |token| token.span());
+            (name, span)
+        })
+        .collect()
+}
+
+/// Check whether a bound can be kept on an alias declaring `used_params`, warning if not
+fn keep_alias_bound(
+    bound: &TokenStream2,
+    used_params: &[syn::GenericParam],
+    generics: &Generics,
+) -> bool {
+    if mentions_undeclared_param(bound, used_params, generics) {
+        emit_warning(
+            bound.span(),
+            &format!(
+                "bound `{}` is dropped from a generated alias, since it mentions generic \
+                 parameters not used by the macro invocation",
+                render_source_tokens(bound)
+            ),
+        );
+        false
+    } else {
+        true
+    }
+}
+
+/// Check whether a bound mentions generic parameters which are not declared on the alias
+///
+/// A bound such as `T: Into<U>` would need `U` on the alias, but declaring it is rejected
+/// because `U` does not occur in the aliased type, so such bounds are dropped instead.
+fn mentions_undeclared_param(
+    bound: &TokenStream2,
+    used_params: &[syn::GenericParam],
+    generics: &Generics,
+) -> bool {
+    generics
+        .params
+        .iter()
+        .filter(|param| !used_params.contains(param))
+        .any(|param| is_generic_param_used_in_token_stream(bound, &generic_param_name(param)))
+}
+
+fn generic_param_name(param: &syn::GenericParam) -> String {
+    match param {
+        syn::GenericParam::Type(type_param) => type_param.ident.to_string(),
+        syn::GenericParam::Lifetime(lifetime_param) => lifetime_param.lifetime.to_string(),
+        syn::GenericParam::Const(const_param) => const_param.ident.to_string(),
+    }
+}
+
+fn create_filtered_generics(used_params: &[syn::GenericParam]) -> syn::Generics {
+    // Create a new Generics struct containing only the used parameters
+    let mut generics = syn::Generics::default();
+
+    // Declarations must list lifetimes before types before consts. The sort is stable, so
+    // the relative order within each kind is kept and the use site (which is built from
+    // the same Generics) passes the arguments in the matching order.
+    let mut sorted_params = used_params.to_vec();
+    sorted_params.sort_by_key(|param| match param {
+        syn::GenericParam::Lifetime(_) => 0,
+        syn::GenericParam::Type(_) => 1,
+        syn::GenericParam::Const(_) => 2,
+    });
+
+    for param in sorted_params {
+        generics.params.push(param);
+    }
+
+    generics
+}
+
+/// Invocations expanding to further inlined macros deeper than this are aliased instead
+const INLINE_EXPANSION_LIMIT: usize = 64;
+
+/// Invocations whose matching keeps more partial matches than this at once are aliased instead
+const INLINE_MATCH_LIMIT: usize = 256;
+
+/// `macro_rules! name { rules }`, optionally preceded by attributes
+#[derive(Clone)]
+struct MacroRulesDef {
+    attrs: Vec<syn::Attribute>,
+    keyword: Ident,
+    name: Ident,
+    body: proc_macro2::Group,
+    rules: Vec<MacroRule>,
+}
+
+impl Parse for MacroRulesDef {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let keyword: Ident = input.parse()?;
+        if keyword != "macro_rules" {
+            return Err(syn::Error::new_spanned(keyword, "expected `macro_rules!`"));
+        }
+        input.parse::<Token![!]>()?;
+        let name: Ident = input.parse()?;
+        let body = match input.parse()? {
+            proc_macro2::TokenTree::Group(group) => group,
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "expected the rules of the macro",
+                ))
+            }
+        };
+        if body.delimiter() != proc_macro2::Delimiter::Brace {
+            input.parse::<Token![;]>()?;
+        }
+        let rules = syn::parse::Parser::parse2(parse_macro_rules, body.stream())?;
+        Ok(MacroRulesDef {
+            attrs,
+            keyword,
+            name,
+            body,
+            rules,
+        })
+    }
+}
+
+/// An arm of `macro_rules!`: `(matcher) => { transcriber }`
+#[derive(Clone)]
+struct MacroRule {
+    matcher: Vec<MatcherItem>,
+    transcriber: TokenStream2,
+}
+
+/// A part of the matcher of a `macro_rules!` arm
+#[derive(Clone)]
+enum MatcherItem {
+    /// An identifier, punctuation or literal matched as written
+    Token(proc_macro2::TokenTree),
+    Group(proc_macro2::Delimiter, Vec<MatcherItem>),
+    /// `$name:kind`
+    Fragment(String, String),
+    /// `$(...) separator? operator`
+    Repeat(Vec<MatcherItem>, Option<proc_macro2::TokenTree>, char),
+}
+
+/// Tokens matched by a metavariable, nested once per repetition it appears in
+#[derive(Clone)]
+enum Binding {
+    Single(TokenStream2),
+    Repeated(Vec<Binding>),
+}
+
+type Bindings = HashMap<String, Binding>;
+
+fn parse_macro_rules(input: ParseStream) -> syn::Result<Vec<MacroRule>> {
+    let mut rules = Vec::new();
+    while !input.is_empty() {
+        let matcher = match input.parse()? {
+            proc_macro2::TokenTree::Group(group) => parse_matcher(group.stream())?,
+            other => return Err(syn::Error::new_spanned(other, "expected a matcher")),
+        };
+        input.parse::<Token![=>]>()?;
+        let transcriber = match input.parse()? {
+            proc_macro2::TokenTree::Group(group) => group.stream(),
+            other => return Err(syn::Error::new_spanned(other, "expected a transcriber")),
+        };
+        rules.push(MacroRule {
+            matcher,
+            transcriber,
+        });
+        if !input.is_empty() {
+            input.parse::<Token![;]>()?;
+        }
+    }
+    Ok(rules)
+}
+
+fn parse_matcher(tokens: TokenStream2) -> syn::Result<Vec<MatcherItem>> {
+    use proc_macro2::{Delimiter, TokenTree};
+
+    const FRAGMENTS: &[&str] = &[
+        "block",
+        "expr",
+        "ident",
+        "item",
+        "lifetime",
+        "literal",
+        "meta",
+        "pat",
+        "pat_param",
+        "path",
+        "stmt",
+        "tt",
+        "ty",
+        "vis",
+    ];
+    let mut tokens = tokens.into_iter().peekable();
+    let mut items = Vec::new();
+    while let Some(token) = tokens.next() {
+        let item = match token {
+            TokenTree::Punct(dollar) if dollar.as_char() == '$' => match tokens.next() {
+                Some(TokenTree::Ident(name)) => {
+                    let kind = match (tokens.next(), tokens.next()) {
+                        (Some(TokenTree::Punct(colon)), Some(TokenTree::Ident(kind)))
+                            if colon.as_char() == ':'
+                                && FRAGMENTS.contains(&kind.to_string().as_str()) =>
+                        {
+                            kind.to_string()
+                        }
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                &name,
+                                "expected a fragment specifier such as `$name:ty`",
+                            ))
+                        }
+                    };
+                    MatcherItem::Fragment(name.to_string(), kind)
+                }
+                Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => {
+                    let inner = parse_matcher(group.stream())?;
+                    let is_operator = |token: &TokenTree| matches!(token, TokenTree::Punct(op) if matches!(op.as_char(), '*' | '+' | '?'));
+                    let (separator, operator) = match tokens.next() {
+                        Some(TokenTree::Punct(op))
+                            if is_operator(&TokenTree::Punct(op.clone())) =>
+                        {
+                            (None, op.as_char())
+                        }
+                        Some(separator) => match tokens.next() {
+                            Some(TokenTree::Punct(op)) if matches!(op.as_char(), '*' | '+') => {
+                                (Some(separator), op.as_char())
+                            }
+                            _ => {
+                                return Err(syn::Error::new_spanned(
+                                    separator,
+                                    "expected a repetition operator `*` or `+`",
+                                ))
+                            }
+                        },
+                        None => {
+                            return Err(syn::Error::new_spanned(
+                                group,
+                                "expected a repetition operator",
+                            ))
+                        }
+                    };
+                    MatcherItem::Repeat(inner, separator, operator)
+                }
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        dollar,
+                        "expected a metavariable or a repetition",
+                    ))
+                }
+            },
+            TokenTree::Group(group) => {
+                MatcherItem::Group(group.delimiter(), parse_matcher(group.stream())?)
+            }
+            other => MatcherItem::Token(other),
+        };
+        items.push(item);
+    }
+    Ok(items)
+}
+
+/// Any number of `macro_rules!` definitions
+fn parse_macro_rules_defs(input: ParseStream) -> syn::Result<Vec<MacroRulesDef>> {
+    let mut defs = Vec::new();
+    while !input.is_empty() {
+        defs.push(input.parse()?);
+    }
+    Ok(defs)
+}
+
+/// Expansion of `mac` by the rules of its definition among `macros`, in which the invocations
+/// of the other macros of `macros` are expanded in turn
+///
+/// Returns `None` if the macro is not in `macros`, if no rule matches, if the first matching
+/// rule matches in more than one way, which `macro_rules!` rejects as ambiguous, if matching
+/// exceeds [`INLINE_MATCH_LIMIT`], if the expansion is not a type or if one of the nested
+/// invocations cannot be expanded.
+fn rules_expansion(mac: &syn::Macro, macros: &[MacroRulesDef], depth: usize) -> Option<Type> {
+    struct NestedExpander<'a> {
+        macros: &'a [MacroRulesDef],
+        depth: usize,
+        failed: bool,
+    }
+
+    impl VisitMut for NestedExpander<'_> {
+        fn visit_type_mut(&mut self, ty: &mut Type) {
+            match ty {
+                Type::Macro(type_macro) => {
+                    if find_macro_rules(&type_macro.mac, self.macros).is_some() {
+                        match rules_expansion(&type_macro.mac, self.macros, self.depth) {
+                            Some(expansion) => *ty = expansion,
+                            None => self.failed = true,
+                        }
+                    }
+                }
+                _ => syn::visit_mut::visit_type_mut(self, ty),
+            }
+        }
+    }
+
+    if depth > INLINE_EXPANSION_LIMIT {
+        return None;
+    }
+    let rules = &find_macro_rules(mac, macros)?.rules;
+    let tokens: Vec<_> = mac.tokens.clone().into_iter().collect();
+    // Like `macro_rules!`, the first rule whose matcher matches is used, and an ambiguous
+    // matcher fails the invocation rather than passing it on to the next rule
+    let mut matched = None;
+    for rule in rules {
+        let mut complete = match_sequence(&rule.matcher, &tokens, 0)?
+            .into_iter()
+            .filter(|(end, _)| *end == tokens.len());
+        if let Some((_, bindings)) = complete.next() {
+            if complete.next().is_some() {
+                return None;
+            }
+            matched = Some((rule, bindings));
+            break;
+        }
+    }
+    let (rule, bindings) = matched?;
+    let expansion = transcribe(&rule.transcriber, &bindings, mac.span())?;
+    let mut ty = syn::parse2(expansion).ok()?;
+    let mut expander = NestedExpander {
+        macros,
+        depth: depth + 1,
+        failed: false,
+    };
+    expander.visit_type_mut(&mut ty);
+    if expander.failed {
+        None
+    } else {
+        Some(ty)
+    }
+}
+
+/// The definition among `macros` of the macro invoked by `mac`, which is named by a single
+/// identifier since `macro_rules!` macros in scope are not invoked through paths
+fn find_macro_rules<'a>(
+    mac: &syn::Macro,
+    macros: &'a [MacroRulesDef],
+) -> Option<&'a MacroRulesDef> {
+    let name = mac.path.get_ident()?;
+    macros.iter().find(|def| def.name == *name)
+}
+
+/// All ways in which `items` match a prefix of `tokens[start..]`, with the position after the
+/// matched tokens
+///
+/// Returns `None` if more than [`INLINE_MATCH_LIMIT`] partial matches are kept at once.
+fn match_sequence(
+    items: &[MatcherItem],
+    tokens: &[proc_macro2::TokenTree],
+    start: usize,
+) -> Option<Vec<(usize, Bindings)>> {
+    let mut states = vec![(start, Bindings::new())];
+    for item in items {
+        let mut next = Vec::new();
+        for (position, bindings) in states {
+            for (end, matched) in match_item(item, tokens, position)? {
+                let mut bindings = bindings.clone();
+                bindings.extend(matched);
+                next.push((end, bindings));
+            }
+        }
+        if next.len() > INLINE_MATCH_LIMIT {
+            return None;
+        }
+        states = next;
+    }
+    Some(states)
+}
+
+fn match_item(
+    item: &MatcherItem,
+    tokens: &[proc_macro2::TokenTree],
+    position: usize,
+) -> Option<Vec<(usize, Bindings)>> {
+    use proc_macro2::TokenTree;
+
+    Some(match item {
+        MatcherItem::Token(expected) => match tokens.get(position) {
+            Some(token) if token.to_string() == expected.to_string() => {
+                vec![(position + 1, Bindings::new())]
+            }
+            _ => Vec::new(),
+        },
+        // Every complete match of the content is kept, so that ambiguities inside are seen
+        MatcherItem::Group(delimiter, inner) => match tokens.get(position) {
+            Some(TokenTree::Group(group)) if group.delimiter() == *delimiter => {
+                let content: Vec<_> = group.stream().into_iter().collect();
+                match_sequence(inner, &content, 0)?
+                    .into_iter()
+                    .filter(|(end, _)| *end == content.len())
+                    .map(|(_, bindings)| (position + 1, bindings))
+                    .collect()
+            }
+            _ => Vec::new(),
+        },
+        MatcherItem::Fragment(name, kind) => match match_fragment(kind, &tokens[position..]) {
+            Some((len, matched)) => {
+                let bindings = std::iter::once((name.clone(), Binding::Single(matched))).collect();
+                vec![(position + len, bindings)]
+            }
+            None => Vec::new(),
+        },
+        MatcherItem::Repeat(inner, separator, operator) => {
+            let mut names = Vec::new();
+            matcher_names(inner, &mut names);
+            let repeated = |iterations: &[Bindings]| -> Bindings {
+                names
+                    .iter()
+                    .map(|name| {
+                        let values = iterations
+                            .iter()
+                            .filter_map(|bindings| bindings.get(name).cloned())
+                            .collect();
+                        (name.clone(), Binding::Repeated(values))
+                    })
+                    .collect()
+            };
+            let mut matches = Vec::new();
+            if *operator != '+' {
+                matches.push((position, repeated(&[])));
+            }
+            let mut frontier: Vec<(usize, Vec<Bindings>)> = vec![(position, Vec::new())];
+            while !frontier.is_empty() {
+                let mut next = Vec::new();
+                for (start, iterations) in frontier {
+                    let mut from = start;
+                    if let (false, Some(separator)) = (iterations.is_empty(), separator) {
+                        match tokens.get(from) {
+                            Some(token) if token.to_string() == separator.to_string() => from += 1,
+                            _ => continue,
+                        }
+                    }
+                    for (end, bindings) in match_sequence(inner, tokens, from)? {
+                        // An iteration must consume tokens, or it would repeat forever
+                        if end == start {
+                            continue;
+                        }
+                        let mut iterations = iterations.clone();
+                        iterations.push(bindings);
+                        matches.push((end, repeated(&iterations)));
+                        if *operator != '?' {
+                            next.push((end, iterations));
+                        }
+                    }
+                }
+                if matches.len() > INLINE_MATCH_LIMIT {
+                    return None;
+                }
+                frontier = next;
+            }
+            matches
+        }
+    })
+}
+
+/// Names of the metavariables bound by `items`
+fn matcher_names(items: &[MatcherItem], names: &mut Vec<String>) {
+    for item in items {
+        match item {
+            MatcherItem::Token(_) => {}
+            MatcherItem::Fragment(name, _) => names.push(name.clone()),
+            MatcherItem::Group(_, inner) | MatcherItem::Repeat(inner, _, _) => {
+                matcher_names(inner, names)
+            }
+        }
+    }
+}
+
+/// Number of leading `tokens` making up a fragment of `kind`, with the matched tokens
+///
+/// Fragments other than single tokens are wrapped in an invisible group, so that they keep
+/// their meaning wherever they are substituted, as with `macro_rules!`.
+fn match_fragment(kind: &str, tokens: &[proc_macro2::TokenTree]) -> Option<(usize, TokenStream2)> {
+    use proc_macro2::{Delimiter, Group, TokenTree};
+
+    let parser = |input: ParseStream| {
+        match kind {
+            "block" => drop(input.parse::<syn::Block>()?),
+            "expr" => drop(input.parse::<syn::Expr>()?),
+            "ident" => drop(input.call(Ident::parse_any)?),
+            "item" => drop(input.parse::<syn::Item>()?),
+            "lifetime" => drop(input.parse::<syn::Lifetime>()?),
+            "literal" => {
+                if input.peek(Token![-]) {
+                    input.parse::<Token![-]>()?;
+                }
+                drop(input.parse::<syn::Lit>()?)
+            }
+            "meta" => drop(input.parse::<Meta>()?),
+            "pat" => drop(input.call(syn::Pat::parse_multi_with_leading_vert)?),
+            "pat_param" => drop(input.call(syn::Pat::parse_single)?),
+            "path" => drop(input.parse::<syn::Path>()?),
+            "stmt" => drop(input.parse::<syn::Stmt>()?),
+            "tt" => drop(input.parse::<TokenTree>()?),
+            "ty" => drop(input.parse::<Type>()?),
+            _ => drop(input.parse::<syn::Visibility>()?),
+        }
+        Ok(input.parse::<TokenStream2>()?.into_iter().count())
+    };
+    let remaining = syn::parse::Parser::parse2(parser, tokens.iter().cloned().collect()).ok()?;
+    let len = tokens.len() - remaining;
+    // `_` is not an identifier for `macro_rules!`
+    if kind == "ident" && tokens[0].to_string() == "_" {
+        return None;
+    }
+    let matched: TokenStream2 = tokens[..len].iter().cloned().collect();
+    if len == 1 || matches!(kind, "ident" | "lifetime" | "literal" | "tt" | "vis") {
+        Some((len, matched))
+    } else {
+        let group = Group::new(Delimiter::None, matched);
+        Some((len, std::iter::once(TokenTree::Group(group)).collect()))
+    }
+}
+
+/// Substitute the metavariables of `transcriber` by `bindings`, giving the other tokens the
+/// location of `span`
+///
+/// Returns `None` for metavariables which are not bound at their depth of repetition and for
+/// `$crate`, which only the compiler can resolve.
+fn transcribe(
+    transcriber: &TokenStream2,
+    bindings: &Bindings,
+    span: proc_macro2::Span,
+) -> Option<TokenStream2> {
+    use proc_macro2::{Delimiter, Group, TokenTree};
+
+    let tokens: Vec<_> = transcriber.clone().into_iter().collect();
+    let mut transcribed = TokenStream2::new();
+    let mut index = 0;
+    while index < tokens.len() {
+        match (&tokens[index], tokens.get(index + 1)) {
+            (TokenTree::Punct(dollar), Some(TokenTree::Ident(name))) if dollar.as_char() == '$' => {
+                match bindings.get(&name.to_string())? {
+                    Binding::Single(matched) => transcribed.extend(matched.clone()),
+                    Binding::Repeated(_) => return None,
+                }
+                index += 2;
+            }
+            (TokenTree::Punct(dollar), Some(TokenTree::Group(group)))
+                if dollar.as_char() == '$' && group.delimiter() == Delimiter::Parenthesis =>
+            {
+                let (separator, len) = match tokens.get(index + 2)? {
+                    TokenTree::Punct(op) if matches!(op.as_char(), '*' | '+' | '?') => (None, 3),
+                    separator => (Some(separator.clone()), 4),
+                };
+                let mut names = Vec::new();
+                transcriber_names(&group.stream(), &mut names);
+                let counts: Vec<_> = names
+                    .iter()
+                    .filter_map(|name| match bindings.get(name) {
+                        Some(Binding::Repeated(values)) => Some(values.len()),
+                        _ => None,
+                    })
+                    .collect();
+                let count = *counts.first()?;
+                if counts.iter().any(|other| *other != count) {
+                    return None;
+                }
+                for iteration in 0..count {
+                    if let (true, Some(separator)) = (iteration > 0, &separator) {
+                        let mut separator = separator.clone();
+                        separator.set_span(separator.span().located_at(span));
+                        transcribed.extend(std::iter::once(separator));
+                    }
+                    let nested = bindings
+                        .iter()
+                        .filter_map(|(name, binding)| match binding {
+                            Binding::Single(_) => Some((name.clone(), binding.clone())),
+                            Binding::Repeated(values) => {
+                                Some((name.clone(), values.get(iteration)?.clone()))
+                            }
+                        })
+                        .collect();
+                    transcribed.extend(transcribe(&group.stream(), &nested, span)?);
+                }
+                index += len;
+            }
+            (TokenTree::Group(group), _) => {
+                let mut transcribed_group = Group::new(
+                    group.delimiter(),
+                    transcribe(&group.stream(), bindings, span)?,
+                );
+                transcribed_group.set_span(span);
+                transcribed.extend(std::iter::once(TokenTree::Group(transcribed_group)));
+                index += 1;
+            }
+            (token, _) => {
+                let mut token = token.clone();
+                token.set_span(token.span().located_at(span));
+                transcribed.extend(std::iter::once(token));
+                index += 1;
+            }
+        }
+    }
+    Some(transcribed)
+}
+
+/// Names of the metavariables used in `transcriber`
+fn transcriber_names(transcriber: &TokenStream2, names: &mut Vec<String>) {
+    use proc_macro2::TokenTree;
+
+    let mut tokens = transcriber.clone().into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(dollar) if dollar.as_char() == '$' => {
+                if let Some(TokenTree::Ident(name)) = tokens.peek() {
+                    names.push(name.to_string());
+                }
+            }
+            TokenTree::Group(group) => transcriber_names(&group.stream(), names),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Renders tokens without whitespace, whose placement depends on the spacing of punctuation
+    fn normalized(tokens: impl ToString) -> String {
+        tokens.to_string().split_whitespace().collect()
+    }
+
+    #[test]
+    fn test_generate_random_type_name() {
+        let span = proc_macro2::Span::call_site();
+        let name1 = generate_random_type_name(DEFAULT_ALIAS_PREFIX, span);
+        let name2 = generate_random_type_name(DEFAULT_ALIAS_PREFIX, span);
+
+        assert_ne!(name1, name2);
+        assert!(name1.to_string().starts_with("__TypeMacroAlias"));
+        assert!(name2.to_string().starts_with("__TypeMacroAlias"));
+    }
+
+    #[test]
+    fn test_stable_names() {
+        let input: DeriveInput = syn::parse_quote!(
+            struct S<T> {
+                pair: Pair![T],
+                buffer: [u8; len!(4)],
+            }
+        );
+        let args: MacroDeriveArgs = syn::parse_quote!(Debug, stable_names = true);
+        let names = || {
+            let (aliases, _) = expand_type_macros(&args, &input).unwrap();
+            let mut names: Vec<_> = aliases.iter().map(|alias| alias.to_string()).collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names(), names());
+
+        let mut other = input.clone();
+        other.ident = syn::parse_quote!(Other);
+        let (aliases, _) = expand_type_macros(&args, &other).unwrap();
+        assert!(aliases
+            .iter()
+            .all(|alias| !names().contains(&alias.to_string())));
+    }
+
+    #[test]
+    fn test_stable_api_names() {
+        let input: DeriveInput = syn::parse_quote!(
+            pub struct S<T> {
+                pub pair: Pair![T],
+                pub buffer: [u8; len!(4)],
+                pub op: Symbol!["+"],
+            }
+        );
+        let args: MacroDeriveArgs = syn::parse_quote!(Debug, stable_api = true);
+        let names = |input: &DeriveInput| {
+            let (aliases, transformed) = expand_type_macros(&args, input).unwrap();
+            let mut names: Vec<_> = aliases
+                .into_iter()
+                .map(|alias| {
+                    item_ident(&syn::parse2(alias).unwrap())
+                        .unwrap()
+                        .to_string()
+                })
+                .collect();
+            names.sort();
+            (names, transformed)
+        };
+        let (expected, transformed) = names(&input);
+
+        // The names must not change within a major version
+        let pinned: DeriveInput = syn::parse_quote!(
+            pub struct S<T> {
+                pub pair: __TypeMacroAliase37bf5ba91ecb610<T>,
+                pub buffer: [u8; __TypeMacroAlias34d0d4609c6de653],
+                pub op: __TypeMacroAlias9a29e0d74f258f0a,
+            }
+        );
+        assert_eq!(transformed, pinned);
+
+        // Neither the order of the fields nor their spacing matter
+        let reordered: DeriveInput = syn::parse_quote!(
+            pub struct S<T> {
+                pub op: Symbol!("+"),
+                pub buffer: [u8; len!(4)],
+                pub pair: Pair![T],
+            }
+        );
+        assert_eq!(names(&reordered).0, expected);
+
+        assert!(
+            syn::parse_str::<MacroDeriveArgs>("stable_api = true, readable_names = true").is_err()
+        );
+    }
+
+    #[test]
+    fn test_type_path_option() {
+        let args: MacroDeriveArgs = syn::parse_quote!(Reflect, type_path = "game::shapes");
+        assert_eq!(args.type_path.unwrap().value(), "game::shapes");
+        for invalid in ["::game", "game::<T>", "game shapes", ""] {
+            let args = format!("Reflect, type_path = {:?}", invalid);
+            assert!(
+                syn::parse_str::<MacroDeriveArgs>(&args).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_alias_name_collisions() {
+        let args: MacroDeriveArgs = syn::parse_quote!(Debug, stable_names = true);
+        let span = proc_macro2::Span::call_site();
+        let mut aliases = MacroAliases::default();
+        let first = generate_alias_name("key", "field", &args, &mut aliases, span);
+        let second = generate_alias_name("key", "field", &args, &mut aliases, span);
+        let third = generate_alias_name("key", "field", &args, &mut aliases, span);
+        assert_eq!(second, format!("{}1", first));
+        assert_eq!(third, format!("{}2", first));
+
+        let input: DeriveInput = syn::parse_quote!(
+            enum E<'a, T, const N: usize> {
+                A { name: &'a T },
+                B([u8; N]),
+            }
+        );
+        let mut names: Vec<_> = reserved_names(&input).into_iter().collect();
+        names.sort();
+        assert_eq!(names, ["'a", "E", "N", "T", "name"]);
+    }
+
+    #[test]
+    fn test_parse_macro_derive_args() {
+        let args: MacroDeriveArgs =
+            syn::parse_quote!(Debug, std::clone::Clone, vis = "pub(crate)",);
+
+        assert_eq!(args.derive_traits.len(), 2);
+        assert!(args.derive_traits[0].is_ident("Debug"));
+        assert_eq!(args.derive_traits[1], syn::parse_quote!(std::clone::Clone));
+        assert_eq!(args.vis, Some(syn::parse_quote!(pub(crate))));
+        assert_eq!(args.prefix, DEFAULT_ALIAS_PREFIX);
+    }
+
+    #[test]
+    fn test_parse_macro_derive_args_key_value() {
+        let args: MacroDeriveArgs = syn::parse_quote!(
+            derive(Debug, Clone),
+            prefix = "__Custom",
+            vis = "pub",
+            PartialEq
+        );
+
+        let traits: Vec<_> = args.derive_traits.iter().collect();
+        assert_eq!(traits.len(), 3);
+        assert!(traits[0].is_ident("Debug"));
+        assert!(traits[1].is_ident("Clone"));
+        assert!(traits[2].is_ident("PartialEq"));
+        assert_eq!(args.prefix, "__Custom");
+        assert!(syn::parse_str::<MacroDeriveArgs>(r#"prefix = "not valid""#).is_err());
+    }
+
+    #[test]
+    fn test_parse_macro_derive_args_generic_paths() {
+        let args: MacroDeriveArgs = syn::parse_quote!(
+            serde::Deserialize<'de>,
+            derive_more::Into<u64>,
+            derive(Debug, Convert<A, B>)
+        );
+
+        assert_eq!(args.derive_traits.len(), 4);
+        assert_eq!(
+            args.derive_traits[0],
+            syn::parse_quote!(serde::Deserialize<'de>)
+        );
+        assert_eq!(
+            args.derive_traits[1],
+            syn::parse_quote!(derive_more::Into<u64>)
+        );
+        assert_eq!(args.derive_traits[3], syn::parse_quote!(Convert<A, B>));
+
+        let input: DeriveInput = syn::parse_quote!(
+            struct S {
+                field: u8,
+            }
+        );
+        let expanded = normalized(impl_type_macro_derive_tricks(&args, &input).unwrap());
+        assert!(expanded.contains(&normalized(
+            quote!(#[derive(serde::Deserialize<'de>, derive_more::Into<u64>, Debug, Convert<A, B>)])
+        )));
+    }
+
+    #[test]
+    fn test_parse_macro_derive_args_dedup() {
+        let args: MacroDeriveArgs = syn::parse_quote!(Debug, Clone, Debug, derive(Clone, Hash),);
+        let expected: Vec<syn::Path> = vec![
+            syn::parse_quote!(Debug),
+            syn::parse_quote!(Clone),
+            syn::parse_quote!(Hash),
+        ];
+        assert_eq!(args.derive_traits, expected);
+
+        let args: MacroDeriveArgs = syn::parse_quote!();
+        assert!(args.derive_traits.is_empty());
+    }
+
+    #[test]
+    fn test_derive_position() {
+        let input: DeriveInput = syn::parse_quote!(
+            #[doc = "item"]
+            #[derive(Clone)]
+            #[repr(C)]
+            struct S {
+                field: u8,
+            }
+        );
+        let expected = [
+            (
+                "first",
+                quote!(#[doc = "item"] #[derive(Debug, Clone)] #[repr(C)]),
+            ),
+            (
+                "last",
+                quote!(#[doc = "item"] #[repr(C)] #[derive(Debug, Clone)]),
+            ),
+            (
+                "preserve",
+                quote!(#[doc = "item"] #[derive(Debug, Clone)] #[repr(C)]),
+            ),
+        ];
+
+        for (position, attrs) in expected {
+            let args: MacroDeriveArgs =
+                syn::parse_str(&format!("Debug, derive_position = {}", position)).unwrap();
+            let expanded = impl_type_macro_derive_tricks(&args, &input)
+                .unwrap()
+                .to_string();
+            assert!(expanded.starts_with(&attrs.to_string()), "{}", expanded);
+        }
+
+        let args: MacroDeriveArgs = syn::parse_quote!(derive_position = "last");
+        assert_eq!(args.derive_position, DerivePosition::Last);
+        assert!(syn::parse_str::<MacroDeriveArgs>("derive_position = middle").is_err());
+    }
+
+    #[test]
+    fn test_cfg_derives() {
+        let args: MacroDeriveArgs = syn::parse_quote!(
+            Debug,
+            cfg(feature = "serde", serde::Serialize, serde::Deserialize),
+            cfg(all(unix, not(test)), Hash)
+        );
+        assert_eq!(args.cfg_derives.len(), 2);
+        assert_eq!(args.cfg_derives[1].derive_traits.len(), 1);
+
+        let input: DeriveInput = syn::parse_quote!(
+            #[repr(C)]
+            struct S {
+                field: u8,
+            }
+        );
+        let expanded = impl_type_macro_derive_tricks(&args, &input)
+            .unwrap()
+            .to_string();
+        let expected = quote! {
+            #[derive(Debug)]
+            #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+            #[cfg_attr(all(unix, not(test)), derive(Hash))]
+            #[repr(C)]
+        };
+        assert!(expanded.starts_with(&expected.to_string()), "{}", expanded);
+    }
+
+    #[test]
+    fn test_attr_passthrough() {
+        let args: MacroDeriveArgs = syn::parse_quote!(
+            attr(serde(deny_unknown_fields), repr(C)),
+            Debug,
+            attr(doc = "passed through")
+        );
+        assert_eq!(args.extra_attrs.len(), 3);
+
+        let input: DeriveInput = syn::parse_quote!(
+            #[allow(dead_code)]
+            struct S {
+                field: u8,
+            }
+        );
+        let expanded = impl_type_macro_derive_tricks(&args, &input)
+            .unwrap()
+            .to_string();
+        let expected = quote! {
+            #[allow(dead_code)]
+            #[derive(Debug)]
+            #[serde(deny_unknown_fields)]
+            #[repr(C)]
+            #[doc = "passed through"]
+        };
+        assert!(expanded.starts_with(&expected.to_string()), "{}", expanded);
+    }
+
+    #[test]
+    fn test_const_param_alias() {
+        let input: DeriveInput = syn::parse_quote!(
+            struct S<T: Copy, const N: usize = 4> {
+                field: ArrayMacro![T, N],
+            }
+        );
+        let (type_aliases, transformed) =
+            expand_type_macros(&MacroDeriveArgs::default(), &input).unwrap();
+
+        assert_eq!(type_aliases.len(), 1);
+        let alias: syn::ItemType = syn::parse2(type_aliases[0].clone()).unwrap();
+        let expected: syn::Generics = syn::parse_quote!(<T: Copy, const N: usize>);
+        assert_eq!(alias.generics, expected);
+
+        let Data::Struct(data_struct) = transformed.data else {
+            unreachable!()
+        };
+        let field_ty = &data_struct.fields.iter().next().unwrap().ty;
+        let alias_name = &alias.ident;
+        assert_eq!(
+            quote!(#field_ty).to_string(),
+            quote!(#alias_name<T, N>).to_string()
+        );
+    }
+
+    #[test]
+    fn test_filtered_generics_order() {
+        let params: Vec<syn::GenericParam> = vec![
+            syn::parse_quote!(const N: usize),
+            syn::parse_quote!(T),
+            syn::parse_quote!('b),
+            syn::parse_quote!(U: Clone),
+            syn::parse_quote!('a),
+        ];
+        let generics = create_filtered_generics(&params);
+        let expected: syn::Generics = syn::parse_quote!(<'b, 'a, T, U: Clone, const N: usize>);
+        assert_eq!(generics.params, expected.params);
+
+        let (_, ty_generics, _) = generics.split_for_impl();
+        assert_eq!(
+            quote!(#ty_generics).to_string(),
+            quote!(<'b, 'a, T, U, N>).to_string()
+        );
+    }
+
+    #[test]
+    fn test_used_generic_params_parsing() {
+        let generics: Generics = syn::parse_quote!(<'a, T, U, vec, const N: usize>);
+        let used = |ty: Type| -> Vec<String> {
+            get_used_generic_params(&ty, &generics)
+                .iter()
+                .map(generic_param_name)
+                .collect()
+        };
+
+        assert_eq!(used(syn::parse_quote!(M![T, [U; N]])), ["T", "U", "N"]);
+        assert_eq!(used(syn::parse_quote!(M![&'a other::T])), ["'a"]);
+        assert_eq!(used(syn::parse_quote!(M![fn(vec: T) -> ::U])), ["T"]);
+        assert_eq!(used(syn::parse_quote!(M![Inner![U]])), ["U"]);
+        // Falls back to token scanning when the tokens are not types
+        assert_eq!(used(syn::parse_quote!(M![vec T])), ["T", "vec"]);
+    }
+
+    #[test]
+    fn test_filtered_generic_params() {
+        let generics: Generics = syn::parse_quote!(<'a, T, U>);
+        let filtered = |ty: Type| -> Vec<String> {
+            let used = get_used_generic_params(&ty, &generics);
+            filtered_generic_params(&ty, &generics, &used)
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect()
+        };
+
+        assert!(filtered(syn::parse_quote!(M![T, &'a U])).is_empty());
+        assert_eq!(filtered(syn::parse_quote!(M![fn(T: u8) -> U])), ["T"]);
+        assert_eq!(
+            filtered(syn::parse_quote!(M![for<'b> fn(&'b U) -> other::T])),
+            ["T"]
+        );
+    }
+
+    #[test]
+    fn test_lifetime_scanning() {
+        let tokens = quote!(x => [{ (&'a T) }], ('b ; ('c)));
+        assert!(is_generic_param_used_in_token_stream(&tokens, "'a"));
+        assert!(is_generic_param_used_in_token_stream(&tokens, "'b"));
+        assert!(is_generic_param_used_in_token_stream(&tokens, "'c"));
+        assert!(is_generic_param_used_in_token_stream(&tokens, "T"));
+        assert!(!is_generic_param_used_in_token_stream(&tokens, "'d"));
+
+        // The name of a lifetime is not a use of an identically named type parameter
+        assert!(!is_generic_param_used_in_token_stream(&tokens, "a"));
+        assert!(!is_generic_param_used_in_token_stream(&quote!(=> 'x), "x"));
+    }
+
+    #[test]
+    fn test_hrtb_lifetimes_are_not_params() {
+        let generics: Generics = syn::parse_quote!(<'x, 'a, T>);
+        let used = |ty: Type| -> Vec<String> {
+            get_used_generic_params(&ty, &generics)
+                .iter()
+                .map(generic_param_name)
+                .collect()
+        };
+
+        assert_eq!(used(syn::parse_quote!(M![for<'x> fn(&'x T)])), ["T"]);
+        assert_eq!(
+            used(syn::parse_quote!(M![Box<dyn for<'x> Fn(&'x T) -> &'a T>])),
+            ["'a", "T"]
+        );
+        // The binder only covers the function type
+        assert_eq!(
+            used(syn::parse_quote!(M![(for<'x> fn(&'x u8), &'x T)])),
+            ["'x", "T"]
+        );
+        // Token scanning fallback excludes binder lifetimes entirely
+        assert_eq!(
+            used(syn::parse_quote!(M![callback for<'x> fn(&'x T)])),
+            ["T"]
+        );
+    }
+
+    #[test]
+    fn test_associated_paths_mark_params() {
+        let generics: Generics = syn::parse_quote!(<T, U, V, const N: usize>);
+        let used = |ty: Type| -> Vec<String> {
+            get_used_generic_params(&ty, &generics)
+                .iter()
+                .map(generic_param_name)
+                .collect()
+        };
+
+        assert_eq!(used(syn::parse_quote!(M![T::Output])), ["T"]);
+        assert_eq!(used(syn::parse_quote!(M![<T as Iterator>::Item])), ["T"]);
+        assert_eq!(
+            used(syn::parse_quote!(M![
+                <<U as IntoIterator>::IntoIter as Iterator>::Item
+            ])),
+            ["U"]
+        );
+        assert_eq!(
+            used(syn::parse_quote!(M![<u8 as Convert<V>>::Output])),
+            ["V"]
+        );
+        assert_eq!(
+            used(syn::parse_quote!(M![[u8; <T as Len>::LEN + N]])),
+            ["T", "N"]
+        );
+        // Only the qualified self type can be a parameter, not the trait path
+        assert_eq!(used(syn::parse_quote!(M![<u8 as T::Assoc>::Item])), ["T"]);
+        assert_eq!(
+            used(syn::parse_quote!(M![<u8 as other::T>::Item])),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_nested_positions_are_rewritten() {
+        let input: DeriveInput = syn::parse_quote!(
+            struct S<'a, T, U> {
+                describe: Box<dyn Describe<M![T]> + Send>,
+                as_ref: &'a dyn AsRef<[M![U]]>,
+                callback: Box<dyn Fn(M![T]) -> M![U] + Send>,
+                iter: Box<dyn Iterator<Item = M![T]>>,
+                bare_fn: fn(M![T]) -> Option<M![U]>,
+            }
+        );
+        let (type_aliases, transformed) =
+            expand_type_macros(&MacroDeriveArgs::default(), &input).unwrap();
+
+        assert_eq!(type_aliases.len(), 2);
+        let Data::Struct(data_struct) = transformed.data else {
+            unreachable!()
+        };
+        for field in data_struct.fields {
+            let ty = field.ty;
+            assert!(!quote!(#ty).to_string().contains('!'), "{}", quote!(#ty));
+        }
+    }
+
+    #[test]
+    fn test_paren_and_group_types() {
+        // `$t:ty` fragments are passed to attribute macros wrapped in invisible groups
+        let fragment = proc_macro2::Group::new(proc_macro2::Delimiter::None, quote!(M![T]));
+        let input: DeriveInput = syn::parse_quote!(
+            struct S<'a, T> {
+                grouped: Option<#fragment>,
+                paren: &'a (dyn AsRef<M![T]> + Send),
+            }
+        );
+        let Data::Struct(data_struct) = &input.data else {
+            unreachable!()
+        };
+        let grouped_ty: Type = syn::parse_quote!(Option<#fragment>);
+        assert_eq!(data_struct.fields.iter().next().unwrap().ty, grouped_ty);
+        assert!(matches!(syn::parse2(quote!(#fragment)), Ok(Type::Group(_))));
+
+        let (type_aliases, transformed) =
+            expand_type_macros(&MacroDeriveArgs::default(), &input).unwrap();
+        assert_eq!(type_aliases.len(), 1);
+        let Data::Struct(data_struct) = transformed.data else {
+            unreachable!()
+        };
+        for field in data_struct.fields {
+            let ty = field.ty;
+            assert!(!quote!(#ty).to_string().contains('!'), "{}", quote!(#ty));
+        }
+    }
+
+    #[test]
+    fn test_qualified_self_types() {
+        let input: DeriveInput = syn::parse_quote!(
+            struct S<T> {
+                field: <M![T] as Trait<N![u8]>>::Assoc,
+            }
+        );
+        let (type_aliases, transformed) =
+            expand_type_macros(&MacroDeriveArgs::default(), &input).unwrap();
+        assert_eq!(type_aliases.len(), 2);
+        let Data::Struct(data_struct) = transformed.data else {
+            unreachable!()
+        };
+        let ty = &data_struct.fields.iter().next().unwrap().ty;
+        let Type::Path(type_path) = ty else {
+            unreachable!()
+        };
+        assert!(type_path.qself.is_some());
+        assert!(!quote!(#ty).to_string().contains('!'), "{}", quote!(#ty));
+    }
+
+    #[test]
+    fn test_array_length_macros() {
+        let input: DeriveInput = syn::parse_quote!(
+            struct S<T, const N: usize> {
+                fixed: [u8; len!(FRAME)],
+                repeated: [T; len!(FRAME)],
+                generic: [u8; len!(N)],
+            }
+        );
+        let (aliases, transformed) =
+            expand_type_macros(&MacroDeriveArgs::default(), &input).unwrap();
+        assert_eq!(aliases.len(), 1);
+        let constant: syn::ItemConst = syn::parse2(aliases[0].clone()).unwrap();
+        assert_eq!(constant.expr, syn::parse_quote!(len!(FRAME)));
+
+        let Data::Struct(data_struct) = transformed.data else {
+            unreachable!()
+        };
+        let lengths: Vec<_> = data_struct
+            .fields
+            .iter()
+            .map(|field| match &field.ty {
+                Type::Array(type_array) => type_array.len.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+        let const_name = &constant.ident;
+        assert_eq!(lengths[0], syn::parse_quote!(#const_name));
+        assert_eq!(lengths[1], syn::parse_quote!(#const_name));
+        assert_eq!(lengths[2], syn::parse_quote!(len!(N)));
+    }
+
+    #[test]
+    fn test_array_length_order() {
+        // The names are random, so only the source order keeps the expansion reproducible
+        let input: DeriveInput = syn::parse_quote!(
+            struct S {
+                a: [u8; len!(A)],
+                b: [u8; len!(B)],
+                c: [u8; len!(C)],
+                d: [u8; len!(D)],
+                e: [u8; len!(E)],
+            }
+        );
+        let (aliases, _) = expand_type_macros(&MacroDeriveArgs::default(), &input).unwrap();
+        let lengths: Vec<syn::Expr> = aliases
+            .into_iter()
+            .map(|alias| {
+                syn::parse2::<syn::ItemConst>(alias)
+                    .unwrap()
+                    .expr
+                    .as_ref()
+                    .clone()
+            })
+            .collect();
+        let expected: Vec<syn::Expr> = vec![
+            syn::parse_quote!(len!(A)),
+            syn::parse_quote!(len!(B)),
+            syn::parse_quote!(len!(C)),
+            syn::parse_quote!(len!(D)),
+            syn::parse_quote!(len!(E)),
+        ];
+        assert_eq!(lengths, expected);
+    }
+
+    #[test]
+    fn test_const_generic_arguments() {
+        let input: DeriveInput = syn::parse_quote!(
+            struct S<T> {
+                mixed: GenericArray<M![T], N>,
+                block: Buffer<{ core::mem::size_of::<M![u8]>() }, 4>,
+            }
+        );
+        let (aliases, transformed) =
+            expand_type_macros(&MacroDeriveArgs::default(), &input).unwrap();
+        assert_eq!(aliases.len(), 2);
+
+        let Data::Struct(data_struct) = transformed.data else {
+            unreachable!()
+        };
+        let types: Vec<_> = data_struct
+            .fields
+            .iter()
+            .map(|field| {
+                let ty = &field.ty;
+                quote!(#ty).to_string()
+            })
+            .collect();
+        assert!(
+            !types[0].contains('!') && types[0].ends_with(", N >"),
+            "{}",
+            types[0]
+        );
+        assert!(!types[1].contains("M !"), "{}", types[1]);
+        assert!(types[1].contains("size_of :: <") && types[1].ends_with(", 4 >"));
+    }
+
+    #[test]
+    fn test_delimiters_share_alias() {
+        let input: DeriveInput = syn::parse_quote!(
+            struct S<T> {
+                bracket: M![T],
+                paren: Option<M!(T)>,
+                brace: Vec<M! { T }>,
+                other: M![u8],
+            }
+        );
+        let (aliases, transformed) =
+            expand_type_macros(&MacroDeriveArgs::default(), &input).unwrap();
+        assert_eq!(aliases.len(), 2);
+
+        let Data::Struct(data_struct) = transformed.data else {
+            unreachable!()
+        };
+        let types: Vec<_> = data_struct
+            .fields
+            .into_iter()
+            .map(|field| field.ty)
+            .collect();
+        let shared = &types[0];
+        assert_eq!(types[1], syn::parse_quote!(Option<#shared>));
+        assert_eq!(types[2], syn::parse_quote!(Vec<#shared>));
+        assert_ne!(&types[3], shared);
+    }
+
+    #[test]
+    fn test_alias_key_ignores_spacing() {
+        use proc_macro2::{Delimiter, Group, Punct, Spacing, TokenStream, TokenTree};
+
+        // `M![T -> U]` with `->` split into two separately spaced puncts
+        let split: TokenStream = [
+            TokenTree::from(Ident::new("T", proc_macro2::Span::call_site())),
+            Punct::new('-', Spacing::Alone).into(),
+            Punct::new('>', Spacing::Alone).into(),
+            Ident::new("U", proc_macro2::Span::call_site()).into(),
+        ]
+        .into_iter()
+        .collect();
+        let joint: Type = syn::parse_quote!(M![T -> U]);
+        let spaced: Type = syn::parse_quote!(M![#split]);
+        assert_ne!(joint, spaced);
+        assert_eq!(alias_key(&joint), alias_key(&spaced));
+
+        let grouped = Group::new(Delimiter::None, quote!(Vec<T>));
+        let plain: Type = syn::parse_quote!(M![Vec<T>]);
+        let invisible: Type = syn::parse_quote!(M![#grouped]);
+        assert_eq!(alias_key(&plain), alias_key(&invisible));
+
+        let other: Type = syn::parse_quote!(M![(Vec<T>)]);
+        assert_ne!(alias_key(&plain), alias_key(&other));
+    }
+
+    #[test]
+    fn test_deep_mode_splices_nested_macros() {
+        let input: DeriveInput = syn::parse_quote!(
+            struct S<T, U> {
+                field: Outer![Inner![T], U, { Inner![u8] }],
+            }
+        );
+        let (aliases, _) = expand_type_macros(&MacroDeriveArgs::default(), &input).unwrap();
+        assert_eq!(aliases.len(), 1);
+
+        let args: MacroDeriveArgs = syn::parse_quote!(Debug, deep = true);
+        assert!(args.deep);
+        let (aliases, transformed) = expand_type_macros(&args, &input).unwrap();
+        assert_eq!(aliases.len(), 3);
+        let outer = aliases
+            .iter()
+            .map(|alias| syn::parse2::<syn::ItemType>(alias.clone()).unwrap())
+            .find(|alias| quote!(#alias).to_string().contains("Outer"))
+            .unwrap();
+        let Type::Macro(type_macro) = &*outer.ty else {
+            unreachable!()
+        };
+        assert!(!type_macro.mac.tokens.to_string().contains("Inner"));
+        assert_eq!(outer.generics.params.len(), 2);
+
+        let Data::Struct(data_struct) = transformed.data else {
+            unreachable!()
+        };
+        let ty = &data_struct.fields.iter().next().unwrap().ty;
+        let outer_name = &outer.ident;
+        assert_eq!(*ty, syn::parse_quote!(#outer_name<T, U>));
+    }
+
+    #[test]
+    fn test_self_substitution() {
+        let input: DeriveInput = syn::parse_quote!(
+            struct Node<'a, T, const N: usize> {
+                children: List![Self],
+                parent: Option<&'a M![u8]>,
+            }
+        );
+        let (aliases, _) = expand_type_macros(&MacroDeriveArgs::default(), &input).unwrap();
+        let list = aliases
+            .iter()
+            .map(|alias| syn::parse2::<syn::ItemType>(alias.clone()).unwrap())
+            .find(|alias| quote!(#alias).to_string().contains("List"))
+            .unwrap();
+        assert_eq!(*list.ty, syn::parse_quote!(List![Node<'a, T, N>]));
+        assert_eq!(list.generics.params.len(), 3);
+    }
+
+    #[test]
+    fn test_debug_flag() {
+        let args: MacroDeriveArgs = syn::parse_quote!(Debug, debug, Clone);
+        assert!(args.debug);
+        assert_eq!(args.derive_traits.len(), 2);
+        assert!(args.derive_traits[0].is_ident("Debug"));
+        assert!(!syn::parse_str::<MacroDeriveArgs>("Debug").unwrap().debug);
+    }
+
+    #[test]
+    fn test_bound_requires_a_consumer() {
+        let mut input: DeriveInput = syn::parse_quote!(
+            struct S<T> {
+                a: M![T],
+            }
+        );
+        let args: MacroDeriveArgs = syn::parse_quote!(Debug, bound = "T: Copy", bound = "");
+        assert_eq!(args.bounds.as_ref().unwrap().len(), 1);
+        assert!(insert_derive_attrs(&args, &mut input.clone()).is_err());
+
+        // Derives of other crates named like serde's get no serde helper
+        let args: MacroDeriveArgs = syn::parse_quote!(Debug, rkyv::Serialize, bound = "T: Copy");
+        assert!(insert_derive_attrs(&args, &mut input.clone()).is_err());
+        let args: MacroDeriveArgs = syn::parse_quote!(
+            Clone,
+            my::Deserialize,
+            perfect_bounds = true,
+            bound = "T: Copy"
+        );
+        let mut manual = input.clone();
+        insert_derive_attrs(&args, &mut manual).unwrap();
+        assert!(!manual
+            .attrs
+            .iter()
+            .any(|attr| attr.path().is_ident("serde")));
+
+        let args: MacroDeriveArgs = syn::parse_quote!(Debug, Serialize, bound = "T: Copy");
+        assert!(insert_derive_attrs(&args, &mut input).unwrap().is_empty());
+        let helper = &input.attrs[1];
+        assert_eq!(
+            quote!(#helper).to_string(),
+            r#"# [serde (bound = "T : Copy")]"#
+        );
+
+        assert!(syn::parse_str::<MacroDeriveArgs>(r#"bound = "T:""#).is_ok());
+        assert!(syn::parse_str::<MacroDeriveArgs>(r#"bound = "T Copy""#).is_err());
+    }
+
+    #[test]
+    fn test_serde_bounds() {
+        let mut input: DeriveInput = syn::parse_quote!(
+            struct S<T> {
+                a: Alias<T>,
+                #[serde(skip_deserializing, rename = "b")]
+                b: Vec<T>,
+                c: u8,
+            }
+        );
+        let args: MacroDeriveArgs = syn::parse_quote!(Debug, serde_bounds = true);
+        assert!(insert_derive_attrs(&args, &mut input.clone()).is_err());
+        let args: MacroDeriveArgs = syn::parse_quote!(rkyv::Serialize, serde_bounds = true);
+        assert!(insert_derive_attrs(&args, &mut input.clone()).is_err());
+
+        // Only the serde derive is bounded
+        let args: MacroDeriveArgs =
+            syn::parse_quote!(rkyv::Serialize, serde::Deserialize, serde_bounds = true);
+        let mut mixed = input.clone();
+        insert_derive_attrs(&args, &mut mixed).unwrap();
+        let helper = &mixed.attrs[1];
+        assert_eq!(
+            quote!(#helper).to_string(),
+            quote!(#[serde(bound(deserialize = "Alias < T > : :: serde :: Deserialize < 'de >"))])
+                .to_string()
+        );
+
+        let args: MacroDeriveArgs = syn::parse_quote!(Serialize, Deserialize, serde_bounds = true);
+        insert_derive_attrs(&args, &mut input).unwrap();
+        let helper = &input.attrs[1];
+        assert_eq!(
+            quote!(#helper).to_string(),
+            quote!(#[serde(bound(
+                serialize = "Alias < T > : :: serde :: Serialize , Vec < T > : :: serde :: Serialize",
+                deserialize = "Alias < T > : :: serde :: Deserialize < 'de >"
+            ))])
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn test_conditional_macro_derive() {
+        let mut attrs: Vec<syn::Attribute> = vec![
+            syn::parse_quote!(#[cfg_attr(test, macro_derive(Debug, derive(Default)), allow(unused))]),
+            syn::parse_quote!(#[cfg_attr(test, allow(dead_code))]),
+        ];
+        let mut args = MacroDeriveArgs::default();
+        merge_stacked_attrs(&mut args, &mut attrs).unwrap();
+        assert_eq!(args.cfg_derives.len(), 1);
+        assert_eq!(args.cfg_derives[0].derive_traits.len(), 2);
+        assert_eq!(
+            quote!(#(#attrs)*).to_string(),
+            quote!(#[cfg_attr(test, allow(unused))] #[cfg_attr(test, allow(dead_code))])
+                .to_string()
+        );
+
+        let mut attrs: Vec<syn::Attribute> =
+            vec![syn::parse_quote!(#[cfg_attr(test, macro_derive(Debug, deep = true))])];
+        assert!(merge_stacked_attrs(&mut MacroDeriveArgs::default(), &mut attrs).is_err());
+    }
+
+    #[test]
+    fn test_derive_groups() {
+        let input = quote!(
+            struct S {
+                f: M![u8],
+            }
+        );
+        let call = derive_group_call(
+            "macro_derive",
+            &quote!(Debug, @path::std, deep = true),
+            &input,
+        );
+        assert_eq!(
+            normalized(call.unwrap().unwrap()),
+            normalized(quote!(path::__macro_derive_group_std! {
+                { ::type_macro_derive_tricks::macro_derive } { , Debug, deep = true } #input
+            }))
+        );
+        assert!(derive_group_call("macro_derive", &quote!(Debug, deep = true), &input).is_none());
+        let call = derive_group_call("macro_derive_mod", &quote!(@std, crate = facade), &input);
+        assert_eq!(
+            normalized(call.unwrap().unwrap()),
+            normalized(quote!(__macro_derive_group_std! {
+                { facade::macro_derive_mod } { , crate = facade } #input
+            }))
+        );
+        assert!(
+            derive_group_call("macro_derive", &quote!(@std, crate = 1), &input)
+                .unwrap()
+                .is_err()
+        );
+        assert!(syn::parse_str::<MacroDeriveArgs>("Debug, crate = \"::facade\"").is_ok());
+
+        let defined = define_derive_group(quote!(pub(crate) std = Debug, @base)).unwrap();
+        assert!(defined
+            .to_string()
+            .ends_with("pub (crate) use __macro_derive_group_std ;"));
+        let defined = define_derive_group(quote!(pub(super) std = Debug)).unwrap();
+        assert!(defined
+            .to_string()
+            .ends_with("pub (super) use __macro_derive_group_std ;"));
+        assert!(define_derive_group(quote!(pub std = Debug)).is_err());
+        assert!(define_derive_group(quote!(std = Debug, deep = "no")).is_err());
+        assert!(define_derive_group(quote!(std =)).is_err());
+
+        // Groups are only expanded by the attributes themselves
+        let mut attrs: Vec<syn::Attribute> = vec![syn::parse_quote!(#[macro_derive(@std)])];
+        assert!(merge_stacked_attrs(&mut MacroDeriveArgs::default(), &mut attrs).is_err());
+    }
+
+    #[test]
+    fn test_write_dump() {
+        let dir = std::env::temp_dir().join(format!("type_macro_dump_{}", std::process::id()));
+        let expansion = quote!(
+            type Alias = Pair![u8];
+            struct Dumped {
+                field: Alias,
+            }
+        );
+        let path = write_dump(&dir, "macro_derive", "Dumped", &expansion).unwrap();
+        assert_eq!(path, dir.join("Dumped.rs"));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "// Expansion of `#[macro_derive]` on `Dumped`\n\n\
+             type Alias = Pair![u8];\n\
+             struct Dumped {\n    field: Alias,\n}\n"
+        );
+        // Compiling again replaces the file
+        let expansion = quote!(
+            struct Dumped;
+        );
+        let path = write_dump(&dir, "macro_derive", "Dumped", &expansion).unwrap();
+        assert_eq!(path, dir.join("Dumped.rs"));
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .ends_with("struct Dumped;\n"));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        let path = write_dump(&dir, "macro_impl", "Display for Wrapper<T>", &expansion).unwrap();
+        assert_eq!(path, dir.join("Display_for_Wrapper_T.rs"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_screaming_snake_case() {
+        let cases = [
+            ("Span", "SPAN"),
+            ("TextSpan", "TEXT_SPAN"),
+            ("HTTPRequest", "HTTP_REQUEST"),
+            ("Utf8Text", "UTF8_TEXT"),
+            ("Vec3D", "VEC3_D"),
+            ("snake_case", "SNAKE_CASE"),
+        ];
+        for (name, converted) in cases {
+            assert_eq!(screaming_snake_case(name), converted);
+        }
+    }
+
+    #[test]
+    fn test_callback() {
+        let input = quote!(
+            struct S {
+                f: M![u8],
+            }
+        );
+        let call = callback_call(&quote!(Debug, callback = path::cb, deep = true), &input);
+        assert_eq!(
+            call.unwrap().unwrap().to_string(),
+            quote!(path::cb! {
+                #[::type_macro_derive_tricks::macro_derive(Debug, deep = true)]
+                #input
+            })
+            .to_string()
+        );
+        let call = callback_call(&quote!(callback = cb, crate = ::facade), &input);
+        assert_eq!(
+            call.unwrap().unwrap().to_string(),
+            quote!(cb! {
+                #[::facade::macro_derive(crate = ::facade)]
+                #input
+            })
+            .to_string()
+        );
+        assert!(callback_call(&quote!(callback = "not a path"), &input)
+            .unwrap()
+            .is_err());
+        assert!(callback_call(&quote!(Debug), &input).is_none());
+        assert!(syn::parse_str::<MacroDeriveArgs>("Debug, callback = cb").is_err());
+    }
+
+    #[test]
+    fn test_inline_rules_expansion() {
+        let args: MacroDeriveArgs = syn::parse_quote!(
+            Debug,
+            inline_rules(
+                macro_rules! InlineMap {
+                    ($k:ty => $v:ty) => { ::std::collections::HashMap<$k, $v> };
+                    ($k:ty) => { InlineMap![$k => ()] };
+                }
+                #[macro_export]
+                macro_rules! InlineTuple {
+                    ($($name:ident: $t:ty),* $(,)?) => { ($(Option<$t>,)*) };
+                    (crate) => { $crate::Local };
+                }
+            )
+        );
+        // Spacing is compared apart, since the printer splits `>>`
+        let expand = |ty: Type| match ty {
+            Type::Macro(type_macro) => rules_expansion(&type_macro.mac, &args.inline_macros, 0)
+                .map(|expansion| quote!(#expansion).to_string().replace(' ', "")),
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            expand(syn::parse_quote!(InlineMap![u8])).unwrap(),
+            "::std::collections::HashMap<u8,()>"
+        );
+        assert_eq!(
+            expand(syn::parse_quote!(InlineTuple![a: u8, b: Vec<T>,])).unwrap(),
+            "(Option<u8>,Option<Vec<T>>,)"
+        );
+        assert_eq!(expand(syn::parse_quote!(InlineTuple![])).unwrap(), "()");
+        // Invocations no rule matches, or with `$crate`, are left to be aliased
+        assert_eq!(expand(syn::parse_quote!(InlineTuple![a u8])), None);
+        assert_eq!(expand(syn::parse_quote!(InlineTuple![crate])), None);
+        assert_eq!(expand(syn::parse_quote!(Other![u8])), None);
+
+        // Each expansion only depends on the arguments of its own attribute
+        let input: DeriveInput = syn::parse_quote!(
+            struct S {
+                map: InlineMap![u8],
+            }
+        );
+        let (aliases, transformed) = expand_type_macros(&args, &input).unwrap();
+        assert!(aliases.is_empty());
+        assert!(quote!(#transformed).to_string().contains("HashMap"));
+        let (aliases, _) = expand_type_macros(&MacroDeriveArgs::default(), &input).unwrap();
+        assert_eq!(aliases.len(), 1);
+
+        // A matcher matching in several ways fails like `macro_rules!`, without trying the
+        // next rule
+        let args: MacroDeriveArgs = syn::parse_quote!(
+            inline_rules(
+                macro_rules! Split {
+                    ($($a:ident)* $($b:ident)*) => { ($($a,)*) };
+                    ($t:ty) => { $t };
+                }
+                macro_rules! Nested {
+                    ($($($t:ty),+);*) => { ($($(Vec<$t>,)+)*) };
+                }
+                macro_rules! Words {
+                    ($($($w:ident)*)*) => { () };
+                }
+            )
+        );
+        let expand = |ty: Type| match ty {
+            Type::Macro(type_macro) => rules_expansion(&type_macro.mac, &args.inline_macros, 0)
+                .map(|expansion| quote!(#expansion).to_string().replace(' ', "")),
+            _ => unreachable!(),
+        };
+        assert_eq!(expand(syn::parse_quote!(Split![u8])), None);
+        assert_eq!(
+            expand(syn::parse_quote!(Nested![u8, u16; i8])).unwrap(),
+            "(Vec<u8>,Vec<u16>,Vec<i8>,)"
+        );
+        // Nested repetitions split the words in exponentially many ways, so matching gives up
+        let words: Vec<_> = (0..32)
+            .map(|index| Ident::new(&format!("w{}", index), proc_macro2::Span::call_site()))
+            .collect();
+        let words: Vec<_> = quote!(#(#words)*).into_iter().collect();
+        let rule = &args.inline_macros[2].rules[0];
+        assert!(match_sequence(&rule.matcher, &words, 0).is_none());
+        assert_eq!(expand(syn::parse_quote!(Words![#(#words)*])), None);
+
+        // Later definitions replace earlier ones
+        let args: MacroDeriveArgs = syn::parse_quote!(
+            inline_rules(macro_rules! M { ($t:ty) => { Vec<$t> }; }),
+            inline_rules(macro_rules! M [($t:ty) => { Box<$t> }];),
+        );
+        assert_eq!(args.inline_macros.len(), 1);
+        let ty: syn::TypeMacro = syn::parse_quote!(M![u8]);
+        let expansion = rules_expansion(&ty.mac, &args.inline_macros, 0).unwrap();
+        assert_eq!(expansion, syn::parse_quote!(Box<u8>));
+
+        for invalid in [
+            "inline_rules(macro_rules! M { ($t) => {}; })",
+            "inline_rules(macro_rules! M { ($($t:ty),) => {}; })",
+            "inline_rules(struct M;)",
+        ] {
+            assert!(
+                syn::parse_str::<MacroDeriveArgs>(invalid).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_source_tokens() {
+        let cases = [
+            (quote!(Pair![&'a T]), "Pair![&'a T]"),
+            (
+                quote!(Callback![dyn Fn(u8) -> u8]),
+                "Callback![dyn Fn(u8) -> u8]",
+            ),
+            (
+                quote!(Map![::std::string::String => u8, 4]),
+                "Map![::std::string::String => u8, 4]",
+            ),
+            (
+                quote!(Boxed![dyn Iterator<Item = T> + Send]),
+                "Boxed![dyn Iterator<Item = T> + Send]",
+            ),
+            (quote!(Bound![T: Clone, { N }]), "Bound![T: Clone, { N }]"),
+        ];
+        for (tokens, rendered) in cases {
+            assert_eq!(render_source_tokens(&tokens), rendered);
+        }
+    }
+
+    #[test]
+    fn test_strict_mode() {
+        let input: DeriveInput = syn::parse_quote!(
+            struct S<const N: M![usize]> {
+                replaced: M![u8],
+                #[macro_derive(skip)]
+                skipped: M![u16],
+            }
+        );
+        assert!(expand_type_macros(&MacroDeriveArgs::default(), &input).is_ok());
+
+        let args: MacroDeriveArgs = syn::parse_quote!(Debug, strict);
+        assert!(args.strict);
+        assert!(expand_type_macros(&args, &input).is_ok());
+
+        // Macro types left out by the filter are reported, except in skipped fields
+        let err = TypeMacroRewriter::new()
+            .args(args)
+            .filter(|type_macro| !type_macro.mac.tokens.to_string().contains("usize"))
+            .rewrite(&input)
+            .unwrap_err();
+        assert_eq!(err.into_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_all_type_positions() {
+        let input: DeriveInput = syn::parse_quote!(
+            struct S<T, const N: M![usize] = 0>
+            where
+                for<'a> &'a M![T]: Into<M![u8]>,
+            {
+                bare_fn: fn(M![T]) -> M![u16],
+                dyn_object: Box<dyn Iterator<Item = M![u32]> + Send>,
+                qself: <M![u64] as Trait<M![i8]>>::Assoc,
+                nested: (&'static [M![i16]], *const (M![i32])),
+                const_arg: Buffer<{ size_of::<M![i64]>() }>,
+            }
+        );
+        let (aliases, transformed) =
+            expand_type_macros(&MacroDeriveArgs::default(), &input).unwrap();
+        assert_eq!(aliases.len(), 10);
+
+        let mut finder = MacroTypeFinder::default();
+        finder.visit_derive_input(&transformed);
+        assert!(finder.found.is_empty());
+    }
+
+    #[test]
+    fn test_macro_impl() {
+        let input: syn::ItemImpl = syn::parse_quote!(
+            impl<T> Tr<M![T]> for S<T>
+            where
+                M![T]: Clone,
+            {
+                type Out = M![T];
+                const C: M![u8] = (0, 0);
+                fn f<U>(&self, u: M![U]) -> M![T] {
+                    let _: M![U] = u;
+                    todo!()
+                }
+            }
+        );
+        let (aliases, transformed) =
+            expand_impl_macros(&MacroDeriveArgs::default(), &input).unwrap();
+        assert_eq!(aliases.len(), 3);
+
+        let mut finder = MacroTypeFinder::default();
+        finder.visit_item_impl(&transformed);
+        assert!(finder.found.is_empty());
+        // The body keeps its macro type
+        assert!(quote!(#transformed).to_string().contains("let _ : M ! [U]"));
+
+        let args: MacroDeriveArgs = syn::parse_quote!(Debug);
+        assert!(impl_macro_impl(&args, &input).is_err());
+    }
+
+    #[test]
+    fn test_macro_fn() {
+        let input: syn::ItemFn = syn::parse_quote!(
+            fn f<T>(x: M![T], it: impl Iterator<Item = M![u8]>) -> N![T]
+            where
+                M![T]: Clone,
+            {
+                let _: M![T] = x;
+                todo!()
+            }
+        );
+        let (aliases, transformed) = expand_fn_macros(&MacroDeriveArgs::default(), &input).unwrap();
+        assert_eq!(aliases.len(), 3);
+
+        let mut finder = MacroTypeFinder::default();
+        finder.visit_signature(&transformed.sig);
+        assert!(finder.found.is_empty());
+        assert!(quote!(#transformed).to_string().contains("let _ : M ! [T]"));
+    }
+
+    #[test]
+    fn test_macro_trait() {
+        let input: syn::ItemTrait = syn::parse_quote!(
+            trait Tr<T>: Into<M![u8]> {
+                type Out: From<M![T]> = M![T];
+                const C: M![u8];
+                fn f(&self) -> N![T] {
+                    let _: N![T] = todo!();
+                }
+                fn g(&self) -> M![Self::Out];
+            }
+        );
+        let (aliases, transformed) =
+            expand_trait_macros(&MacroDeriveArgs::default(), &input).unwrap();
+        assert_eq!(aliases.len(), 3);
+
+        // Only the macro type mentioning `Self` is left in the signatures
+        let mut finder = MacroTypeFinder::default();
+        finder.visit_item_trait(&transformed);
+        assert_eq!(finder.found.len(), 1);
+        assert!(quote!(#transformed).to_string().contains("let _ : N ! [T]"));
+
+        let args: MacroDeriveArgs = syn::parse_quote!(strict);
+        assert!(expand_trait_macros(&args, &input).is_err());
+    }
+
+    #[test]
+    fn test_const_and_static_annotations() {
+        let mut item: syn::ItemConst = syn::parse_quote!(
+            const C: Vec<M![u8]> = Vec::<M![u8]>::new();
+        );
+        let aliases = expand_annotation_macros(
+            &MacroDeriveArgs::default(),
+            "const",
+            &item.ident,
+            &item.vis,
+            &mut item.ty,
+        )
+        .unwrap();
+        assert_eq!(aliases.len(), 1);
+        let mut finder = MacroTypeFinder::default();
+        finder.visit_type(&item.ty);
+        assert!(finder.found.is_empty());
+        // The initializer is left as written
+        assert!(quote!(#item).to_string().contains("Vec :: < M ! [u8] >"));
+
+        let mut item: syn::ItemStatic = syn::parse_quote!(
+            static S: M![Self] = todo!();
+        );
+        let args: MacroDeriveArgs = syn::parse_quote!(strict);
+        assert!(
+            expand_annotation_macros(&args, "static", &item.ident, &item.vis, &mut item.ty)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_module_shares_aliases() {
+        let module: syn::ItemMod = syn::parse_quote!(
+            mod m {
+                struct A<T> {
+                    a: M![T],
+                    b: M![u8],
+                    c: M![Self],
+                }
+                struct B<T: Clone> {
+                    a: M![T],
+                    b: M![u8],
+                    c: M![Self],
+                }
+                struct C<U, T> {
+                    a: M![T],
+                    b: M![(T, U)],
+                }
+                struct D<T, U> {
+                    b: M![(T, U)],
+                }
+                fn f() {}
+            }
+        );
+        let expanded = impl_macro_derive_mod(&syn::parse_quote!(Debug), module).unwrap();
+        let module: syn::ItemMod = syn::parse2(expanded).unwrap();
+        let items = module.content.unwrap().1;
+        // `M![T]` and `M![u8]` are shared, `M![Self]` and `M![(T, U)]` are not
+        let aliases = items
+            .iter()
+            .filter(|item| quote!(#item).to_string().contains("type __TypeMacroAlias"))
+            .count();
+        assert_eq!(aliases, 6);
+        assert_eq!(items.len(), aliases + 5);
+    }
+
+    #[test]
+    fn test_type_aliased_items() {
+        let items = syn::parse::Parser::parse2(
+            parse_items,
+            quote! {
+                struct S { a: M![u8] }
+                fn f(a: M![u8]) {}
+                mod m { const C: M![u8] = (); }
+                use std::fmt;
+            },
+        )
+        .unwrap();
+        let expanded = expand_items(&MacroDeriveArgs::default(), items).unwrap();
+        let file: syn::File = syn::parse2(expanded).unwrap();
+        // The macro types are only left in the aliases, one per item
+        let mut finder = MacroTypeFinder::default();
+        finder.visit_file(&file);
+        assert_eq!(finder.found.len(), 3);
+        assert_eq!(file.items.len(), 2 + 2 + 1 + 1);
+    }
+
+    #[test]
+    fn test_union_fields() {
+        let input: DeriveInput = syn::parse_quote!(
+            union U<T> {
+                /// Documented
+                #[allow(dead_code)]
+                a: ManuallyDrop<M![T]>,
+                #[macro_derive(skip)]
+                b: M![u8],
+                c: u32,
+            }
+        );
+        let (aliases, transformed) =
+            expand_type_macros(&MacroDeriveArgs::default(), &input).unwrap();
+        assert_eq!(aliases.len(), 1);
+        let data_union = match &transformed.data {
+            Data::Union(data_union) => data_union,
+            _ => panic!("expected a union"),
+        };
+        let fields: Vec<_> = data_union.fields.named.iter().collect();
+        assert_eq!(fields.len(), 3);
+        assert_eq!(fields[0].attrs.len(), 2);
+        let wrapped = &fields[0].ty;
+        assert!(quote!(#wrapped)
+            .to_string()
+            .starts_with("ManuallyDrop < __TypeMacroAlias"));
+        assert!(fields[1].attrs.is_empty());
+        assert!(matches!(fields[1].ty, Type::Macro(_)));
+        assert_eq!(fields[2].ident.as_ref().unwrap(), "c");
+    }
+
+    #[test]
+    fn test_recover_emits_original_item() {
+        let input = quote! {
+            #[macro_derive(Clone)]
+            struct S {
+                #[macro_alias]
+                a: M![u8],
+            }
+        };
+        let err = syn::Error::new(proc_macro2::Span::call_site(), "failed");
+        let recovered = recover(err, input).to_string();
+        assert!(recovered.starts_with(":: core :: compile_error !"));
+        assert!(recovered.contains("struct S { a : M ! [u8] , }"));
+        assert!(!recovered.contains("macro_alias"));
+        assert!(!recovered.contains("macro_derive"));
+
+        let err = syn::Error::new(proc_macro2::Span::call_site(), "failed");
+        let recovered = recover(err, quote!(struct 1;)).to_string();
+        assert!(!recovered.contains("struct"));
+    }
+
+    #[test]
+    fn test_rewriter_hooks() {
+        let input: DeriveInput = syn::parse_quote!(
+            struct S<T> {
+                a: Outer![Keep![T]],
+                b: Other![u8],
+                #[macro_alias]
+                c: Vec<T>,
+            }
+        );
+        let (aliases, transformed) = TypeMacroRewriter::new()
+            .args(syn::parse_quote!(deep = true))
+            .filter(|type_macro| !type_macro.mac.path.is_ident("Keep"))
+            .name_aliases(|_| syn::parse_quote!(S))
+            .alias_attributes(|ty| match ty {
+                Type::Macro(_) => vec![syn::parse_quote!(#[cfg(feature = "macros")])],
+                _ => Vec::new(),
+            })
+            .rewrite(&input)
+            .unwrap();
+
+        let mut aliases: Vec<_> = aliases.iter().map(normalized).collect();
+        aliases.sort();
+        // The name of the item is taken, so the aliases get suffixes
+        assert_eq!(
+            aliases,
+            [
+                "# [cfg (feature = \"macros\")] # [doc (hidden)] # [allow (dead_code)] \
+                 type S2 = Other ! [u8] ;",
+                "# [cfg (feature = \"macros\")] # [doc (hidden)] \
+                 # [allow (dead_code , type_alias_bounds)] type S1 < T > = Outer ! [Keep ! [T]] ;",
+                "# [doc (hidden)] # [allow (dead_code , type_alias_bounds)] \
+                 type S3 < T > = Vec < T > ;",
+            ]
+            .map(normalized)
+        );
+        assert!(quote!(#transformed).to_string().contains("a : S1 < T >"));
+    }
+
+    #[test]
+    fn test_parse_macro_derive_args_reports_all_invalid_entries() {
+        let err = syn::parse_str::<MacroDeriveArgs>(
+            "Debug, Debgu!, 42, derive(Clone, 1 + 2), cfg(test, Eq, 'a), unknown = 1, Hash",
+        )
+        .err()
+        .unwrap();
+        let messages: Vec<_> = err.into_iter().map(|err| err.to_string()).collect();
+        assert_eq!(messages.len(), 5, "{:?}", messages);
+        assert!(messages[..4]
+            .iter()
+            .all(|message| message.starts_with("expected a derive trait path")));
+        assert_eq!(messages[4], "unsupported `macro_derive` argument");
+    }
+
+    #[test]
+    fn test_parse_macro_derive_args_rejects_unknown_option() {
+        assert!(syn::parse_str::<MacroDeriveArgs>("Debug, unknown = 1").is_err());
+        assert!(syn::parse_str::<MacroDeriveArgs>("vis = 1").is_err());
+
+        let err = syn::parse_str::<MacroDeriveArgs>("Debug, Debgu!, Clone")
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "expected a derive trait path, found `Debgu !`"
+        );
+    }
+}
@@ -0,0 +1,913 @@
+use proc_macro2::TokenStream;
+use type_macro_derive_tricks_core::expand;
+
+fn tokens(source: &str) -> TokenStream {
+    source.parse().unwrap()
+}
+
+/// Alias names are random unless asked to be stable, as with the attribute
+fn assert_expands_to(args: &str, item: &str, expected: &str) {
+    let args = format!("stable_names = true, {}", args);
+    let expanded = expand(tokens(&args), tokens(item));
+    let expanded: syn::File = syn::parse2(expanded).unwrap();
+    let expected: syn::File = syn::parse_str(expected).unwrap();
+    assert_eq!(expanded, expected);
+}
+
+#[test]
+fn test_golden_struct() {
+    assert_expands_to(
+        "Debug, Clone",
+        "pub struct Line<T> {
+            pub from: Pair![T],
+            pub to: Pair![T],
+            pub buffer: [u8; len!()],
+        }",
+        "#[doc(hidden)]
+        #[allow(dead_code, type_alias_bounds)]
+        pub type __TypeMacroAlias73c851ca6451b52d<T> = Pair![T];
+        #[doc(hidden)]
+        #[allow(dead_code, non_upper_case_globals)]
+        pub const __TypeMacroAlias100782264f5c869c: usize = len!();
+        #[derive(Debug, Clone)]
+        pub struct Line<T> {
+            pub from: __TypeMacroAlias73c851ca6451b52d<T>,
+            pub to: __TypeMacroAlias73c851ca6451b52d<T>,
+            pub buffer: [u8; __TypeMacroAlias100782264f5c869c],
+        }",
+    );
+}
+
+#[test]
+fn test_golden_aliases_in_source_order() {
+    assert_expands_to(
+        "Debug, prefix = \"A\", vis = \"pub(crate)\"",
+        "enum E { X(Third![u8]), Y(First![u8], Second![u8]) }",
+        "#[doc(hidden)]
+        #[allow(dead_code)]
+        pub(crate) type Ab76674cc843892a9 = Third![u8];
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        pub(crate) type Acb545d14a97870ca = First![u8];
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        pub(crate) type A1be271f1505fbdf4 = Second![u8];
+        #[derive(Debug)]
+        enum E { X(Ab76674cc843892a9), Y(Acb545d14a97870ca, A1be271f1505fbdf4) }",
+    );
+}
+
+#[test]
+fn test_golden_error() {
+    assert_expands_to(
+        "Debug, unknown = 1",
+        "struct S { #[macro_alias] a: u8 }",
+        "::core::compile_error! { \"unsupported `macro_derive` argument\" }
+        struct S { a: u8 }",
+    );
+}
+
+#[test]
+fn test_golden_where_clause() {
+    assert_expands_to(
+        "Clone, prefix = \"A\", where_clause(Pair![T]: Copy)",
+        "struct S<T> where T: Clone { pair: Pair![T] }",
+        "#[doc(hidden)]
+        #[allow(dead_code, type_alias_bounds)]
+        type Ae37bf5ba91ecb610<T> = Pair![T];
+        #[derive(Clone)]
+        struct S<T> where T: Clone, Ae37bf5ba91ecb610<T>: Copy { pair: Ae37bf5ba91ecb610<T> }",
+    );
+}
+
+#[test]
+fn test_golden_rkyv_attributes() {
+    // Container attributes follow the derive and field wrappers keep their order
+    assert_expands_to(
+        "rkyv::Archive, rkyv::Serialize, rkyv::Deserialize, prefix = \"A\", \
+         attr(archive(check_bytes), archive_attr(derive(Debug)))",
+        "#[archive(compare(PartialEq))]
+        struct Record {
+            #[with(rkyv::with::AsBox)]
+            #[macro_alias]
+            #[with(rkyv::with::Inline)]
+            names: Vec<Name![u8]>,
+            #[with(rkyv::with::Skip)]
+            id: Id![u32],
+        }",
+        "#[doc(hidden)]
+        #[allow(dead_code)]
+        type Ab8f1cf0b29eebf83 = Vec<Name![u8]>;
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        type A10408909baaf0542 = Id![u32];
+        #[derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize)]
+        #[archive(check_bytes)]
+        #[archive_attr(derive(Debug))]
+        #[archive(compare(PartialEq))]
+        struct Record {
+            #[with(rkyv::with::AsBox)]
+            #[with(rkyv::with::Inline)]
+            names: Ab8f1cf0b29eebf83,
+            #[with(rkyv::with::Skip)]
+            id: A10408909baaf0542,
+        }",
+    );
+}
+
+#[test]
+fn test_golden_thiserror_attributes() {
+    // Variant and field helpers stay on their rewritten variants and fields, after the derive
+    assert_expands_to(
+        "Debug, thiserror::Error, prefix = \"A\"",
+        "#[error(\"parse failed\")]
+        enum ParseError {
+            #[error(\"io: {0}\")]
+            Io(#[from] IoError![std::io::ErrorKind]),
+            #[error(\"at {line}\")]
+            Syntax {
+                line: Line![u32],
+                #[source]
+                #[macro_alias]
+                cause: Box<Cause![dyn std::error::Error]>,
+            },
+        }",
+        "#[doc(hidden)]
+        #[allow(dead_code)]
+        type A01ec4f7a2779da02 = IoError![std::io::ErrorKind];
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        type A6cab97d52b95ac31 = Line![u32];
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        type A42d48de6f9b0034b = Box<Cause![dyn std::error::Error]>;
+        #[derive(Debug, thiserror::Error)]
+        #[error(\"parse failed\")]
+        enum ParseError {
+            #[error(\"io: {0}\")]
+            Io(#[from] A01ec4f7a2779da02),
+            #[error(\"at {line}\")]
+            Syntax {
+                line: A6cab97d52b95ac31,
+                #[source]
+                cause: A42d48de6f9b0034b,
+            },
+        }",
+    );
+}
+
+#[test]
+fn test_golden_builder_attributes() {
+    // Public aliases let builder setters name the field types outside the crate
+    assert_expands_to(
+        "derive_builder::Builder, prefix = \"A\", vis = \"pub\"",
+        "#[builder(setter(into))]
+        pub(crate) struct Config {
+            #[builder(default)]
+            retries: Count![u8],
+            #[builder(setter(each(name = \"path\")))]
+            paths: Paths![],
+        }",
+        "#[doc(hidden)]
+        #[allow(dead_code)]
+        pub type A7906c46110526d3c = Count![u8];
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        pub type Acdbf077da4c934c2 = Paths![];
+        #[derive(derive_builder::Builder)]
+        #[builder(setter(into))]
+        pub(crate) struct Config {
+            #[builder(default)]
+            retries: A7906c46110526d3c,
+            #[builder(setter(each(name = \"path\")))]
+            paths: Acdbf077da4c934c2,
+        }",
+    );
+}
+
+#[test]
+fn test_golden_strum_attributes() {
+    assert_expands_to(
+        "strum::EnumIter, strum::Display, prefix = \"A\", \
+         attr(strum(serialize_all = \"snake_case\"))",
+        "enum Shape {
+            #[strum(to_string = \"point at {0:?}\")]
+            Point(Pair![i32]),
+            #[strum(disabled)]
+            Line { from: Pair![i32], to: Pair![i32] },
+            Empty,
+        }",
+        "#[doc(hidden)]
+        #[allow(dead_code)]
+        type Aeee99d0161012cab = Pair![i32];
+        #[derive(strum::EnumIter, strum::Display)]
+        #[strum(serialize_all = \"snake_case\")]
+        enum Shape {
+            #[strum(to_string = \"point at {0:?}\")]
+            Point(Aeee99d0161012cab),
+            #[strum(disabled)]
+            Line { from: Aeee99d0161012cab, to: Aeee99d0161012cab },
+            Empty,
+        }",
+    );
+}
+
+#[test]
+fn test_golden_derive_arguments() {
+    // Derive arguments are passed to the helper attribute named after the trait
+    assert_expands_to(
+        "derive_more::Display(\"<{}>\", _0), prefix = \"A\", \
+         derive(derive_more::From, derive_more::AsRef(forward))",
+        "struct Wrapper(Inner![u8]);",
+        "#[doc(hidden)]
+        #[allow(dead_code)]
+        type A41063502c15473a4 = Inner![u8];
+        #[derive(derive_more::Display, derive_more::From, derive_more::AsRef)]
+        #[display(\"<{}>\", _0)]
+        #[as_ref(forward)]
+        struct Wrapper(A41063502c15473a4);",
+    );
+}
+
+#[test]
+fn test_golden_repr() {
+    assert_expands_to(
+        "bytemuck::Pod, bytemuck::Zeroable, prefix = \"A\", repr(C)",
+        "#[allow(dead_code)]
+        struct Vertex { position: Vec3![f32] }",
+        "#[doc(hidden)]
+        #[allow(dead_code)]
+        type Aa24dea9b9d6c0ed8 = Vec3![f32];
+        #[allow(dead_code)]
+        #[repr(C)]
+        #[derive(bytemuck::Pod, bytemuck::Zeroable)]
+        struct Vertex { position: Aa24dea9b9d6c0ed8 }",
+    );
+}
+
+#[test]
+fn test_golden_prost_attributes() {
+    assert_expands_to(
+        "Clone, PartialEq, prost::Message, prefix = \"A\"",
+        "struct Person {
+            #[prost(string, tag = \"1\")]
+            name: Text![],
+            #[prost(int32, repeated, tag = \"2\")]
+            scores: Many![i32],
+            #[prost(message, optional, tag = \"3\")]
+            #[doc = \"Home address\"]
+            address: Opt![Address],
+        }",
+        "#[doc(hidden)]
+        #[allow(dead_code)]
+        type A74fe265bf0f9420a = Text![];
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        type Aaaaa2f72f5e9695c = Many![i32];
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        type Ab449b60628148b58 = Opt![Address];
+        #[derive(Clone, PartialEq, prost::Message)]
+        struct Person {
+            #[prost(string, tag = \"1\")]
+            name: A74fe265bf0f9420a,
+            #[prost(int32, repeated, tag = \"2\")]
+            scores: Aaaaa2f72f5e9695c,
+            #[prost(message, optional, tag = \"3\")]
+            #[doc = \"Home address\"]
+            address: Ab449b60628148b58,
+        }",
+    );
+}
+
+#[test]
+fn test_golden_row_attributes() {
+    assert_expands_to(
+        "sqlx::FromRow, diesel::Queryable, prefix = \"A\", attr(diesel(table_name = users))",
+        "pub struct User {
+            pub id: SqlType![bigint],
+            #[sqlx(rename = \"user_name\")]
+            #[diesel(column_name = user_name)]
+            pub name: SqlType![nullable text],
+        }",
+        "#[doc(hidden)]
+        #[allow(dead_code)]
+        pub type A6ca3e358a8ef43ac = SqlType![bigint];
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        pub type Ab844807b735f85b9 = SqlType![nullable text];
+        #[derive(sqlx::FromRow, diesel::Queryable)]
+        #[diesel(table_name = users)]
+        pub struct User {
+            pub id: A6ca3e358a8ef43ac,
+            #[sqlx(rename = \"user_name\")]
+            #[diesel(column_name = user_name)]
+            pub name: Ab844807b735f85b9,
+        }",
+    );
+}
+
+#[test]
+fn test_golden_binary_serialization_attributes() {
+    assert_expands_to(
+        "borsh::BorshSerialize, borsh::BorshDeserialize, bincode::Encode, prefix = \"A\", \
+         attr(borsh(use_discriminant = true))",
+        "#[repr(u8)]
+        enum Message<T> {
+            Ping = 0,
+            Data(Pair![T]) = 1,
+        }",
+        "#[doc(hidden)]
+        #[allow(dead_code, type_alias_bounds)]
+        type A3f78578a59869a6e<T> = Pair![T];
+        #[derive(borsh::BorshSerialize, borsh::BorshDeserialize, bincode::Encode)]
+        #[borsh(use_discriminant = true)]
+        #[repr(u8)]
+        enum Message<T> {
+            Ping = 0,
+            Data(A3f78578a59869a6e<T>) = 1,
+        }",
+    );
+}
+
+#[test]
+fn test_golden_container_helper_position() {
+    // Helpers follow their derive, ahead of the item's own attributes
+    assert_expands_to(
+        "borsh::BorshSerialize, prefix = \"A\", derive_position = first, \
+         attr(borsh(use_discriminant = true))",
+        "/// A message
+        #[repr(u8)]
+        #[non_exhaustive]
+        enum Message<T> {
+            Data(Pair![T]) = 1,
+        }",
+        "#[doc(hidden)]
+        #[allow(dead_code, type_alias_bounds)]
+        type A3f78578a59869a6e<T> = Pair![T];
+        /// A message
+        #[derive(borsh::BorshSerialize)]
+        #[borsh(use_discriminant = true)]
+        #[repr(u8)]
+        #[non_exhaustive]
+        enum Message<T> {
+            Data(A3f78578a59869a6e<T>) = 1,
+        }",
+    );
+}
+
+#[test]
+fn test_golden_rewrite_attrs() {
+    // Token and string bounds of the listed helpers refer to the aliases of the fields
+    assert_expands_to(
+        "educe::Educe, derivative::Derivative, prefix = \"A\", rewrite_attrs(educe, derivative)",
+        "#[educe(Debug(bound(Pair![T]: std::fmt::Debug)))]
+        #[derivative(Clone(bound = \"Pair![T]: Clone\"), Default(bound = \"\"))]
+        #[doc = \"Pair![T]\"]
+        struct Line<T> {
+            #[educe(Debug(ignore))]
+            #[derivative(Clone(clone_with = \"clone_pair\"))]
+            ends: Pair![T],
+        }",
+        "#[doc(hidden)]
+        #[allow(dead_code, type_alias_bounds)]
+        type A73c851ca6451b52d<T> = Pair![T];
+        #[derive(educe::Educe, derivative::Derivative)]
+        #[educe(Debug(bound(A73c851ca6451b52d<T> : std::fmt::Debug)))]
+        #[derivative(Clone(bound = \"A73c851ca6451b52d < T > : Clone\"), Default(bound = \"\"))]
+        #[doc = \"Pair![T]\"]
+        struct Line<T> {
+            #[educe(Debug(ignore))]
+            #[derivative(Clone(clone_with = \"clone_pair\"))]
+            ends: A73c851ca6451b52d<T>,
+        }",
+    );
+}
+
+#[test]
+fn test_golden_reflect_attributes() {
+    // `type_path` follows the derive and names the aliases after the fields
+    assert_expands_to(
+        "bevy_reflect::Reflect(Default), prefix = \"A\", type_path = \"game\"",
+        "struct Segment { start: Pair![i32] }",
+        "#[doc(hidden)]
+        #[allow(dead_code)]
+        type A_Segment_start = Pair![i32];
+        #[derive(bevy_reflect::Reflect)]
+        #[reflect(Default)]
+        #[type_path = \"game\"]
+        struct Segment { start: A_Segment_start }",
+    );
+}
+
+#[test]
+fn test_golden_schemars_with() {
+    assert_expands_to(
+        "schemars::JsonSchema, prefix = \"A\", vis = \"pub\", \
+         schemars_with(Money![usd] = \"Decimal\", Id![User] = String)",
+        "pub struct Invoice {
+            pub total: Money![usd],
+            #[schemars(with = \"u64\")]
+            pub user: Id![User],
+            pub lines: Vec<Money![usd]>,
+        }",
+        "#[doc(hidden)]
+        #[allow(dead_code)]
+        pub type Ae90104a26ab64657 = Money![usd];
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        pub type Aae9ef741f2e601a3 = Id![User];
+        #[derive(schemars::JsonSchema)]
+        pub struct Invoice {
+            #[schemars(with = \"Decimal\")]
+            pub total: Ae90104a26ab64657,
+            #[schemars(with = \"u64\")]
+            pub user: Aae9ef741f2e601a3,
+            pub lines: Vec<Ae90104a26ab64657>,
+        }",
+    );
+}
+
+#[test]
+fn test_golden_field_cfg() {
+    // Aliases exist wherever one of the fields using them does
+    assert_expands_to(
+        "Debug, prefix = \"A\"",
+        "struct S {
+            #[cfg(feature = \"a\")]
+            a: Gated![u8],
+            #[cfg(feature = \"b\")]
+            #[cfg(unix)]
+            b: Gated![u8],
+            #[cfg(feature = \"a\")]
+            c: Pair![u8],
+            d: Pair![u8],
+        }",
+        "#[cfg(any(feature = \"a\", all(feature = \"b\", unix)))]
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        type A1ef2e9ff8d34578b = Gated![u8];
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        type A1c0ad3eef5d718d2 = Pair![u8];
+        #[derive(Debug)]
+        struct S {
+            #[cfg(feature = \"a\")]
+            a: A1ef2e9ff8d34578b,
+            #[cfg(feature = \"b\")]
+            #[cfg(unix)]
+            b: A1ef2e9ff8d34578b,
+            #[cfg(feature = \"a\")]
+            c: A1c0ad3eef5d718d2,
+            d: A1c0ad3eef5d718d2,
+        }",
+    );
+}
+
+#[test]
+fn test_golden_variant_cfg() {
+    assert_expands_to(
+        "Debug, prefix = \"A\"",
+        "enum E {
+            #[cfg(feature = \"std\")]
+            Owned(Gated![u8], #[cfg(unix)] Gated![u16]),
+            Plain(u8),
+        }",
+        "#[cfg(feature = \"std\")]
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        type Afb111c38c0596d19 = Gated![u8];
+        #[cfg(all(feature = \"std\", unix))]
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        type A9fe3698d4da8fecc = Gated![u16];
+        #[derive(Debug)]
+        enum E {
+            #[cfg(feature = \"std\")]
+            Owned(Afb111c38c0596d19, #[cfg(unix)] A9fe3698d4da8fecc),
+            Plain(u8),
+        }",
+    );
+}
+
+#[test]
+fn test_golden_attribute_order() {
+    let item = "/// Docs
+        #[allow(dead_code)]
+        #[derive(Clone)]
+        #[deny(missing_docs)]
+        #[repr(C)]
+        #[must_use]
+        #[non_exhaustive]
+        pub struct S {
+            /// Field docs
+            #[allow(unused)]
+            #[macro_alias]
+            #[deprecated]
+            pub field: u8,
+        }";
+    let expected = [
+        (
+            "first",
+            "/// Docs
+            #[allow(dead_code)]
+            #[deny(missing_docs)]
+            #[derive(Debug, Clone)]
+            #[repr(C)]
+            #[must_use]
+            #[non_exhaustive]",
+        ),
+        (
+            "last",
+            "/// Docs
+            #[allow(dead_code)]
+            #[deny(missing_docs)]
+            #[repr(C)]
+            #[must_use]
+            #[non_exhaustive]
+            #[derive(Debug, Clone)]",
+        ),
+        (
+            "preserve",
+            "/// Docs
+            #[allow(dead_code)]
+            #[derive(Debug, Clone)]
+            #[deny(missing_docs)]
+            #[repr(C)]
+            #[must_use]
+            #[non_exhaustive]",
+        ),
+    ];
+    for (position, attrs) in expected {
+        assert_expands_to(
+            &format!("Debug, prefix = \"A\", derive_position = {}", position),
+            item,
+            &format!(
+                "#[doc(hidden)]
+                #[allow(dead_code)]
+                pub type A4281e0553486bbd9 = u8;
+                {attrs}
+                pub struct S {{
+                    /// Field docs
+                    #[allow(unused)]
+                    #[deprecated]
+                    pub field: A4281e0553486bbd9,
+                }}"
+            ),
+        );
+    }
+}
+
+#[test]
+fn test_golden_alias_attrs() {
+    assert_expands_to(
+        "Debug, prefix = \"A\", alias_attrs(allow(clippy::type_complexity), allow(dead_code))",
+        "struct S<T> { a: Pair![T], b: Pair![u8] }",
+        "#[allow(clippy::type_complexity)]
+        #[allow(dead_code)]
+        #[doc(hidden)]
+        #[allow(dead_code, type_alias_bounds)]
+        type Ae37bf5ba91ecb610<T> = Pair![T];
+        #[allow(clippy::type_complexity)]
+        #[allow(dead_code)]
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        type A5e9432a7ad7c75bc = Pair![u8];
+        #[derive(Debug)]
+        struct S<T> { a: Ae37bf5ba91ecb610<T>, b: A5e9432a7ad7c75bc }",
+    );
+}
+
+#[test]
+fn test_golden_doc_original() {
+    assert_expands_to(
+        "Debug, serde::Serialize, prefix = \"A\", doc_original = true",
+        "/// An operator
+        #[serde(rename_all = \"lowercase\")]
+        pub enum Op<T> {
+            /// Addition
+            Add(#[serde(skip)] Symbol![\"+\"], Pair![T]),
+            #[serde(rename = \"neg\")]
+            Neg { operand: T },
+        }",
+        "#[doc(hidden)]
+        #[allow(dead_code)]
+        pub type Aae332ebeeb397702 = Symbol![\"+\"];
+        #[doc(hidden)]
+        #[allow(dead_code, type_alias_bounds)]
+        pub type A3171e24e054379f8<T> = Pair![T];
+        #[cfg(not(doc))]
+        /// An operator
+        #[derive(Debug, serde::Serialize)]
+        #[serde(rename_all = \"lowercase\")]
+        pub enum Op<T> {
+            /// Addition
+            Add(#[serde(skip)] Aae332ebeeb397702, A3171e24e054379f8<T>),
+            #[serde(rename = \"neg\")]
+            Neg { operand: T },
+        }
+        #[cfg(doc)]
+        /// An operator
+        pub enum Op<T> {
+            /// Addition
+            Add(Symbol![\"+\"], Pair![T]),
+            Neg { operand: T },
+        }",
+    );
+}
+
+#[test]
+fn test_golden_readable_names() {
+    assert_expands_to(
+        "Debug, readable_names = true",
+        "struct MyStruct<T> {
+            fn_token: Token![fn],
+            span: (Pair![T], Pair![u8]),
+            buffer: [u8; len!()],
+            r#type: Token![fn],
+        }",
+        "#[doc(hidden)]
+        #[allow(dead_code)]
+        type __TypeMacroAlias_MyStruct_fn_token = Token![fn];
+        #[doc(hidden)]
+        #[allow(dead_code, type_alias_bounds)]
+        type __TypeMacroAlias_MyStruct_span<T> = Pair![T];
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        type __TypeMacroAlias_MyStruct_span1 = Pair![u8];
+        #[doc(hidden)]
+        #[allow(dead_code, non_upper_case_globals)]
+        const __TypeMacroAlias_MyStruct_buffer: usize = len!();
+        #[derive(Debug)]
+        struct MyStruct<T> {
+            fn_token: __TypeMacroAlias_MyStruct_fn_token,
+            span: (__TypeMacroAlias_MyStruct_span<T>, __TypeMacroAlias_MyStruct_span1),
+            buffer: [u8; __TypeMacroAlias_MyStruct_buffer],
+            r#type: __TypeMacroAlias_MyStruct_fn_token,
+        }",
+    );
+    assert_expands_to(
+        "Debug, prefix = \"__MacroTy\", readable_names = true",
+        "enum Shape<const N: Len![]> { Line { from: Pair![i8] }, Point(Pair![i8], Pair![u8]) }",
+        "#[doc(hidden)]
+        #[allow(dead_code)]
+        type __MacroTy_Shape_Line_from = Pair![i8];
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        type __MacroTy_Shape_Point_1 = Pair![u8];
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        type __MacroTy_Shape_Len = Len![];
+        #[derive(Debug)]
+        enum Shape<const N: __MacroTy_Shape_Len> {
+            Line { from: __MacroTy_Shape_Line_from },
+            Point(__MacroTy_Shape_Line_from, __MacroTy_Shape_Point_1)
+        }",
+    );
+}
+
+#[test]
+fn test_golden_document_aliases() {
+    assert_expands_to(
+        "Debug, readable_names = true, document_aliases = true, prefix = \"Op\"",
+        "pub enum Expr<'a, T: Clone> where Sym![\"+\"]: Clone {
+            Add { op: Sym![\"+\"], operands: Pair![&'a T] },
+            Call(Sym![\"()\"], Callback![fn(T) -> u8], [u8; len!(T::N)]),
+            Neg(Sym![\"+\"], [u8; len!(N)]),
+        }",
+        "#[doc = \" Alias of `Sym![\\\"+\\\"]`, used by the field `Expr::Add::op`, the field \
+        `Expr::Neg::0` and `Expr`\"]
+        #[allow(dead_code)]
+        pub type Op_Expr_Add_op = Sym![\"+\"];
+        #[doc = \" Alias of `Pair![&'a T]`, used by the field `Expr::Add::operands`\"]
+        #[allow(dead_code, type_alias_bounds)]
+        pub type Op_Expr_Add_operands<'a, T: Clone> = Pair![&'a T];
+        #[doc = \" Alias of `Sym![\\\"()\\\"]`, used by the field `Expr::Call::0`\"]
+        #[allow(dead_code)]
+        pub type Op_Expr_Call_0 = Sym![\"()\"];
+        #[doc = \" Alias of `Callback![fn(T) -> u8]`, used by the field `Expr::Call::1`\"]
+        #[allow(dead_code, type_alias_bounds)]
+        pub type Op_Expr_Call_1<T: Clone> = Callback![fn(T) -> u8];
+        #[doc = \" Alias of `len!(N)`, used by the field `Expr::Neg::1`\"]
+        #[allow(dead_code, non_upper_case_globals)]
+        pub const Op_Expr_Neg_1: usize = len!(N);
+        #[derive(Debug)]
+        pub enum Expr<'a, T: Clone> where Op_Expr_Add_op: Clone {
+            Add { op: Op_Expr_Add_op, operands: Op_Expr_Add_operands<'a, T> },
+            Call(Op_Expr_Call_0, Op_Expr_Call_1<T>, [u8; len!(T::N)]),
+            Neg(Op_Expr_Add_op, [u8; Op_Expr_Neg_1]),
+        }",
+    );
+}
+
+#[test]
+fn test_golden_shared_aliases() {
+    assert_expands_to(
+        "Debug, prefix = \"A\"",
+        "enum BinOp<S> {
+            Add(WithSpan<Symbol![\"+\"], S>),
+            Sub(WithSpan<Symbol![\"-\"], S>),
+            AddAssign(WithSpan<Symbol![\"+\"], S>, WithSpan<Symbol![\"=\"], S>),
+            Plus { op: WithSpan<Symbol!(\"+\"), S> },
+            Sum(Vec<WithSpan<Symbol!{\"+\"}, S>>),
+            Inc(WithSpan<Symbol![\"+\"], S>, WithSpan<Symbol![\"+\"], S>),
+            Pos(Option<WithSpan<Symbol![\"+\"], S>>),
+            Concat(WithSpan<Symbol![\"+\"], S>),
+            Neg(WithSpan<Symbol![\"-\"], S>),
+            Assign(WithSpan<Symbol![\"=\"], S>),
+        }",
+        "#[doc(hidden)]
+        #[allow(dead_code)]
+        type A9d2980b9f4e5211c = Symbol![\"+\"];
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        type A691253da3d81786a = Symbol![\"-\"];
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        type A9c04613266bcd89a = Symbol![\"=\"];
+        #[derive(Debug)]
+        enum BinOp<S> {
+            Add(WithSpan<A9d2980b9f4e5211c, S>),
+            Sub(WithSpan<A691253da3d81786a, S>),
+            AddAssign(WithSpan<A9d2980b9f4e5211c, S>, WithSpan<A9c04613266bcd89a, S>),
+            Plus { op: WithSpan<A9d2980b9f4e5211c, S> },
+            Sum(Vec<WithSpan<A9d2980b9f4e5211c, S>>),
+            Inc(WithSpan<A9d2980b9f4e5211c, S>, WithSpan<A9d2980b9f4e5211c, S>),
+            Pos(Option<WithSpan<A9d2980b9f4e5211c, S>>),
+            Concat(WithSpan<A9d2980b9f4e5211c, S>),
+            Neg(WithSpan<A691253da3d81786a, S>),
+            Assign(WithSpan<A9c04613266bcd89a, S>),
+        }",
+    );
+}
+
+#[test]
+fn test_golden_qualify_derives() {
+    assert_expands_to(
+        "Debug, Clone, serde::Serialize, cfg(test, PartialEq, Eq), prefix = \"A\", \
+         qualify_derives = true",
+        "struct Token { kind: Kind![punct] }",
+        "#[doc(hidden)]
+        #[allow(dead_code)]
+        type A7507c4999a5e820b = Kind![punct];
+        #[derive(::core::fmt::Debug, ::core::clone::Clone, serde::Serialize)]
+        #[cfg_attr(test, derive(::core::cmp::PartialEq, ::core::cmp::Eq))]
+        struct Token { kind: A7507c4999a5e820b }",
+    );
+}
+
+#[test]
+fn test_golden_split_derives() {
+    assert_expands_to(
+        "Debug, Clone, serde::Serialize, cfg(test, PartialEq, Eq), prefix = \"A\", \
+         split_derives = true, attr(serde(transparent))",
+        "struct Token { kind: Kind![punct] }",
+        "#[doc(hidden)]
+        #[allow(dead_code)]
+        type A7507c4999a5e820b = Kind![punct];
+        #[derive(Debug)]
+        #[derive(Clone)]
+        #[derive(serde::Serialize)]
+        #[cfg_attr(test, derive(PartialEq))]
+        #[cfg_attr(test, derive(Eq))]
+        #[serde(transparent)]
+        struct Token { kind: A7507c4999a5e820b }",
+    );
+}
+
+#[test]
+fn test_golden_introspect() {
+    assert_expands_to(
+        "Debug, prefix = \"A\", introspect = true",
+        "pub enum HTTPRequest<T> {
+            Get { path: Path![T], #[cfg(test)] query: Vec<Query![T]>, version: u8 },
+            Post(Path![T]),
+        }",
+        "#[doc(hidden)]
+        #[allow(dead_code, type_alias_bounds)]
+        pub type A4ba4faa0a5f10c69<T> = Path![T];
+        #[cfg(test)]
+        #[doc(hidden)]
+        #[allow(dead_code, type_alias_bounds)]
+        pub type Af3e022abcbe5ea20<T> = Query![T];
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        pub const HTTP_REQUEST_MACRO_TYPES: &[(&str, &str)] = &[
+            (\"Get::path\", \"Path![T]\"),
+            #[cfg(test)] (\"Get::query\", \"Vec<Query![T]>\"),
+            (\"Post::0\", \"Path![T]\")
+        ];
+        #[derive(Debug)]
+        pub enum HTTPRequest<T> {
+            Get { path: A4ba4faa0a5f10c69<T>, #[cfg(test)] query: Vec<Af3e022abcbe5ea20<T> >, version: u8 },
+            Post(A4ba4faa0a5f10c69<T>),
+        }",
+    );
+}
+
+#[test]
+fn test_golden_assert_traits() {
+    assert_expands_to(
+        "Debug, prefix = \"A\", assert_traits(Send, Sync)",
+        "pub struct Cache<'a, T: Ord = u8> where T: 'a {
+            pub key: Key![&'a T],
+            #[cfg(feature = \"stats\")]
+            pub hits: Counter![],
+            pub name: String,
+        }",
+        "#[doc(hidden)]
+        #[allow(dead_code, type_alias_bounds)]
+        pub type A51943ae7beed9ed6<'a, T: Ord> = Key![&'a T];
+        #[cfg(feature = \"stats\")]
+        #[doc(hidden)]
+        #[allow(dead_code)]
+        pub type Af17a6fae4cf8c3d0 = Counter![];
+        const _: () = {
+            fn _assert<T: ?Sized + Send>() {}
+            fn _check<'a, T: Ord>(_: &Cache<'a, T>) where T: Send + Sync, T: 'a {
+                _assert::<A51943ae7beed9ed6<'a, T> >();
+            }
+        };
+        const _: () = {
+            fn _assert<T: ?Sized + Sync>() {}
+            fn _check<'a, T: Ord>(_: &Cache<'a, T>) where T: Send + Sync, T: 'a {
+                _assert::<A51943ae7beed9ed6<'a, T> >();
+            }
+        };
+        #[cfg(feature = \"stats\")]
+        const _: () = {
+            fn _assert<T: ?Sized + Send>() {}
+            fn _check<'a, T: Ord>(_: &Cache<'a, T>) where T: Send + Sync, T: 'a {
+                _assert::<Af17a6fae4cf8c3d0>();
+            }
+        };
+        #[cfg(feature = \"stats\")]
+        const _: () = {
+            fn _assert<T: ?Sized + Sync>() {}
+            fn _check<'a, T: Ord>(_: &Cache<'a, T>) where T: Send + Sync, T: 'a {
+                _assert::<Af17a6fae4cf8c3d0>();
+            }
+        };
+        #[derive(Debug)]
+        pub struct Cache<'a, T: Ord = u8> where T: 'a {
+            pub key: A51943ae7beed9ed6<'a, T>,
+            #[cfg(feature = \"stats\")]
+            pub hits: Af17a6fae4cf8c3d0,
+            pub name: String,
+        }",
+    );
+}
+
+#[test]
+fn test_golden_keep_original() {
+    assert_expands_to(
+        "Debug, serde::Serialize, prefix = \"A\", repr(C), keep_original = RawFrame",
+        "/// A frame
+        #[serde(rename_all = \"camelCase\")]
+        pub struct Frame<T> {
+            #[serde(rename = \"h\")]
+            pub header: Header![T],
+            pub payload: [u8; 4],
+        }",
+        "#[doc(hidden)]
+        #[allow(dead_code, type_alias_bounds)]
+        pub type A64fdc7e1e365ce73<T> = Header![T];
+        /// A frame
+        #[repr(C)]
+        #[derive(Debug, serde::Serialize)]
+        #[serde(rename_all = \"camelCase\")]
+        pub struct Frame<T> {
+            #[serde(rename = \"h\")]
+            pub header: A64fdc7e1e365ce73<T>,
+            pub payload: [u8; 4],
+        }
+        /// A frame
+        #[repr(C)]
+        pub struct RawFrame<T> {
+            pub header: Header![T],
+            pub payload: [u8; 4],
+        }",
+    );
+}
+
+#[test]
+fn test_golden_group_and_callback() {
+    // The re-invocations are emitted as by the attribute
+    assert_expands_to(
+        "Debug, @std",
+        "struct Point<T> { coords: Pair![T] }",
+        "__macro_derive_group_std! {
+            { ::type_macro_derive_tricks::macro_derive }
+            { , stable_names = true, Debug }
+            struct Point<T> { coords: Pair![T] }
+        }",
+    );
+    assert_expands_to(
+        "Debug, callback = expand_pairs",
+        "struct Point<T> { coords: Pair![T] }",
+        "expand_pairs! {
+            #[::type_macro_derive_tricks::macro_derive(stable_names = true, Debug)]
+            struct Point<T> { coords: Pair![T] }
+        }",
+    );
+}
@@ -2,419 +2,277 @@
 
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
-use rand::{distributions::Alphanumeric, Rng};
-use std::collections::HashMap;
-use syn::{
-    parse_macro_input, punctuated::Punctuated, Data, DeriveInput, Fields, Generics, Ident, Type,
-};
-use template_quote::quote;
+use type_macro_derive_tricks_core as tricks_core;
 
 /// Main procedural macro that handles types with macros in type positions
 ///
-/// Usage: `#[macro_derive(Trait1, Trait2, ...)]`
+/// Usage: `#[macro_derive(Trait1, Trait2, ...)]` or
+/// `#[macro_derive(derive(Trait1, Trait2, ...), option = "value", ...)]`
 ///
 /// This macro:
 /// 1. Identifies all macro invocations in type positions
 /// 2. Generates unique type aliases for each macro type
 /// 3. Replaces the macro types with the aliases
 /// 4. Applies the specified derive traits to the transformed type
+///
+/// Options:
+/// - `derive(...)`: traits to derive, equivalent to listing them directly.
+/// - `@name`: the entries of the derive group `name` declared with [`define_derive_group`].
+/// - `Trait(args)`: derive `Trait` and pass `args` to its helper attribute named after it in
+///   snake case, e.g. `derive_more::Display("{}", _0)` adds `#[display("{}", _0)]`.
+/// - `vis = "pub(crate)"`: visibility of the generated type aliases. Defaults to
+///   the visibility of the annotated item.
+/// - `prefix = "..."`: prefix of the generated type alias names. Defaults to
+///   `__TypeMacroAlias`.
+/// - `derive_position = first | last | preserve`: where the generated `#[derive]` is
+///   placed among the attributes of the item. `first` (the default) places it after the
+///   leading doc comments and lint attributes and before all other attributes, so that helper
+///   attributes follow it, `last` after all of them, and `preserve` at the position of the
+///   first existing `#[derive]` on the item. The other attributes of the item and its fields
+///   keep their order in every case.
+/// - `split_derives = true`: emit one `#[derive]` attribute per trait instead of a combined
+///   one, and one `#[cfg_attr(predicate, derive(Trait))]` per conditional trait.
+/// - `cfg(predicate, Trait1, Trait2, ...)`: traits derived only when `predicate` holds,
+///   emitted as `#[cfg_attr(predicate, derive(Trait1, Trait2, ...))]`.
+/// - `qualify_derives = true`: emit the standard derives written as a single identifier, such
+///   as `Debug` or `Clone`, with their `::core` path, so that derive macros of the same name in
+///   scope are not picked up instead.
+/// - `attr(...)`: attributes appended to the transformed item right after the generated
+///   derive, e.g. `attr(serde(deny_unknown_fields))` emits `#[serde(deny_unknown_fields)]`.
+/// - `alias_attrs(...)`: attributes added to the generated type aliases, e.g.
+///   `alias_attrs(allow(clippy::type_complexity))` to silence lints they trigger. Aliases shared
+///   by several items of a module get the attributes of all of them.
+/// - `repr(...)`: representation attribute emitted right before the generated derive, for
+///   layout derives such as those of zerocopy and bytemuck.
+/// - `rewrite_attrs(educe, derivative, ...)`: replace the macro types in the arguments of the
+///   named helper attributes on the item, its variants and fields with their aliases,
+///   including where predicates in string literals such as `bound = "Pair![T]: Clone"`.
+/// - `schemars_with(Macro![x] = "Type", ...)`: add `#[schemars(with = "Type")]` to the fields
+///   whose type is one of the given invocations, unless they choose their schema already.
+/// - `where_clause(Pair![T]: Copy, ...)`: predicates appended to the where clause of the
+///   transformed item, so that derives copy them to their impls. Macro types in them are
+///   replaced with their aliases like those of the fields.
+/// - `inline(Macro![x] = "Vec<String>", ...)`: replace the given invocations with their
+///   expansion instead of an alias, for derives which inspect field types syntactically, such
+///   as `Option<...>` and `Vec<...>` fields of clap. The expansion is used as written.
+/// - `inline_rules(macro_rules! Name { ... } ...)`: replace the invocations of the given
+///   macros with their expansion instead of an alias, matching them against the rules like
+///   `macro_rules!` does. The given macros in the expansion are expanded in turn, and other
+///   macro types in it are aliased. Invocations which no rule matches, which match a rule
+///   ambiguously, which do not expand to a type or which use `$crate` are aliased as usual.
+/// - `deep = true`: also alias type macros nested in the arguments of other type macros, as
+///   in `Outer![Inner![T]]`. The inner invocation is replaced by its alias before the outer
+///   one is aliased, so every macro invocation followed by a delimited group in the
+///   arguments must expand to a type.
+/// - `stable_names = true`: derive the generated names from the item and the macro
+///   invocation instead of choosing them randomly, so that they stay the same across
+///   builds. This keeps IDE hovers and completions from changing on every expansion.
+/// - `stable_api = true`: derive the generated names from the item and the invocation only,
+///   independently of the order and spacing of the fields. The derivation does not change
+///   within a major version of this crate, so that public aliases can be named downstream.
+///   It cannot be combined with `readable_names`.
+/// - `readable_names = true`: name the generated items after the item and the field using them,
+///   as in `__TypeMacroAlias_MyStruct_fn_token`, or the macro outside of fields. Names which
+///   are taken get a numeric suffix. This makes compiler errors and expanded code readable.
+/// - `type_path = "module::path"`: emit bevy_reflect's `#[type_path = "module::path"]` after
+///   the derive, and name the generated items as `readable_names` does unless `stable_api`
+///   is set, so that reflected items are registered the same way in every build.
+/// - `document_aliases = true`: document the generated items with the macro invocation they
+///   stand for and the fields using it, instead of hiding them with `#[doc(hidden)]`.
+/// - `perfect_bounds = true`: implement `Clone`, `Debug`, `PartialEq`, `Eq`, `Hash` and, for
+///   structs, `Default` by hand instead of deriving them. The impls are bounded by the field
+///   types mentioning generic parameters, as in `where Alias<T>: Clone`, rather than by every
+///   generic parameter.
+/// - `bound = "T: Trait, ..."`: where predicates replacing the inferred bounds of the impls
+///   written by `perfect_bounds`, also passed to serde derives as `#[serde(bound = "...")]`.
+/// - `serde_bounds = true`: pass `#[serde(bound(serialize = "...", deserialize = "..."))]` to
+///   serde derives, bounding the field types mentioning generic parameters rather than the
+///   parameters themselves. Fields skipped or (de)serialized `with` custom functions are left
+///   out. An explicit `bound` takes precedence.
+/// - `serde_borrow = true`: add `#[serde(borrow)]` to the fields referring to aliases with
+///   lifetime parameters, which serde cannot recognize as borrowed by itself. Fields skipped
+///   when deserializing or already marked are left as they are.
+/// - `expose_types = true`: also generate a trait named after the item with a `Types` suffix,
+///   implemented by the item, with one associated type per field whose type was rewritten.
+///   Associated types are named after their field, `_0`, `_1`, ... for unnamed fields, and
+///   prefixed with the variant name in enums, as in `Line_to`.
+/// - `introspect = true`: also generate a hidden constant named after the item in upper case
+///   with a `_MACRO_TYPES` suffix, listing the name of every field whose type was rewritten
+///   together with the type as written, as in `("range", "Pair![T]")`. Fields of enums are
+///   prefixed with their variant, as in `Line::to`.
+/// - `assert_traits(Send, Sync)`: check that every alias used by the fields implements each
+///   listed trait, assuming the generic parameters implement all of them, and report a
+///   missing implementation at the first field using the alias.
+/// - `phantom = true`: add a hidden `PhantomData` field to structs and unions whose generic
+///   parameters are no longer used by any field once the macro types are aliased, which
+///   happens when a parameter only appears in a non-type position of the macro arguments.
+///   Without it, such parameters are reported as errors.
+/// - `doc_original = true`: under `cfg(doc)`, emit the item with its fields as written instead
+///   of the rewritten item, so that rustdoc renders the types the macros expand to rather than
+///   the names of hidden aliases. Items with macro types cannot be derived, so the derived
+///   impls are missing from the documentation, and the helper attributes of derives are
+///   removed from the copy. Impls written by hand, e.g. with `perfect_bounds`, are documented.
+/// - `keep_original = "Name"`: also emit the item with its fields as written under the name
+///   `Name`, without derives and with only the attributes of the compiler, like the copy of
+///   `doc_original`.
+/// - `callback = path`: emit `path! { item }` instead, where the item carries the attribute
+///   with its other arguments, for macros which need to rewrite the item before it is aliased
+///   and derived, as in continuation-passing `macro_rules!`. Only the outer attribute of an
+///   item accepts it.
+/// - `crate = path`: the path of this crate, for crates re-exporting it, used where the
+///   attribute is invoked again by derive groups and callbacks. Defaults to
+///   `::type_macro_derive_tricks`.
+/// - `debug`: print the generated aliases and the transformed item to stderr during
+///   compilation. Setting the `TYPE_MACRO_DERIVE_DEBUG=1` environment variable enables this
+///   for every invocation.
+/// - `dump`: write the generated items, formatted, to `type_macro_derive/<crate>/<Item>.rs`
+///   in `OUT_DIR`, `CARGO_TARGET_DIR` or the temporary directory, or to
+///   `<crate>/<source>/<Item>.rs` with the `dump-source-paths` feature, where `<source>` is
+///   the file of the item without its extension. The files are replaced on every compilation. Setting the `TYPE_MACRO_DERIVE_DUMP=1`
+///   environment variable enables this for every invocation, and setting it to a directory
+///   writes there instead.
+/// - `strict`: fail with an error pointing at every macro type which could not be replaced
+///   with an alias, instead of leaving it to the derives.
+///
+/// Field options:
+/// - `#[macro_derive(skip)]`: leave the macro types of this field untouched.
+/// - `#[macro_alias]`: generate an alias for the whole field type, even if it is not a
+///   macro invocation.
 #[proc_macro_attribute]
 pub fn macro_derive(args: TokenStream, input: TokenStream) -> TokenStream {
-    let derive_traits = parse_derive_traits(args);
-    let input = parse_macro_input!(input as DeriveInput);
-
-    let expanded = impl_type_macro_derive_tricks(&derive_traits, &input);
-    TokenStream::from(expanded)
+    with_recovery(input, |input| tricks_core::macro_derive(args.into(), input))
 }
 
-fn parse_derive_traits(args: TokenStream) -> Vec<syn::Path> {
-    let args = TokenStream2::from(args);
-
-    if args.is_empty() {
-        return Vec::new();
-    }
-
-    // Parse comma-separated list of trait names
-    let mut traits = Vec::new();
-    let mut current_trait = String::new();
-
-    for token in args.into_iter() {
-        match token {
-            proc_macro2::TokenTree::Punct(punct) if punct.as_char() == ',' => {
-                if !current_trait.is_empty() {
-                    if let Ok(path) = syn::parse_str::<syn::Path>(current_trait.trim()) {
-                        traits.push(path);
-                    }
-                    current_trait.clear();
-                }
-            }
-            _ => {
-                current_trait.push_str(&token.to_string());
-            }
-        }
-    }
-
-    // Don't forget the last trait
-    if !current_trait.is_empty() {
-        if let Ok(path) = syn::parse_str::<syn::Path>(current_trait.trim()) {
-            traits.push(path);
-        }
-    }
-
-    traits
-}
-
-fn impl_type_macro_derive_tricks(derive_traits: &[syn::Path], input: &DeriveInput) -> TokenStream2 {
-    let mut macro_types = HashMap::new();
-    let mut type_aliases = Vec::new();
-
-    // Step 1: Collect all macro types and generate aliases
-    collect_macro_types(&input.data, &input.generics, &mut macro_types);
-
-    // Step 2: Generate type aliases
-    for (macro_type, alias_name) in &macro_types {
-        // Generate type aliases with only the specific generic parameters used by the macro
-        // and add #[doc(hidden)] to hide them from documentation
-        let used_generic_params = get_used_generic_params(macro_type, &input.generics);
-
-        let alias = if used_generic_params.is_empty() {
-            quote! {
-                #[doc(hidden)]
-                type #alias_name = #macro_type;
-            }
-        } else {
-            // Create a filtered Generics struct with only the used parameters
-            let filtered_generics = create_filtered_generics(&used_generic_params)
-                .params
-                .into_iter()
-                .map(|mut param| {
-                    match &mut param {
-                        syn::GenericParam::Type(tp) => {
-                            tp.eq_token = None;
-                            tp.default = None;
-                        }
-                        syn::GenericParam::Const(cp) => {
-                            cp.eq_token = None;
-                            cp.default = None;
-                        }
-                        _ => (),
-                    }
-                    param
-                })
-                .collect::<Punctuated<_, syn::Token![,]>>();
-            quote! {
-                #[doc(hidden)]
-                type #alias_name <#filtered_generics> = #macro_type;
-            }
-        };
-        type_aliases.push(alias);
-    }
-
-    // Step 3: Transform the original type by replacing macro types with aliases
-    let transformed_input = transform_input(input, &macro_types);
-
-    // Step 4: Generate derive attribute
-    let derive_attrs = if !derive_traits.is_empty() {
-        let traits: Vec<_> = derive_traits.iter().collect();
-        quote! {
-            #[derive(#(#traits),*)]
-        }
-    } else {
-        quote! {}
-    };
-
-    // Step 5: Combine everything
-    quote! {
-        #(#type_aliases)*
-
-        #derive_attrs
-        #transformed_input
-    }
-}
-
-fn collect_macro_types(data: &Data, generics: &Generics, macro_types: &mut HashMap<Type, Ident>) {
-    match data {
-        Data::Struct(data_struct) => {
-            collect_macro_types_from_fields(&data_struct.fields, generics, macro_types);
-        }
-        Data::Enum(data_enum) => {
-            for variant in &data_enum.variants {
-                collect_macro_types_from_fields(&variant.fields, generics, macro_types);
-            }
-        }
-        Data::Union(data_union) => {
-            collect_macro_types_from_fields(
-                &Fields::Named(data_union.fields.clone()),
-                generics,
-                macro_types,
-            );
-        }
-    }
-}
-
-fn collect_macro_types_from_fields(
-    fields: &Fields,
-    generics: &Generics,
-    macro_types: &mut HashMap<Type, Ident>,
-) {
-    match fields {
-        Fields::Named(fields) => {
-            for field in &fields.named {
-                collect_macro_types_from_type(&field.ty, generics, macro_types);
-            }
-        }
-        Fields::Unnamed(fields) => {
-            for field in &fields.unnamed {
-                collect_macro_types_from_type(&field.ty, generics, macro_types);
-            }
-        }
-        Fields::Unit => {}
-    }
-}
-
-fn collect_macro_types_from_type(
-    ty: &Type,
-    _generics: &Generics,
-    macro_types: &mut HashMap<Type, Ident>,
-) {
-    // Handle macro types directly - create aliases only for actual macro invocations
-    if let Type::Macro(_) = ty {
-        if !macro_types.contains_key(ty) {
-            let alias_name = generate_random_type_name();
-            macro_types.insert(ty.clone(), alias_name);
-        }
-        return;
-    }
-
-    // Recursively check all nested types for macro invocations
-    match ty {
-        Type::Path(type_path) => {
-            for segment in &type_path.path.segments {
-                if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
-                    for arg in &args.args {
-                        if let syn::GenericArgument::Type(nested_ty) = arg {
-                            collect_macro_types_from_type(nested_ty, _generics, macro_types);
-                        }
-                    }
-                }
-            }
-        }
-        Type::Array(type_array) => {
-            collect_macro_types_from_type(&type_array.elem, _generics, macro_types);
-        }
-        Type::Ptr(type_ptr) => {
-            collect_macro_types_from_type(&type_ptr.elem, _generics, macro_types);
-        }
-        Type::Reference(type_ref) => {
-            collect_macro_types_from_type(&type_ref.elem, _generics, macro_types);
-        }
-        Type::Slice(type_slice) => {
-            collect_macro_types_from_type(&type_slice.elem, _generics, macro_types);
-        }
-        Type::Tuple(type_tuple) => {
-            for elem in &type_tuple.elems {
-                collect_macro_types_from_type(elem, _generics, macro_types);
-            }
-        }
-        _ => {}
-    }
+/// Attribute that only replaces macro types with generated aliases, without deriving anything
+///
+/// Usage: `#[type_macro_expand]` or `#[type_macro_expand(vis = "...", prefix = "...")]`
+///
+/// Options other than derives, `cfg(...)` and `attr(...)` behave as for [`macro_derive`].
+///
+/// Place it above other attribute macros that cannot handle macro invocations in field types.
+/// Derive attributes on the item are left in place and apply to the transformed item.
+///
+/// It can also be applied to `const` and `static` items, in which case the macro types in the
+/// type annotation are replaced and the initializer is left as written.
+#[proc_macro_attribute]
+pub fn type_macro_expand(args: TokenStream, input: TokenStream) -> TokenStream {
+    with_recovery(input, |input| {
+        tricks_core::type_macro_expand(args.into(), input)
+    })
 }
 
-fn generate_random_type_name() -> Ident {
-    let random_suffix: String = rand::thread_rng()
-        .sample_iter(&Alphanumeric)
-        .take(12)
-        .map(char::from)
-        .collect();
-
-    Ident::new(
-        &format!("__TypeMacroAlias{}", random_suffix),
-        proc_macro2::Span::call_site(),
-    )
+/// Attribute applying [`macro_derive`] to every struct, enum and union of an inline module
+///
+/// Usage: `#[macro_derive_mod(Trait1, Trait2, ...)]` on `mod name { ... }`, with the options of
+/// [`macro_derive`]
+///
+/// All items share one alias per macro type, which is emitted at the top of the module.
+/// Items may carry their own `#[macro_derive(...)]` attribute, whose derives and options are
+/// added to those of the module for that item. Nested modules are left untouched. The
+/// aliases are private to the module unless `vis` is given.
+#[proc_macro_attribute]
+pub fn macro_derive_mod(args: TokenStream, input: TokenStream) -> TokenStream {
+    with_recovery(input, |input| {
+        tricks_core::macro_derive_mod(args.into(), input)
+    })
 }
 
-fn get_used_generic_params(macro_type: &Type, generics: &Generics) -> Vec<syn::GenericParam> {
-    // Analyze which specific generic parameters are used in the macro type
-    let mut used_params = Vec::new();
-
-    if let Type::Macro(type_macro) = macro_type {
-        let macro_tokens = &type_macro.mac.tokens;
-
-        for param in &generics.params {
-            let param_name = match param {
-                syn::GenericParam::Type(type_param) => type_param.ident.to_string(),
-                syn::GenericParam::Lifetime(lifetime_param) => lifetime_param.lifetime.to_string(),
-                syn::GenericParam::Const(const_param) => const_param.ident.to_string(),
-            };
-
-            // Use the improved token search that handles nested structures
-            if is_generic_param_used_in_token_stream(macro_tokens, &param_name) {
-                used_params.push(param.clone());
-            }
-        }
-    }
-
-    used_params
+/// Attribute that replaces macro types in the signatures of an `impl` block with generated
+/// aliases
+///
+/// Usage: `#[macro_impl]` or `#[macro_impl(vis = "...", prefix = "...")]`
+///
+/// Macro types are replaced in the implemented trait and the self type, in the generics and
+/// where clauses of the block and its items, in method signatures, associated types and the
+/// types of associated constants. Method bodies are left as written. Options other than
+/// derives, `cfg(...)` and `attr(...)` behave as for [`macro_derive`].
+#[proc_macro_attribute]
+pub fn macro_impl(args: TokenStream, input: TokenStream) -> TokenStream {
+    with_recovery(input, |input| tricks_core::macro_impl(args.into(), input))
 }
 
-fn is_generic_param_used_in_token_stream(
-    tokens: &proc_macro2::TokenStream,
-    identifier: &str,
-) -> bool {
-    use proc_macro2::TokenTree;
-
-    let tokens_vec: Vec<TokenTree> = tokens.clone().into_iter().collect();
-
-    for (i, token) in tokens_vec.iter().enumerate() {
-        match token {
-            TokenTree::Ident(ident) => {
-                // Handle regular type parameters and const parameters
-                if *ident == identifier {
-                    return true;
-                }
-            }
-            TokenTree::Group(group) => {
-                // Recursively search inside groups (brackets, braces, parentheses)
-                if is_generic_param_used_in_token_stream(&group.stream(), identifier) {
-                    return true;
-                }
-            }
-            TokenTree::Punct(punct) => {
-                // Handle lifetimes: look for ' followed by an identifier
-                if punct.as_char() == '\'' && i + 1 < tokens_vec.len() {
-                    if let TokenTree::Ident(ident) = &tokens_vec[i + 1] {
-                        let lifetime = format!("'{}", ident);
-                        if lifetime == identifier {
-                            return true;
-                        }
-                    }
-                }
-            }
-            TokenTree::Literal(_) => {
-                // Literals don't contain type parameters
-                continue;
-            }
-        }
-    }
-
-    false
+/// Attribute that replaces macro types in the items of a trait definition with generated
+/// aliases
+///
+/// Usage: `#[macro_trait]` or `#[macro_trait(vis = "...", prefix = "...")]`
+///
+/// Macro types are replaced in the generics, supertraits and where clause of the trait, in
+/// method signatures, in the bounds and defaults of associated types and in the types of
+/// associated constants. Default method bodies are left as written, and so are macro types
+/// mentioning `Self`, which a free-standing alias cannot refer to. Options other than derives,
+/// `cfg(...)` and `attr(...)` behave as for [`macro_derive`].
+#[proc_macro_attribute]
+pub fn macro_trait(args: TokenStream, input: TokenStream) -> TokenStream {
+    with_recovery(input, |input| tricks_core::macro_trait(args.into(), input))
 }
 
-fn create_filtered_generics(used_params: &[syn::GenericParam]) -> syn::Generics {
-    // Create a new Generics struct containing only the used parameters
-    let mut generics = syn::Generics::default();
-
-    for param in used_params {
-        generics.params.push(param.clone());
-    }
-
-    generics
+/// Attribute that replaces macro types in the signature of a free function with generated
+/// aliases
+///
+/// Usage: `#[macro_fn]` or `#[macro_fn(vis = "...", prefix = "...")]`
+///
+/// Macro types are replaced in the generics and where clause, the parameter types and the
+/// return type, including inside `impl Trait` bounds. The aliases are emitted next to the
+/// function with its visibility unless `vis` is given, and the body is left as written.
+/// Options other than derives, `cfg(...)` and `attr(...)` behave as for [`macro_derive`].
+#[proc_macro_attribute]
+pub fn macro_fn(args: TokenStream, input: TokenStream) -> TokenStream {
+    with_recovery(input, |input| tricks_core::macro_fn(args.into(), input))
 }
 
-fn transform_input(input: &DeriveInput, macro_types: &HashMap<Type, Ident>) -> DeriveInput {
-    let mut transformed = input.clone();
-
-    match &mut transformed.data {
-        Data::Struct(data_struct) => {
-            transform_fields(&mut data_struct.fields, macro_types, &input.generics);
-        }
-        Data::Enum(data_enum) => {
-            for variant in &mut data_enum.variants {
-                transform_fields(&mut variant.fields, macro_types, &input.generics);
-            }
-        }
-        Data::Union(data_union) => {
-            let mut fields = Fields::Named(data_union.fields.clone());
-            transform_fields(&mut fields, macro_types, &input.generics);
-            if let Fields::Named(named_fields) = fields {
-                data_union.fields = named_fields;
-            }
-        }
-    }
-
-    transformed
+/// Function-like macro replacing the macro types of all items it contains with generated
+/// aliases
+///
+/// Usage: `type_aliased! { item1 item2 ... }`
+///
+/// Each item is rewritten as by the attribute for its kind: structs, enums and unions as by
+/// [`type_macro_expand`], functions as by [`macro_fn`], `impl` blocks as by [`macro_impl`],
+/// traits as by [`macro_trait`], and the type annotations of `const` and `static` items.
+/// Inline modules are rewritten recursively and other items are emitted unchanged. Since it
+/// can be invoked wherever items are allowed, it also reaches items declared inside function
+/// bodies.
+#[proc_macro]
+pub fn type_aliased(input: TokenStream) -> TokenStream {
+    with_recovery(input, tricks_core::type_aliased)
 }
 
-fn transform_fields(fields: &mut Fields, macro_types: &HashMap<Type, Ident>, generics: &Generics) {
-    match fields {
-        Fields::Named(fields) => {
-            for field in &mut fields.named {
-                transform_type(&mut field.ty, macro_types, generics);
-            }
-        }
-        Fields::Unnamed(fields) => {
-            for field in &mut fields.unnamed {
-                transform_type(&mut field.ty, macro_types, generics);
-            }
-        }
-        Fields::Unit => {}
-    }
+/// Function-like macro defining `macro_rules!` type macros together with derive groups
+/// expanding them in place
+///
+/// Usage: `define_type_macro! { macro_rules! Name { ... } ... }`
+///
+/// The definitions are emitted, so that the macros can be invoked anywhere else. Each macro
+/// comes with a derive group, as declared by [`define_derive_group`], named after it, so that
+/// `@Name` in the arguments of [`macro_derive`] or [`macro_derive_mod`] stands for
+/// `inline_rules(macro_rules! Name { ... })`. Using the group counts as a use of the macro,
+/// which is otherwise reported by the `unused_macros` lint like any other macro.
+#[proc_macro]
+pub fn define_type_macro(input: TokenStream) -> TokenStream {
+    with_recovery(input, tricks_core::define_type_macro)
 }
 
-fn transform_type(ty: &mut Type, macro_types: &HashMap<Type, Ident>, generics: &Generics) {
-    // Handle macro types directly
-    if let Type::Macro(_) = ty {
-        // Check if this macro type has an alias
-        if let Some(alias) = macro_types.get(ty) {
-            let used_generic_params = get_used_generic_params(ty, generics);
-
-            if used_generic_params.is_empty() {
-                *ty = syn::parse_quote!(#alias);
-            } else {
-                // Create filtered generics and use them
-                let filtered_generics = create_filtered_generics(&used_generic_params);
-                let (_, ty_generics, _) = filtered_generics.split_for_impl();
-                *ty = syn::parse_quote!(#alias #ty_generics);
-            }
-        }
-        return;
-    }
-
-    // Recursively transform nested types, looking for macro parts within them
-    match ty {
-        Type::Path(type_path) => {
-            for segment in &mut type_path.path.segments {
-                if let syn::PathArguments::AngleBracketed(args) = &mut segment.arguments {
-                    for arg in &mut args.args {
-                        if let syn::GenericArgument::Type(nested_ty) = arg {
-                            transform_type(nested_ty, macro_types, generics);
-                        }
-                    }
-                }
-            }
-        }
-        Type::Array(type_array) => {
-            transform_type(&mut type_array.elem, macro_types, generics);
-        }
-        Type::Ptr(type_ptr) => {
-            transform_type(&mut type_ptr.elem, macro_types, generics);
-        }
-        Type::Reference(type_ref) => {
-            transform_type(&mut type_ref.elem, macro_types, generics);
-        }
-        Type::Slice(type_slice) => {
-            transform_type(&mut type_slice.elem, macro_types, generics);
-        }
-        Type::Tuple(type_tuple) => {
-            for elem in &mut type_tuple.elems {
-                transform_type(elem, macro_types, generics);
-            }
-        }
-        _ => {}
-    }
+/// Function-like macro defining a derive group, a named list of entries of [`macro_derive`]
+///
+/// Usage: `define_derive_group!(name = Trait1, Trait2, option = "value", ...);`
+///
+/// `@name` in the arguments of [`macro_derive`] or [`macro_derive_mod`] stands for the entries
+/// of the group, which may contain derives, options and other groups. The group is a
+/// `macro_rules!` macro, so it is in scope after its definition in the same module and in its
+/// child modules. A visibility such as `pub(crate)` before the name re-exports it, so that it
+/// can be used elsewhere as `@path::to::name`. `pub` is rejected, as `macro_rules!` macros
+/// cannot be re-exported from the crate. Doc comments and other attributes are passed to
+/// the generated macro.
+#[proc_macro]
+pub fn define_derive_group(input: TokenStream) -> TokenStream {
+    with_recovery(input, tricks_core::define_derive_group)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_generate_random_type_name() {
-        let name1 = generate_random_type_name();
-        let name2 = generate_random_type_name();
-
-        assert_ne!(name1, name2);
-        assert!(name1.to_string().starts_with("__TypeMacroAlias"));
-        assert!(name2.to_string().starts_with("__TypeMacroAlias"));
-    }
+/// Run `expand` on `input`, emitting the original items next to the error if it fails
+fn with_recovery(
+    input: TokenStream,
+    expand: impl FnOnce(TokenStream2) -> syn::Result<TokenStream2>,
+) -> TokenStream {
+    let input = TokenStream2::from(input);
+    let expanded = expand(input.clone()).unwrap_or_else(|err| tricks_core::recover(err, input));
+    TokenStream::from(expanded)
 }
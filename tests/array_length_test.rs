@@ -0,0 +1,35 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! frame_len {
+    ($frames:expr) => { $frames * 4 };
+}
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug, Clone, PartialEq, Default)]
+pub struct Frames<T> {
+    pub data: [u8; frame_len!(2)],
+    pub pairs: [Pair![T]; frame_len!(1)],
+    pub nested: Option<[[u16; frame_len!(1)]; 2]>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_array_length_macros() {
+        let frames: Frames<u8> = Frames::default();
+        assert_eq!(frames.data.len(), 8);
+        assert_eq!(frames.pairs.len(), 4);
+        assert_eq!(frames.clone(), frames);
+
+        let nested = Frames {
+            nested: Some([[1u16; 4]; 2]),
+            ..Frames::<i32>::default()
+        };
+        assert_eq!(nested.nested.unwrap()[1], [1; 4]);
+    }
+}
@@ -0,0 +1,50 @@
+use std::sync::Arc;
+use type_macro_derive_tricks::{macro_derive, macro_derive_mod};
+
+macro_rules! Shared {
+    ($t:ty) => { Arc<$t> };
+}
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug, Clone, assert_traits(Send, Sync, Clone))]
+pub struct Cache<'a, T = u8> {
+    pub entries: Shared![Vec<T>],
+    pub range: Pair![&'a T],
+    #[cfg(any())]
+    pub hits: Shared![std::cell::Cell<u8>],
+}
+
+#[macro_derive_mod(Debug, assert_traits(Send, Sync))]
+mod shapes {
+    use std::sync::Arc;
+
+    pub enum Shape<T> {
+        Point(Pair![T]),
+        Shared { inner: Shared![T] },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_send<T: Send>(_: &T) {}
+
+    #[test]
+    fn test_asserted_traits() {
+        let value = 3;
+        let cache = Cache {
+            entries: Arc::new(vec![1u8, 2]),
+            range: (&value, &value),
+        };
+        is_send(&cache);
+        assert_eq!(cache.clone().entries.len(), 2);
+
+        let shape = shapes::Shape::Shared { inner: Arc::new(1) };
+        is_send(&shape);
+        assert!(format!("{:?}", shapes::Shape::Point((1, 2))).contains("Point"));
+    }
+}
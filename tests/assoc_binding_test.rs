@@ -0,0 +1,41 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug, Clone)]
+pub struct AssocBinding<I>
+where
+    I: Iterator<Item = Pair![u8]> + Clone + std::fmt::Debug,
+{
+    pub iter: I,
+}
+
+#[macro_derive(Debug)]
+pub struct AssocInPath<T: AsRef<[Pair![u16]]>> {
+    pub value: T,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assoc_binding() {
+        let instance = AssocBinding {
+            iter: vec![(1, 2), (3, 4)].into_iter(),
+        };
+        let items: Vec<_> = instance.clone().iter.collect();
+        assert_eq!(items, vec![(1, 2), (3, 4)]);
+    }
+
+    #[test]
+    fn test_bound_with_macro_argument() {
+        let instance = AssocInPath {
+            value: vec![(1u16, 2u16)],
+        };
+        let slice: &[(u16, u16)] = instance.value.as_ref();
+        assert_eq!(slice[0], (1, 2));
+    }
+}
@@ -0,0 +1,44 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Same {
+    ($t:ty) => { $t };
+}
+
+pub trait Shape {
+    type Output;
+    const LEN: usize;
+}
+
+impl Shape for u8 {
+    type Output = u16;
+    const LEN: usize = 2;
+}
+
+#[macro_derive(Debug, Clone)]
+pub struct AssociatedPaths<T: Shape, I: Iterator>
+where
+    T::Output: std::fmt::Debug + Clone,
+    I::Item: std::fmt::Debug + Clone,
+{
+    pub shorthand: Same![Vec<T::Output>],
+    pub qualified: Same![Option<<I as Iterator>::Item>],
+    pub sized: Same![[u8; <u8 as Shape>::LEN]],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_associated_paths() {
+        let instance: AssociatedPaths<u8, std::vec::IntoIter<bool>> = AssociatedPaths {
+            shorthand: vec![1u16],
+            qualified: Some(true),
+            sized: [0; 2],
+        };
+        let cloned = instance.clone();
+        assert_eq!(cloned.shorthand, vec![1]);
+        assert_eq!(cloned.qualified, Some(true));
+        assert!(format!("{:?}", instance).contains("AssociatedPaths"));
+    }
+}
@@ -0,0 +1,41 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug, Clone, Copy, attr(repr(C), must_use))]
+pub struct Packed {
+    pub small: Pair![u8],
+    pub large: u32,
+}
+
+#[macro_derive(Debug, attr(repr(u8)))]
+pub enum Tagged {
+    A(Pair![u8]),
+    B,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repr_is_applied() {
+        let instance = Packed {
+            small: (1, 2),
+            large: 3,
+        };
+        let copied = instance;
+        assert_eq!(copied.small, instance.small);
+        assert_eq!(std::mem::size_of::<Packed>(), 8);
+        assert_eq!(std::mem::align_of::<Packed>(), 4);
+    }
+
+    #[test]
+    fn test_enum_repr_is_applied() {
+        assert!(format!("{:?}", Tagged::A((1, 2))).contains('A'));
+        assert!(format!("{:?}", Tagged::B).contains('B'));
+        assert_eq!(std::mem::size_of::<Tagged>(), 3);
+    }
+}
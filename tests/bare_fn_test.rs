@@ -0,0 +1,38 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Clone, Copy)]
+pub struct BareFnFields<T: 'static, U: 'static> {
+    pub convert: fn(Pair![T]) -> Pair![U],
+    pub named: fn(input: Pair![u8], scale: u8) -> Option<Pair![u8]>,
+    pub nested: Option<fn(&Pair![T])>,
+}
+
+fn swap(pair: (i32, i32)) -> (i64, i64) {
+    (pair.1 as i64, pair.0 as i64)
+}
+
+fn scale(input: (u8, u8), scale: u8) -> Option<(u8, u8)> {
+    Some((input.0.checked_mul(scale)?, input.1.checked_mul(scale)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bare_fn_fields() {
+        let instance = BareFnFields {
+            convert: swap,
+            named: scale,
+            nested: None,
+        };
+        let copied = instance;
+        assert_eq!((copied.convert)((1, 2)), (2, 1));
+        assert_eq!((copied.named)((2, 3), 2), Some((4, 6)));
+        assert!(copied.nested.is_none());
+    }
+}
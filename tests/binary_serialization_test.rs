@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(
+    Debug,
+    PartialEq,
+    borsh::BorshSerialize,
+    borsh::BorshDeserialize,
+    bincode::Encode,
+    bincode::Decode,
+    Serialize,
+    Deserialize,
+    attr(borsh(use_discriminant = true)),
+)]
+#[repr(u8)]
+pub enum Message<T> {
+    Ping = 0,
+    Data(Pair![T]) = 1,
+    Batch(Vec<Pair![T]>) = 7,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages() -> [Message<u16>; 3] {
+        [
+            Message::Ping,
+            Message::Data((1, 2)),
+            Message::Batch(vec![(3, 4), (5, 6)]),
+        ]
+    }
+
+    #[test]
+    fn test_borsh_round_trip() {
+        for message in messages() {
+            let bytes = borsh::to_vec(&message).unwrap();
+            assert_eq!(borsh::from_slice::<Message<u16>>(&bytes).unwrap(), message);
+        }
+        // `use_discriminant = true` writes the explicit discriminants
+        assert_eq!(borsh::to_vec(&Message::<u16>::Batch(vec![])).unwrap()[0], 7);
+    }
+
+    #[test]
+    fn test_bincode_round_trip() {
+        let config = bincode::config::standard();
+        for message in messages() {
+            let bytes = bincode::encode_to_vec(&message, config).unwrap();
+            let (decoded, len): (Message<u16>, _) =
+                bincode::decode_from_slice(&bytes, config).unwrap();
+            assert_eq!((decoded, len), (message, bytes.len()));
+        }
+    }
+
+    #[test]
+    fn test_postcard_round_trip() {
+        for message in messages() {
+            let bytes = postcard::to_allocvec(&message).unwrap();
+            assert_eq!(postcard::from_bytes::<Message<u16>>(&bytes).unwrap(), message);
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Id {
+    ($t:ty) => { (u64, PhantomData<$t>) };
+}
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+pub trait Unit {
+    const SYMBOL: &'static str;
+}
+
+pub struct Metre;
+
+impl Unit for Metre {
+    const SYMBOL: &'static str = "m";
+}
+
+/// `perfect_bounds` would bound `Pair![f64]`, which does not mention `U`; the explicit bound
+/// asks for `U: Unit` instead
+#[macro_derive(Debug, Clone, perfect_bounds = true, bound = "U: Unit")]
+pub struct Length<U> {
+    pub range: Pair![f64],
+    pub unit: Id![U],
+}
+
+/// serde would bound `T: Serialize` and `T: Deserialize<'de>` itself
+#[macro_derive(Serialize, Deserialize, bound = "")]
+pub struct Record<T> {
+    pub id: Id![T],
+    pub values: Pair![u32],
+}
+
+#[macro_derive(cfg(all(), Serialize), bound = "")]
+pub struct Optional<T> {
+    pub id: Id![T],
+}
+
+/// The rkyv derive named like serde's takes no `serde(bound)` helper
+#[macro_derive(
+    Clone,
+    rkyv::Archive,
+    rkyv::Serialize,
+    perfect_bounds = true,
+    bound = "",
+    attr(archive(check_bytes))
+)]
+pub struct Snapshot<T> {
+    pub id: Id![T],
+    pub values: Pair![u32],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct NotSerializable;
+
+    #[test]
+    fn test_bound_on_manual_impls() {
+        let length = Length::<Metre> {
+            range: (0.0, 1.5),
+            unit: (0, PhantomData),
+        };
+        assert_eq!(length.clone().range, (0.0, 1.5));
+        assert_eq!(Metre::SYMBOL, "m");
+    }
+
+    #[test]
+    fn test_bound_on_serde_derives() {
+        let record = Record::<NotSerializable> {
+            id: (3, PhantomData),
+            values: (1, 2),
+        };
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(json, r#"{"id":[3,null],"values":[1,2]}"#);
+        let parsed: Record<NotSerializable> = serde_json::from_str(&json).unwrap();
+        assert_eq!((parsed.id.0, parsed.values), (3, (1, 2)));
+
+        let optional = Optional::<NotSerializable> {
+            id: (4, PhantomData),
+        };
+        assert_eq!(serde_json::to_string(&optional).unwrap(), r#"{"id":[4,null]}"#);
+    }
+
+    #[test]
+    fn test_bound_beside_other_serialize_derive() {
+        let snapshot = Snapshot::<NotSerializable> {
+            id: (6, PhantomData),
+            values: (1, 2),
+        };
+        assert_eq!(snapshot.clone().values, (1, 2));
+        let bytes = rkyv::to_bytes::<_, 256>(&snapshot).unwrap();
+        let archived = rkyv::check_archived_root::<Snapshot<NotSerializable>>(&bytes).unwrap();
+        assert_eq!((archived.id.0, archived.values.1), (6, 2));
+    }
+}
@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+use type_macro_derive_tricks::{macro_derive, macro_derive_mod};
+
+macro_rules! Count {
+    ($t:ty) => { Option<$t> };
+}
+
+macro_rules! Paths {
+    () => { Vec<PathBuf> };
+}
+
+/// The setters outside the module take the aliases, which are public
+#[macro_derive_mod(Debug, derive_builder::Builder, vis = "pub")]
+mod config {
+    use std::path::PathBuf;
+
+    pub struct Config {
+        #[builder(default)]
+        pub retries: Count![u8],
+        #[builder(setter(each(name = "path", into)), default)]
+        pub paths: Paths![],
+        #[builder(setter(into))]
+        pub name: String,
+    }
+}
+
+/// `strip_option` looks for `Option<...>` as written, so that invocation is inlined
+#[macro_derive(Debug, typed_builder::TypedBuilder, inline(Count![u8] = "Option<u8>"))]
+pub struct Request {
+    #[builder(default, setter(strip_option))]
+    pub retries: Count![u8],
+    #[builder(default)]
+    pub paths: Paths![],
+    #[builder(setter(into))]
+    pub url: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_builder() {
+        let config = config::ConfigBuilder::default()
+            .path("a")
+            .path(PathBuf::from("b"))
+            .name("app")
+            .build()
+            .unwrap();
+        assert_eq!(config.retries, None);
+        assert_eq!(config.paths, [PathBuf::from("a"), PathBuf::from("b")]);
+        assert_eq!(config.name, "app");
+
+        let config = config::ConfigBuilder::default()
+            .retries(Some(3))
+            .name("app")
+            .build()
+            .unwrap();
+        assert_eq!(config.retries, Some(3));
+        assert!(config::ConfigBuilder::default().build().is_err());
+    }
+
+    #[test]
+    fn test_typed_builder() {
+        let request = Request::builder().retries(2).url("https://example.com").build();
+        assert_eq!(request.retries, Some(2));
+        assert!(request.paths.is_empty());
+        assert_eq!(request.url, "https://example.com");
+    }
+}
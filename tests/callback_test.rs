@@ -0,0 +1,73 @@
+use type_macro_derive_tricks::{define_derive_group, macro_derive};
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+/// Replaces `Unit![Name]` with a pair of the generic parameter of the struct, which a type
+/// macro cannot name by itself, and lists the names of the units
+macro_rules! with_units {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident<$param:ident> {
+            $($field_vis:vis $field:ident: Unit![$unit:ident]),* $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        $vis struct $name<$param> {
+            $($field_vis $field: Pair![$param]),*
+        }
+
+        impl<$param> $name<$param> {
+            pub fn units() -> &'static [&'static str] {
+                &[$(stringify!($unit)),*]
+            }
+        }
+    };
+}
+
+#[macro_derive(Debug, Clone, PartialEq, callback = with_units)]
+pub struct Measure<T> {
+    pub length: Unit![Metre],
+    pub mass: Unit![Gram],
+}
+
+define_derive_group!(measure = Debug, Clone);
+
+/// A crate re-exporting the attributes under another path
+mod facade {
+    pub use type_macro_derive_tricks::*;
+}
+
+// The group is expanded first, and the callback then rewrites the item
+#[facade::macro_derive(PartialEq, @measure, callback = with_units, crate = crate::facade)]
+pub struct Capacity<T> {
+    pub volume: Unit![Litre],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_callback() {
+        let measure = Measure {
+            length: (1, 2),
+            mass: (3, 4),
+        };
+        assert_eq!(measure.clone(), measure);
+        assert_eq!(
+            format!("{:?}", measure),
+            "Measure { length: (1, 2), mass: (3, 4) }"
+        );
+        assert_eq!(Measure::<u8>::units(), ["Metre", "Gram"]);
+    }
+
+    #[test]
+    fn test_callback_in_group() {
+        let capacity = Capacity { volume: (1, 2) };
+        assert_eq!(capacity.clone(), capacity);
+        assert_eq!(format!("{:?}", capacity), "Capacity { volume: (1, 2) }");
+        assert_eq!(Capacity::<u8>::units(), ["Litre"]);
+    }
+}
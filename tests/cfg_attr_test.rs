@@ -0,0 +1,45 @@
+use type_macro_derive_tricks::{macro_derive, macro_derive_mod};
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Clone, PartialEq, expose_types = true)]
+#[cfg_attr(test, macro_derive(Debug))]
+#[cfg_attr(any(), macro_derive(Default))]
+pub struct Line {
+    pub from: Pair![i32],
+    pub to: Pair![i32],
+}
+
+#[macro_derive_mod(Clone)]
+mod shapes {
+    #[cfg_attr(test, macro_derive(Debug), allow(dead_code))]
+    #[cfg_attr(any(), macro_derive(Default))]
+    pub struct Point {
+        pub at: Pair![i32],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conditional_derives() {
+        let line = Line {
+            from: (0, 0),
+            to: (1, 1),
+        };
+        assert_eq!(line.clone(), line);
+        assert_eq!(format!("{:?}", line), "Line { from: (0, 0), to: (1, 1) }");
+        let to: <Line as LineTypes>::to = (1, 1);
+        assert_eq!(line.to, to);
+    }
+
+    #[test]
+    fn test_conditional_derives_in_module() {
+        let point = shapes::Point { at: (2, 3) };
+        assert_eq!(format!("{:?}", point.clone()), "Point { at: (2, 3) }");
+    }
+}
@@ -0,0 +1,22 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug, cfg(test, Clone, PartialEq), cfg(not(test), Hash))]
+pub struct ConditionalDerive {
+    pub pair: Pair![u8],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cfg_derive_is_applied() {
+        let instance = ConditionalDerive { pair: (1, 2) };
+        assert_eq!(instance.clone(), instance);
+        assert!(format!("{:?}", instance).contains("ConditionalDerive"));
+    }
+}
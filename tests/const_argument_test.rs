@@ -0,0 +1,30 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Buffer<T, const N: usize>(pub [T; N]);
+
+#[macro_derive(Debug, Clone, PartialEq)]
+pub struct ConstArguments<T> {
+    pub mixed: Buffer<Pair![T], 2>,
+    pub sized: Buffer<u8, { core::mem::size_of::<Pair![u16]>() }>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_const_generic_arguments() {
+        let instance = ConstArguments {
+            mixed: Buffer([(1, 2), (3, 4)]),
+            sized: Buffer([0; 4]),
+        };
+        assert_eq!(instance.clone(), instance);
+        assert_eq!(instance.mixed.0[1], (3, 4));
+        assert_eq!(instance.sized.0.len(), 4);
+    }
+}
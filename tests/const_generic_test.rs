@@ -0,0 +1,73 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! ArrayMacro {
+    ($t:ty, $n:expr) => { [$t; $n] };
+}
+
+macro_rules! Matrix {
+    ($t:ty, $rows:expr, $cols:expr) => { [[$t; $cols]; $rows] };
+}
+
+macro_rules! Size {
+    () => { usize };
+}
+
+#[macro_derive(Debug, Clone, PartialEq)]
+pub struct ConstArray<T, const N: usize> {
+    pub values: ArrayMacro![T, N],
+    pub fixed: ArrayMacro![u8, 4],
+}
+
+#[macro_derive(Debug, Clone, Copy)]
+pub struct ConstDefault<const R: usize, const C: usize = 2> {
+    pub cells: Matrix![i32, R, C],
+}
+
+/// The type of a const parameter is aliased as well, so `strict` accepts it
+#[macro_derive(Debug, strict)]
+pub struct Buffer<const N: Size![]> {
+    pub data: [u8; N],
+}
+
+#[macro_derive(Debug)]
+pub enum ConstEnum<'a, T, const N: usize> {
+    Borrowed(ArrayMacro![&'a T, N]),
+    Owned(ArrayMacro![T, N]),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_const_param_in_macro() {
+        let instance = ConstArray {
+            values: ["a", "b", "c"],
+            fixed: [1, 2, 3, 4],
+        };
+        assert_eq!(instance.clone(), instance);
+        assert_eq!(instance.values.len(), 3);
+    }
+
+    #[test]
+    fn test_const_param_with_default() {
+        let instance: ConstDefault<3> = ConstDefault { cells: [[0; 2]; 3] };
+        let copied = instance;
+        assert_eq!(copied.cells.len(), 3);
+        assert_eq!(copied.cells[0].len(), 2);
+    }
+
+    #[test]
+    fn test_const_param_with_lifetime() {
+        let value = 1u8;
+        let borrowed = ConstEnum::<u8, 2>::Borrowed([&value, &value]);
+        assert!(format!("{:?}", borrowed).contains("Borrowed"));
+        assert!(format!("{:?}", ConstEnum::<u8, 1>::Owned([2])).contains("Owned"));
+    }
+
+    #[test]
+    fn test_macro_typed_const_param() {
+        let buffer = Buffer::<3> { data: [1, 2, 3] };
+        assert_eq!(format!("{:?}", buffer), "Buffer { data: [1, 2, 3] }");
+    }
+}
@@ -0,0 +1,44 @@
+use type_macro_derive_tricks::{macro_derive, type_macro_expand};
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+macro_rules! Boxed {
+    ($t:path) => { Box<$t> };
+}
+
+// `Boxed` only accepts paths, so `Pair![T]` has to be replaced by its alias first
+#[macro_derive(Debug, Clone, PartialEq, deep = true)]
+pub struct DeepFields<T> {
+    pub boxed: Boxed![Pair![T]],
+    pub nested: Option<Boxed![Vec<Pair![u8]>]>,
+}
+
+#[type_macro_expand(deep = true)]
+#[derive(Debug, Default)]
+pub struct DeepExpand {
+    pub boxed: Boxed![Pair![String]],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deep_fields() {
+        let instance = DeepFields {
+            boxed: Box::new((1, 2)),
+            nested: Some(Box::new(vec![(3, 4)])),
+        };
+        assert_eq!(instance.clone(), instance);
+        assert_eq!(*instance.boxed, (1, 2));
+        assert_eq!(instance.nested.unwrap()[0], (3, 4));
+    }
+
+    #[test]
+    fn test_deep_expand() {
+        let instance = DeepExpand::default();
+        assert_eq!(*instance.boxed, (String::new(), String::new()));
+    }
+}
@@ -0,0 +1,93 @@
+use type_macro_derive_tricks::{define_derive_group, macro_derive, macro_derive_mod};
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+define_derive_group!(std = Debug, Clone, PartialEq, Eq, Hash);
+
+define_derive_group! {
+    /// Standard derives of syntax tree nodes
+    pub(crate) node = @std, PartialOrd, Ord, qualify_derives = true
+}
+
+#[macro_derive(@std)]
+pub struct Span {
+    pub range: Pair![usize],
+}
+
+#[macro_derive(@node, Default, prefix = "__NodeAlias")]
+pub struct Ident {
+    pub span: Pair![usize],
+    pub name: String,
+}
+
+#[macro_derive_mod(@std)]
+mod tokens {
+    pub struct Punct {
+        pub span: Pair![usize],
+        pub ch: char,
+    }
+}
+
+mod nested {
+    use type_macro_derive_tricks::macro_derive;
+
+    #[macro_derive(@crate::node)]
+    pub enum Literal {
+        Int(Pair![i64]),
+        Str(String),
+    }
+}
+
+/// A crate re-exporting the attributes under another path
+mod facade {
+    pub use type_macro_derive_tricks::*;
+}
+
+#[facade::macro_derive(@std, crate = crate::facade)]
+pub struct Comment {
+    pub span: Pair![usize],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_group() {
+        let span = Span { range: (0, 3) };
+        assert_eq!(span.clone(), span);
+        assert_eq!(format!("{:?}", span), "Span { range: (0, 3) }");
+        let mut set = HashSet::new();
+        set.insert(span.clone());
+        assert!(set.contains(&span));
+    }
+
+    #[test]
+    fn test_nested_group_and_options() {
+        let ident = Ident {
+            span: (1, 2),
+            name: "x".to_string(),
+        };
+        assert!(ident > Ident::default());
+        assert_eq!(ident.clone().span, (1, 2));
+    }
+
+    #[test]
+    fn test_module_and_path() {
+        let punct = tokens::Punct {
+            span: (4, 5),
+            ch: ';',
+        };
+        assert_eq!(punct.clone(), punct);
+        assert!(nested::Literal::Int((1, 2)) < nested::Literal::Str(String::new()));
+    }
+
+    #[test]
+    fn test_crate_path() {
+        let comment = Comment { span: (6, 9) };
+        assert_eq!(comment.clone(), comment);
+    }
+}
@@ -0,0 +1,44 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug, Clone,)]
+pub struct TrailingComma {
+    pub value: Pair![u8],
+}
+
+#[macro_derive()]
+pub struct EmptyList {
+    pub value: Pair![u8],
+}
+
+#[macro_derive(Debug, Clone, Debug, derive(Clone, PartialEq))]
+pub enum Duplicated {
+    Value(Pair![i32]),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trailing_comma() {
+        let instance = TrailingComma { value: (1, 2) };
+        assert_eq!(instance.clone().value, (1, 2));
+    }
+
+    #[test]
+    fn test_empty_list() {
+        let instance = EmptyList { value: (3, 4) };
+        assert_eq!(instance.value, (3, 4));
+    }
+
+    #[test]
+    fn test_duplicates_are_merged() {
+        let value = Duplicated::Value((5, 6));
+        assert_eq!(value.clone(), value);
+        assert!(format!("{:?}", value).contains("Value"));
+    }
+}
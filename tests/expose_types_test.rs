@@ -0,0 +1,67 @@
+use type_macro_derive_tricks::{macro_derive, type_macro_expand};
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+macro_rules! Token {
+    [fn] => { FnToken };
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FnToken;
+
+#[macro_derive(Debug, Clone, PartialEq, expose_types = true)]
+pub struct Signature<S: Clone> {
+    pub fn_token: Token![fn],
+    pub span: Pair![S],
+    pub name: String,
+}
+
+#[macro_derive(Debug, expose_types = true)]
+pub enum Shape<T> {
+    Point(Pair![T]),
+    Line { from: Pair![T], to: Pair![T] },
+    Empty,
+}
+
+#[type_macro_expand(expose_types = true)]
+pub struct Wrapper<'a>(pub &'a Pair![u8], pub u8);
+
+/// Names the resolved types in a signature, as downstream code would
+fn span_of<S: Clone>(signature: &Signature<S>) -> &<Signature<S> as SignatureTypes>::span {
+    &signature.span
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_struct_types() {
+        let signature = Signature {
+            fn_token: FnToken,
+            span: (1u32, 4u32),
+            name: "f".to_string(),
+        };
+        let token: <Signature<u32> as SignatureTypes>::fn_token = FnToken;
+        assert_eq!(signature.fn_token, token);
+        assert_eq!(span_of(&signature), &(1, 4));
+    }
+
+    #[test]
+    fn test_enum_types() {
+        let point: <Shape<i8> as ShapeTypes>::Point_0 = (1, 2);
+        let to: <Shape<i8> as ShapeTypes>::Line_to = (3, 4);
+        let shape = Shape::Line { from: point, to };
+        assert!(format!("{:?}", shape).contains("Line"));
+        assert!(format!("{:?}", Shape::<i8>::Empty).contains("Empty"));
+    }
+
+    #[test]
+    fn test_tuple_struct_types() {
+        let pair: <Wrapper<'static> as WrapperTypes>::_0 = &(1, 2);
+        let wrapper = Wrapper(pair, 3);
+        assert_eq!(wrapper.0 .1 + wrapper.1, 5);
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug, Clone)]
+pub struct LongTypes<'a, K, V> {
+    #[macro_alias]
+    pub index: HashMap<K, Vec<Result<Option<&'a V>, String>>>,
+    #[macro_alias]
+    pub plain: Vec<Option<Box<u32>>>,
+    // Macros inside an aliased type expand within the alias
+    #[macro_alias]
+    pub nested: Option<Pair![K]>,
+    pub pair: Pair![V],
+}
+
+#[macro_derive(Debug)]
+pub enum AliasedVariant<T> {
+    Long(#[macro_alias] Result<Vec<Option<T>>, Box<dyn std::error::Error>>),
+    Short(T),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aliased_field_types() {
+        let value = 10u8;
+        let mut index = HashMap::new();
+        index.insert("key", vec![Ok(Some(&value))]);
+        let instance = LongTypes {
+            index,
+            plain: vec![Some(Box::new(1))],
+            nested: Some(("a", "b")),
+            pair: (1u8, 2u8),
+        };
+        let cloned = instance.clone();
+        assert_eq!(cloned.index["key"][0], Ok(Some(&10)));
+        assert_eq!(cloned.nested, Some(("a", "b")));
+        assert!(format!("{:?}", instance).contains("LongTypes"));
+    }
+
+    #[test]
+    fn test_aliased_variant_field() {
+        let value = AliasedVariant::Long(Ok(vec![Some(1)]));
+        assert!(format!("{:?}", value).contains("Long"));
+        assert!(format!("{:?}", AliasedVariant::Short(2)).contains("Short"));
+    }
+}
@@ -0,0 +1,65 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+/// Only defined where the gated fields exist
+#[cfg(any())]
+macro_rules! Gated {
+    ($t:ty) => { Vec<$t> };
+}
+
+#[macro_derive(Debug, Clone, PartialEq, expose_types = true)]
+pub struct Config {
+    pub size: Pair![u32],
+    #[cfg(any())]
+    pub extra: Gated![u8],
+    #[cfg(any())]
+    #[cfg(not(test))]
+    pub more: Gated![u16],
+    #[cfg(test)]
+    pub enabled: Pair![bool],
+}
+
+#[macro_derive(Debug, expose_types = true)]
+pub enum Event {
+    Resized(Pair![u32]),
+    #[cfg(any())]
+    Extended(Gated![u32], Pair![u32]),
+    #[cfg(any())]
+    Detailed {
+        #[cfg(not(test))]
+        detail: Gated![u64],
+    },
+    Flagged {
+        #[cfg(any())]
+        extra: Gated![u8],
+        #[cfg(test)]
+        flags: Pair![bool],
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gated_fields() {
+        let config = Config {
+            size: (1, 2),
+            enabled: (true, false),
+        };
+        assert_eq!(config.clone(), config);
+        let size: <Config as ConfigTypes>::size = (3, 4);
+        assert_eq!(size, (3, 4));
+
+        let event = Event::Flagged {
+            flags: (true, true),
+        };
+        assert!(format!("{:?}", event).contains("flags"));
+        assert!(format!("{:?}", Event::Resized((1, 1))).contains("Resized"));
+        let resized: <Event as EventTypes>::Resized_0 = (5, 6);
+        assert_eq!(resized, (5, 6));
+    }
+}
@@ -0,0 +1,50 @@
+#![allow(non_camel_case_types)]
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Same {
+    ($t:ty) => { $t };
+}
+
+mod other {
+    pub type T = u8;
+    pub type vec = u16;
+}
+
+// `other::T` does not refer to the parameter `T`, so the alias must not declare it
+#[macro_derive(Debug, Clone)]
+pub struct QualifiedName<T> {
+    pub qualified: Same![other::T],
+    pub param: Same![T],
+}
+
+// Neither the argument name nor the trailing path segment refers to the parameter `vec`
+#[macro_derive(Debug)]
+pub struct LowercaseParam<vec> {
+    pub callback: Same![fn(vec: u8) -> u8],
+    pub qualified: Same![other::vec],
+    pub value: vec,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_qualified_name_is_not_a_param() {
+        let instance = QualifiedName {
+            qualified: 1u8,
+            param: "param",
+        };
+        assert_eq!(instance.clone().qualified, 1);
+    }
+
+    #[test]
+    fn test_lowercase_param() {
+        let instance = LowercaseParam {
+            callback: |x| x + 1,
+            qualified: 2,
+            value: (),
+        };
+        assert!(format!("{:?}", instance).contains("LowercaseParam"));
+    }
+}
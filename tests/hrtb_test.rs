@@ -0,0 +1,35 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Same {
+    ($t:ty) => { $t };
+}
+
+#[macro_derive(Clone, Copy)]
+pub struct HigherRanked<'x, T: 'static> {
+    pub callback: Same![for<'x> fn(&'x T) -> bool],
+    pub boxed: &'x Same![dyn for<'y> Fn(&'y T) -> &'y T],
+}
+
+fn is_positive(value: &i32) -> bool {
+    *value > 0
+}
+
+fn identity(value: &i32) -> &i32 {
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_higher_ranked_fields() {
+        let instance = HigherRanked {
+            callback: is_positive,
+            boxed: &identity,
+        };
+        let copied = instance;
+        assert!((copied.callback)(&1));
+        assert_eq!(*(copied.boxed)(&2), 2);
+    }
+}
@@ -0,0 +1,101 @@
+// Invocations expanded through the group of `define_type_macro!` count as uses
+#![deny(unused_macros)]
+
+use serde::Deserialize;
+use type_macro_derive_tricks::{define_derive_group, define_type_macro, macro_derive, macro_derive_mod};
+
+macro_rules! Unaliased {
+    ($t:ty) => { Box<$t> };
+}
+
+/// serde only borrows fields whose type is written as `&str`, which an alias would hide
+#[macro_derive(
+    Debug,
+    Deserialize,
+    PartialEq,
+    inline_rules(
+        macro_rules! Text {
+            ($l:lifetime) => { &$l str };
+        }
+        macro_rules! Maybe {
+            ($t:ty) => { Option<$t> };
+        }
+        macro_rules! Pairs {
+            ($($t:ty),+ $(,)?) => { ($(($t, $t)),+) };
+        }
+        macro_rules! Wrapped {
+            ($t:ty) => { Vec<Maybe![$t]> };
+        }
+        macro_rules! Rows {
+            ($($($t:ty),+);*) => { ($($(Vec<$t>,)+)*) };
+        }
+    ),
+)]
+pub struct Settings<'a> {
+    pub name: Text!['a],
+    pub timeout: Maybe![u32],
+    pub ranges: Pairs![u8, i16,],
+    pub values: Wrapped![bool],
+    pub boxed: Maybe![Unaliased![u8]],
+    pub rows: Rows![u8, u16; i8],
+}
+
+// Groups share the rules between items
+define_derive_group!(text = inline_rules(macro_rules! Text { ($l:lifetime) => { &$l str }; }));
+
+#[macro_derive_mod(Debug, serde::Deserialize, @text)]
+mod records {
+    pub struct Entry<'a> {
+        pub key: Text!['a],
+    }
+}
+
+define_type_macro! {
+    /// A `$t` borrowed for `$l`
+    macro_rules! Borrowed {
+        ($l:lifetime $t:ty) => { &$l $t };
+    }
+
+    macro_rules! Listed {
+        ($t:ty) => { Vec<$t> };
+    }
+}
+
+#[macro_derive(Debug, Deserialize, @Borrowed, @Listed)]
+pub struct Record<'a> {
+    pub value: Borrowed!['a str],
+    pub tags: Listed![String],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expanded_in_place() {
+        let json = r#"{"name":"a","ranges":[[1,2],[-3,4]],"values":[true,null],"boxed":5,"rows":[[1],[],[-2]]}"#;
+        let settings: Settings = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            settings,
+            Settings {
+                name: "a",
+                timeout: None,
+                ranges: ((1, 2), (-3, 4)),
+                values: vec![Some(true), None],
+                boxed: Some(Box::new(5)),
+                rows: (vec![1], vec![], vec![-2]),
+            }
+        );
+        let entry: records::Entry = serde_json::from_str(r#"{"key":"k"}"#).unwrap();
+        assert_eq!(entry.key, "k");
+    }
+
+    #[test]
+    fn test_defined_macro() {
+        let record: Record = serde_json::from_str(r#"{"value":"v","tags":["t"]}"#).unwrap();
+        // The definition is emitted, so the macro can be used elsewhere as well
+        let value: Borrowed!['static str] = "v";
+        assert_eq!(record.value, value);
+        assert_eq!(record.tags, ["t"]);
+    }
+}
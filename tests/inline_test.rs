@@ -0,0 +1,55 @@
+use serde::Deserialize;
+use std::borrow::Cow;
+use type_macro_derive_tricks::{macro_derive, type_macro_expand};
+
+// Inlined invocations are never expanded
+#[allow(unused_macros)]
+macro_rules! Text {
+    ($l:lifetime) => { Cow<$l, str> };
+}
+
+#[allow(unused_macros)]
+macro_rules! Strings {
+    () => { Vec<String> };
+}
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+/// serde borrows `Cow<str>` fields only when it sees the type, so the invocation is inlined
+#[macro_derive(Deserialize, inline(Text!['a] = "Cow<'a, str>"))]
+pub struct Message<'a> {
+    #[serde(borrow)]
+    pub body: Text!['a],
+    pub range: Pair![u8],
+}
+
+#[type_macro_expand(inline(Strings!() = Vec<String>))]
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    pub files: Strings![],
+    pub lines: Pair![u32],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inlined_type_is_seen_by_derive() {
+        let message: Message = serde_json::from_str(r#"{"body":"hi","range":[1,2]}"#).unwrap();
+        assert!(matches!(message.body, Cow::Borrowed("hi")));
+        assert_eq!(message.range, (1, 2));
+    }
+
+    #[test]
+    fn test_inlined_bare_type() {
+        let args = Args {
+            files: vec!["a.rs".to_string()],
+            lines: (1, 2),
+        };
+        assert_eq!(args.files.len(), 1);
+        assert_eq!(Args::default().files, Vec::<String>::new());
+    }
+}
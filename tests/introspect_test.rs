@@ -0,0 +1,35 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug, introspect = true)]
+pub struct TextSpan<T> {
+    pub range: Pair![T],
+    pub lines: Vec<Pair![u32]>,
+    pub source: String,
+}
+
+#[macro_derive(Clone, introspect = true)]
+pub struct Unit(pub Pair![u8]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_macro_types() {
+        assert_eq!(
+            TEXT_SPAN_MACRO_TYPES,
+            [("range", "Pair![T]"), ("lines", "Vec<Pair![u32]>")]
+        );
+        assert_eq!(UNIT_MACRO_TYPES, [("0", "Pair![u8]")]);
+        let span = TextSpan {
+            range: (0u8, 1),
+            lines: vec![],
+            source: String::new(),
+        };
+        assert!(format!("{:?}", span).starts_with("TextSpan"));
+    }
+}
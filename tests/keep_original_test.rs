@@ -0,0 +1,51 @@
+use std::mem::size_of;
+use type_macro_derive_tricks::{macro_derive, macro_derive_mod};
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug, Clone, repr(C), keep_original = "RawPoint")]
+pub struct Point<T> {
+    pub coords: Pair![T],
+    pub weight: f32,
+}
+
+#[macro_derive(Debug, keep_original = RawShape)]
+pub enum Shape {
+    Dot(Pair![i32]),
+    Empty,
+}
+
+#[macro_derive_mod(Debug)]
+mod nested {
+    #[macro_derive(keep_original = "RawLine")]
+    pub struct Line {
+        pub ends: Pair![u8],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_original_copy() {
+        let raw = RawPoint {
+            coords: (1u16, 2),
+            weight: 0.5,
+        };
+        let point = Point {
+            coords: raw.coords,
+            weight: raw.weight,
+        };
+        assert_eq!(point.clone().coords, (1, 2));
+        assert_eq!(size_of::<RawPoint<u16>>(), size_of::<Point<u16>>());
+
+        let shapes = [RawShape::Dot((3, 4)), RawShape::Empty];
+        assert!(matches!(shapes[0], RawShape::Dot((3, 4))));
+        assert_eq!(format!("{:?}", Shape::Dot((3, 4))), "Dot((3, 4))");
+        assert_eq!(nested::RawLine { ends: (5, 6) }.ends, (5, 6));
+        assert!(format!("{:?}", nested::Line { ends: (5, 6) }).starts_with("Line"));
+    }
+}
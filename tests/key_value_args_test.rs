@@ -0,0 +1,54 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(derive(Debug, Clone, PartialEq), prefix = "__PairAlias")]
+pub struct ExplicitList<T> {
+    pub first: Pair![T],
+    pub second: Pair![u8],
+}
+
+// Bare traits and the explicit list can be mixed
+#[macro_derive(Debug, derive(Clone), vis = "pub(crate)")]
+pub(crate) enum MixedList {
+    Ints(Pair![i32]),
+    Empty,
+}
+
+// Trailing comma and empty derive list
+#[macro_derive(derive(), prefix = "__Unused",)]
+pub struct NoDerives {
+    pub value: Pair![bool],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explicit_derive_list() {
+        let instance = ExplicitList {
+            first: ("a", "b"),
+            second: (1, 2),
+        };
+        assert_eq!(instance.clone(), instance);
+        assert!(format!("{:?}", instance).contains("ExplicitList"));
+    }
+
+    #[test]
+    fn test_mixed_derive_list() {
+        let value = MixedList::Ints((1, 2)).clone();
+        assert!(format!("{:?}", value).contains("Ints"));
+        assert!(format!("{:?}", MixedList::Empty).contains("Empty"));
+    }
+
+    #[test]
+    fn test_no_derives() {
+        let instance = NoDerives {
+            value: (true, false),
+        };
+        assert_eq!(instance.value, (true, false));
+    }
+}
@@ -0,0 +1,30 @@
+#![deny(unused)]
+
+use type_macro_derive_tricks::{macro_derive, macro_derive_mod};
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+macro_rules! len {
+    () => { 4 };
+}
+
+// Private items the crate never uses, which are reported themselves but not their aliases
+#[allow(dead_code)]
+#[macro_derive(Debug, expose_types = true)]
+struct Unused {
+    pair: Pair![u8],
+    buffer: [u8; len!()],
+}
+
+#[macro_derive_mod(Clone)]
+mod shapes {
+    #[allow(dead_code)]
+    pub(super) struct Point {
+        at: Pair![i32],
+    }
+}
+
+#[test]
+fn test_generated_items_are_not_reported() {}
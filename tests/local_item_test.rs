@@ -0,0 +1,79 @@
+use type_macro_derive_tricks::{macro_derive, type_macro_expand};
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[test]
+fn test_local_struct() {
+    #[macro_derive(Debug, Clone, PartialEq)]
+    struct Local<T> {
+        pair: Pair![T],
+    }
+
+    let local = Local { pair: (1, 2) };
+    assert_eq!(local.clone(), local);
+}
+
+#[test]
+fn test_local_items_referring_to_each_other() {
+    macro_rules! Wrapped {
+        ($t:ty) => { Option<$t> };
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Default)]
+    struct Unit;
+
+    #[macro_derive(Debug, Clone, PartialEq, Default)]
+    pub struct Holder {
+        unit: Wrapped![Unit],
+        pair: Pair![Unit],
+    }
+
+    #[type_macro_expand]
+    #[derive(Debug)]
+    enum Choice {
+        Holder(Wrapped![Holder]),
+    }
+
+    let holder = Holder::default();
+    assert_eq!(holder.unit, None);
+    assert_eq!(holder.pair, (Unit, Unit));
+    assert!(format!("{:?}", Choice::Holder(Some(holder))).starts_with("Holder"));
+}
+
+#[test]
+fn test_local_structs_with_same_names() {
+    // Identical items in sibling scopes get identical stable names without clashing
+    let first = {
+        #[macro_derive(Debug, PartialEq, stable_names = true)]
+        struct Local {
+            pair: Pair![u8],
+        }
+        format!("{:?}", Local { pair: (1, 2) })
+    };
+    let second = {
+        #[macro_derive(Debug, PartialEq, stable_names = true)]
+        struct Local {
+            pair: Pair![u8],
+        }
+        format!("{:?}", Local { pair: (1, 2) })
+    };
+    assert_eq!(first, second);
+}
+
+#[test]
+fn test_local_struct_in_generic_function() {
+    fn build<T: Default>() -> T {
+        #[macro_derive(Debug, Default)]
+        struct Scratch {
+            buffer: Pair![u32],
+        }
+
+        let scratch = Scratch::default();
+        assert_eq!(scratch.buffer, (0, 0));
+        T::default()
+    }
+
+    assert_eq!(build::<u8>(), 0);
+}
@@ -0,0 +1,62 @@
+use type_macro_derive_tricks::macro_derive_mod;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive_mod(Debug, Clone)]
+pub mod shapes {
+    #[macro_derive(PartialEq)]
+    pub struct Line {
+        pub from: Pair![i32],
+        pub to: Pair![i32],
+    }
+
+    pub struct Labeled<T> {
+        pub label: T,
+        pub at: Pair![i32],
+        pub both: Pair![T],
+    }
+
+    pub enum Shape<T> {
+        Point(Pair![i32]),
+        Segment(Pair![Pair![T]]),
+    }
+
+    // Parameters are declared in another order, so the alias cannot be shared
+    pub struct Swapped<B, A> {
+        pub first: Pair![(A, B)],
+    }
+
+    pub struct Ordered<A, B> {
+        pub first: Pair![(A, B)],
+    }
+
+    pub fn origin() -> Line {
+        Line {
+            from: (0, 0),
+            to: (0, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::shapes::*;
+
+    #[test]
+    fn test_module_items() {
+        let line = origin();
+        assert_eq!(line.clone(), line);
+        let labeled = Labeled {
+            label: "a",
+            at: (1, 2),
+            both: ("b", "c"),
+        };
+        assert_eq!(labeled.clone().both, ("b", "c"));
+        assert!(format!("{:?}", Shape::<u8>::Point((1, 2))).contains("Point"));
+        let swapped = Swapped::<u8, &str> { first: (("a", 1), ("b", 2)) };
+        let ordered = Ordered::<&str, u8> { first: swapped.first };
+        assert_eq!(ordered.clone().first.1, ("b", 2));
+    }
+}
@@ -0,0 +1,50 @@
+use type_macro_derive_tricks::macro_fn;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_fn]
+pub fn swap<T>(pair: Pair![T]) -> Pair![T] {
+    let (a, b): Pair![T] = pair;
+    (b, a)
+}
+
+#[macro_fn]
+pub fn pairs<T: Copy>(items: &[T]) -> impl Iterator<Item = Pair![T]> + '_ {
+    items.iter().map(|&item| (item, item))
+}
+
+#[macro_fn]
+pub fn firsts<T, I>(pairs: I) -> Vec<T>
+where
+    I: IntoIterator<Item = Pair![T]>,
+{
+    pairs.into_iter().map(|(first, _)| first).collect()
+}
+
+#[macro_fn]
+pub fn sum_all(pairs: impl IntoIterator<Item = Pair![u32]>) -> u32 {
+    pairs.into_iter().map(|(a, b)| a + b).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_argument_and_return_types() {
+        assert_eq!(swap((1, 2)), (2, 1));
+    }
+
+    #[test]
+    fn test_impl_trait() {
+        assert_eq!(pairs(&[1, 2]).collect::<Vec<_>>(), vec![(1, 1), (2, 2)]);
+        assert_eq!(sum_all(vec![(1, 2), (3, 4)]), 10);
+    }
+
+    #[test]
+    fn test_where_clause() {
+        assert_eq!(firsts(vec![("a", "b"), ("c", "d")]), vec!["a", "c"]);
+    }
+}
@@ -0,0 +1,72 @@
+use type_macro_derive_tricks::macro_impl;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+pub struct Points<T> {
+    pub items: Vec<(T, T)>,
+}
+
+#[macro_impl]
+impl<T: Clone> Points<T> {
+    pub const ORIGIN: Pair![i32] = (0, 0);
+
+    pub fn first(&self) -> Option<Pair![T]> {
+        self.items.first().cloned()
+    }
+
+    pub fn push(&mut self, point: Pair![T]) {
+        self.items.push(point);
+    }
+
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Vec<Pair![U]> {
+        self.items.iter().map(|(a, b)| (f(a), f(b))).collect()
+    }
+}
+
+pub trait Source {
+    type Item;
+    const WIDTH: usize;
+
+    fn get(&self) -> Self::Item;
+}
+
+#[macro_impl]
+impl<T: Copy> Source for Points<T>
+where
+    Pair![T]: Default,
+{
+    type Item = Pair![T];
+    const WIDTH: usize = 2;
+
+    fn get(&self) -> Pair![T] {
+        self.items.last().copied().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inherent_impl() {
+        let mut points = Points { items: Vec::new() };
+        assert_eq!(points.first(), None);
+        points.push((1, 2));
+        assert_eq!(points.first(), Some((1, 2)));
+        assert_eq!(points.map(|x| x * 10), vec![(10, 20)]);
+        assert_eq!(Points::<u8>::ORIGIN, (0, 0));
+    }
+
+    #[test]
+    fn test_trait_impl() {
+        let points = Points {
+            items: vec![(1u8, 2u8), (3, 4)],
+        };
+        let item: <Points<u8> as Source>::Item = points.get();
+        assert_eq!(item, (3, 4));
+        assert_eq!(<Points<u8> as Source>::WIDTH, 2);
+        assert_eq!(Points::<u8> { items: Vec::new() }.get(), (0, 0));
+    }
+}
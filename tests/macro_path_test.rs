@@ -0,0 +1,63 @@
+use type_macro_derive_tricks::macro_derive;
+
+#[macro_export]
+macro_rules! ExportedPair {
+    ($t:ty) => { ($t, $t) };
+}
+
+// Items generated by a macro refer to the exported macro through `$crate`
+macro_rules! define_point {
+    ($name:ident, $t:ty) => {
+        #[macro_derive(Debug, Clone, PartialEq)]
+        pub struct $name {
+            pub coords: $crate::ExportedPair![$t],
+        }
+    };
+}
+
+define_point!(CratePoint, i32);
+
+pub mod macros {
+    macro_rules! Triple {
+        ($t:ty) => { ($t, $t, $t) };
+    }
+    pub(crate) use Triple;
+}
+
+// Re-exported under another name
+pub(crate) use macros::Triple as Triplet;
+
+pub mod shapes {
+    use super::*;
+
+    #[macro_derive(Debug, Clone, PartialEq)]
+    pub struct Shape<T> {
+        pub local: self::super::macros::Triple![T],
+        pub parent: super::macros::Triple![u8],
+        pub absolute: crate::macros::Triple![i64],
+        pub renamed: Triplet![bool],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crate_path() {
+        let point = CratePoint { coords: (1, 2) };
+        assert_eq!(point.clone(), point);
+    }
+
+    #[test]
+    fn test_relative_paths() {
+        let shape = shapes::Shape {
+            local: ('a', 'b', 'c'),
+            parent: (1, 2, 3),
+            absolute: (4, 5, 6),
+            renamed: (true, false, true),
+        };
+        assert_eq!(shape.clone(), shape);
+        assert_eq!(shape.parent.2, 3);
+    }
+}
@@ -0,0 +1,61 @@
+use type_macro_derive_tricks::macro_trait;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_trait]
+pub trait Grammar<T>: Into<Pair![u8]> {
+    type Token: From<Pair![T]>;
+    const EMPTY: Pair![u8];
+
+    fn tokens(&self) -> Vec<Pair![T]>;
+
+    fn first<U: From<T>>(&self, fallback: Pair![U]) -> Pair![U]
+    where
+        T: Clone,
+    {
+        let tokens: Vec<Pair![T]> = self.tokens();
+        match tokens.first() {
+            Some((a, b)) => (a.clone().into(), b.clone().into()),
+            None => fallback,
+        }
+    }
+
+    fn same(&self, other: Pair![Self::Token]) -> bool;
+}
+
+pub struct Digits;
+
+impl From<Digits> for (u8, u8) {
+    fn from(_: Digits) -> Self {
+        (0, 9)
+    }
+}
+
+impl Grammar<u8> for Digits {
+    type Token = (u8, u8);
+    const EMPTY: (u8, u8) = (0, 0);
+
+    fn tokens(&self) -> Vec<(u8, u8)> {
+        vec![(1, 2)]
+    }
+
+    fn same(&self, (a, b): ((u8, u8), (u8, u8))) -> bool {
+        a == b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trait_items() {
+        assert_eq!(Digits.tokens(), vec![(1, 2)]);
+        assert_eq!(Digits.first::<u32>((7, 7)), (1, 2));
+        assert_eq!(<Digits as Grammar<u8>>::EMPTY, (0, 0));
+        assert!(Digits.same(((1, 2), (1, 2))));
+        assert_eq!(<(u8, u8)>::from(Digits), (0, 9));
+    }
+}
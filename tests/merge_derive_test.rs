@@ -0,0 +1,38 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug)]
+#[derive(Clone, PartialEq)]
+pub struct ExistingDerive<T> {
+    pub pair: Pair![T],
+}
+
+// Traits listed in both places are only derived once
+#[macro_derive(Debug, Clone)]
+#[derive(Clone)]
+#[derive(PartialEq, Eq)]
+pub enum OverlappingDerive {
+    Value(Pair![u16]),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_existing_derive_is_merged() {
+        let instance = ExistingDerive { pair: (1, 2) };
+        assert_eq!(instance.clone(), instance);
+        assert!(format!("{:?}", instance).contains("ExistingDerive"));
+    }
+
+    #[test]
+    fn test_overlapping_derive_is_deduplicated() {
+        let value = OverlappingDerive::Value((3, 4));
+        assert_eq!(value.clone(), value);
+        assert!(format!("{:?}", value).contains("Value"));
+    }
+}
@@ -0,0 +1,9 @@
+//! Warnings are only emitted on nightly, so these cases run with
+//! `cargo +nightly test --features nightly-diagnostics`
+#![cfg(feature = "nightly-diagnostics")]
+
+#[test]
+fn warnings() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui-nightly/*.rs");
+}
@@ -0,0 +1,38 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug, Clone, PartialEq)]
+pub struct DefaultMacro<T = Pair![i32]> {
+    pub value: T,
+}
+
+// Defaults may refer to earlier parameters
+#[macro_derive(Debug, Clone)]
+pub struct DependentDefault<T, U = Vec<Pair![T]>> {
+    pub first: T,
+    pub rest: U,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_macro() {
+        let instance: DefaultMacro = DefaultMacro { value: (1, 2) };
+        assert_eq!(instance.clone(), instance);
+        assert_eq!(instance.value, (1i32, 2i32));
+    }
+
+    #[test]
+    fn test_dependent_default() {
+        let instance: DependentDefault<u8> = DependentDefault {
+            first: 1,
+            rest: vec![(2, 3)],
+        };
+        assert_eq!(instance.clone().rest, vec![(2u8, 3u8)]);
+    }
+}
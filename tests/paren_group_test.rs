@@ -0,0 +1,36 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+// The `$field` fragment reaches `macro_derive` wrapped in an invisible group
+macro_rules! define_wrapper {
+    ($name:ident, $field:ty) => {
+        #[macro_derive(Debug, Clone, PartialEq)]
+        pub struct $name<'a, T> {
+            pub value: Option<$field>,
+            #[allow(unused_parens)]
+            pub borrowed: &'a (Pair![T]),
+        }
+    };
+}
+
+define_wrapper!(Wrapper, Pair![T]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grouped_and_parenthesized_types() {
+        let pair = (3, 4);
+        let wrapper = Wrapper {
+            value: Some((1, 2)),
+            borrowed: &pair,
+        };
+        assert_eq!(wrapper.clone(), wrapper);
+        assert_eq!(wrapper.value, Some((1, 2)));
+        assert_eq!(*wrapper.borrowed, (3, 4));
+    }
+}
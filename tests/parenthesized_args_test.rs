@@ -0,0 +1,30 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Clone)]
+pub struct ClosureBound<F, T>
+where
+    F: Fn(Pair![T]) -> Pair![u64] + Clone,
+    T: Clone,
+{
+    pub callback: F,
+    pub input: Pair![T],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parenthesized_bound() {
+        let instance = ClosureBound {
+            callback: |(a, b): (u32, u32)| (a as u64, b as u64),
+            input: (1u32, 2u32),
+        };
+        let cloned = instance.clone();
+        assert_eq!((cloned.callback)(cloned.input), (1, 2));
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashSet;
+use std::marker::PhantomData;
+use type_macro_derive_tricks::{macro_derive, macro_derive_mod};
+
+/// Identifier of a `$t`, which holds no `$t` itself
+macro_rules! Id {
+    ($t:ty) => { (u64, PhantomData<$t>) };
+}
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+/// Neither `Clone` nor `Debug`, which `#[derive]` would require of `T`
+pub struct Opaque;
+
+#[macro_derive(Clone, Debug, PartialEq, Eq, Hash, Default, perfect_bounds = true)]
+pub struct Handle<T> {
+    pub id: Id![T],
+    pub name: &'static str,
+}
+
+#[macro_derive(Clone, Copy, Debug, PartialEq, Hash, perfect_bounds = true)]
+pub enum Event<T> {
+    Moved { from: Pair![T], to: Pair![T] },
+    Clicked(Pair![T]),
+    Closed,
+}
+
+#[macro_derive(Clone, std::fmt::Debug, perfect_bounds = true)]
+pub struct Labelled<T>(pub Id![T], pub String);
+
+#[macro_derive_mod(Clone, Debug, perfect_bounds = true)]
+mod shapes {
+    use std::marker::PhantomData;
+
+    pub struct Tagged<T> {
+        pub tag: Id![T],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounds_on_field_types() {
+        let handle = Handle::<Opaque> {
+            id: (7, PhantomData),
+            name: "seven",
+        };
+        let cloned = handle.clone();
+        assert!(handle == cloned);
+        assert!(format!("{:?}", cloned).starts_with("Handle { id: (7, PhantomData"));
+        assert!(format!("{:?}", cloned).ends_with("name: \"seven\" }"));
+        assert_eq!(Handle::<Opaque>::default().id.0, 0);
+
+        let mut set = HashSet::new();
+        set.insert(handle);
+        assert!(set.contains(&cloned));
+    }
+
+    #[test]
+    fn test_enum_impls() {
+        let moved = Event::Moved {
+            from: (0, 0),
+            to: (1, 2),
+        };
+        let copied = moved;
+        assert_eq!(moved, copied);
+        assert_ne!(moved, Event::Clicked((0, 0)));
+        assert_ne!(Event::Clicked((0, 1)), Event::Clicked((0, 0)));
+        assert_eq!(Event::<u8>::Closed, Event::Closed);
+        assert_eq!(
+            format!("{:?}", moved),
+            "Moved { from: (0, 0), to: (1, 2) }"
+        );
+        assert_eq!(format!("{:?}", Event::Clicked((3, 4))), "Clicked((3, 4))");
+        assert_eq!(format!("{:?}", Event::<u8>::Closed), "Closed");
+    }
+
+    #[test]
+    fn test_tuple_struct_and_module() {
+        let labelled = Labelled::<Opaque>((1, PhantomData), "one".to_string());
+        assert!(format!("{:?}", labelled.clone()).starts_with("Labelled((1, PhantomData"));
+
+        let tagged = shapes::Tagged::<Opaque> {
+            tag: (2, PhantomData),
+        };
+        assert_eq!(tagged.clone().tag.0, 2);
+    }
+}
@@ -0,0 +1,51 @@
+use std::marker::PhantomData;
+use type_macro_derive_tricks::macro_derive;
+
+/// Names the argument of a callback, which is not a type
+macro_rules! Callback {
+    (fn($arg:ident: $t:ty)) => { fn($t) };
+}
+
+fn ignore(_: u8) {}
+
+#[macro_derive(Clone, phantom = true)]
+pub struct Handler<T> {
+    pub on_event: Callback![fn(T: u8)],
+}
+
+#[macro_derive(Clone, phantom = true)]
+pub struct Listener<'a, T>(pub Callback![fn(T: &'a u8)]);
+
+/// Parameters still used by a field are left alone
+#[macro_derive(Clone, phantom = true)]
+pub struct Typed<T> {
+    pub on_event: Callback![fn(T: u8)],
+    pub value: T,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phantom_named_field() {
+        let handler = Handler::<String> {
+            on_event: ignore,
+            __type_macro_phantom: PhantomData,
+        };
+        (handler.clone().on_event)(1);
+    }
+
+    #[test]
+    fn test_phantom_unnamed_field() {
+        fn ignore_ref(_: &u8) {}
+        let listener = Listener::<'static, String>(ignore_ref, PhantomData);
+        (listener.clone().0)(&1);
+
+        let typed = Typed {
+            on_event: ignore,
+            value: "kept",
+        };
+        assert_eq!(typed.clone().value, "kept");
+    }
+}
@@ -0,0 +1,55 @@
+use prost::Message;
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Text {
+    () => { String };
+}
+
+macro_rules! Many {
+    ($t:ty) => { Vec<$t> };
+}
+
+macro_rules! Opt {
+    ($t:ty) => { Option<$t> };
+}
+
+#[macro_derive(Clone, PartialEq, Message)]
+pub struct Address {
+    #[prost(string, tag = "1")]
+    pub city: Text![],
+}
+
+/// prost reads the protobuf type from `#[prost(...)]`, so every field may be aliased
+#[macro_derive(Clone, PartialEq, Message)]
+pub struct Person {
+    #[prost(string, tag = "1")]
+    pub name: Text![],
+    #[prost(int32, repeated, tag = "2")]
+    pub scores: Many![i32],
+    #[prost(message, optional, tag = "3")]
+    pub address: Opt![Address],
+    #[prost(message, repeated, tag = "4")]
+    pub previous: Many![Address],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let person = Person {
+            name: "Ada".to_string(),
+            scores: vec![1, -2, 3],
+            address: Some(Address {
+                city: "London".to_string(),
+            }),
+            previous: vec![Address {
+                city: "Paris".to_string(),
+            }],
+        };
+        let bytes = person.encode_to_vec();
+        assert_eq!(Person::decode(bytes.as_slice()).unwrap(), person);
+        assert_eq!(Person::decode(&[][..]).unwrap(), Person::default());
+    }
+}
@@ -0,0 +1,35 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! VecOf {
+    ($t:ty) => { Vec<$t> };
+}
+
+pub trait Container {
+    type Element;
+}
+
+impl<T> Container for Vec<T> {
+    type Element = T;
+}
+
+#[macro_derive(Debug, Clone, PartialEq)]
+pub struct QualifiedSelf<T: Clone + std::fmt::Debug + PartialEq> {
+    pub element: <VecOf![T] as Container>::Element,
+    pub iterated: Option<<VecOf![u8] as IntoIterator>::Item>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_qualified_self_types() {
+        let instance = QualifiedSelf {
+            element: "value".to_string(),
+            iterated: Some(7),
+        };
+        assert_eq!(instance.clone(), instance);
+        assert_eq!(instance.element, "value");
+        assert_eq!(instance.iterated, Some(7));
+    }
+}
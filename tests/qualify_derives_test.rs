@@ -0,0 +1,44 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+mod shadowed {
+    use type_macro_derive_tricks::macro_derive;
+
+    // Shadows the `Default` derive of the prelude with zerocopy's `FromZeros`
+    #[allow(unused_imports)]
+    use zerocopy::FromZeros as Default;
+
+    /// Without `qualify_derives`, `Default` would derive `FromZeros`, which `String` does not
+    /// implement
+    #[macro_derive(Debug, Default, PartialEq, qualify_derives = true)]
+    pub struct Config {
+        pub size: Pair![u16],
+        pub name: String,
+    }
+}
+
+#[macro_derive(Clone, std::fmt::Debug, cfg(all(), PartialEq), qualify_derives = true)]
+pub struct Range(pub Pair![i32]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shadowed_derive() {
+        let config = shadowed::Config::default();
+        assert_eq!(config.size, (0, 0));
+        assert_eq!(config, shadowed::Config::default());
+        assert!(format!("{:?}", config).starts_with("Config { size: (0, 0)"));
+    }
+
+    #[test]
+    fn test_paths_and_cfg_derives() {
+        let range = Range((1, 2));
+        assert_eq!(range.clone(), range);
+        assert_eq!(format!("{:?}", range), "Range((1, 2))");
+    }
+}
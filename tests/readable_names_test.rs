@@ -0,0 +1,40 @@
+use type_macro_derive_tricks::{macro_derive, macro_derive_mod};
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug, Clone, readable_names = true)]
+pub struct Segment<T> {
+    pub ends: Pair![T],
+    pub width: Pair![u8],
+}
+
+#[macro_derive_mod(Debug, readable_names = true, prefix = "Alias", vis = "pub")]
+mod shapes {
+    pub struct Circle {
+        pub center: Pair![f32],
+    }
+
+    pub struct Square {
+        pub corner: Pair![f32],
+        pub size: Pair![u16],
+    }
+}
+
+#[test]
+fn test_readable_names() {
+    // The aliases can be named in code, since their names do not change between builds
+    let ends: __TypeMacroAlias_Segment_ends<i32> = (1, 2);
+    let width: __TypeMacroAlias_Segment_width = (3, 4);
+    let segment = Segment { ends, width };
+    assert_eq!(segment.clone().ends, (1, 2));
+
+    // Aliases shared by the items of a module are named after their first use
+    let corner: shapes::Alias_Circle_center = (0.0, 1.0);
+    let size: shapes::Alias_Square_size = (2, 2);
+    let square = shapes::Square { corner, size };
+    assert_eq!(format!("{:?}", square.size), "(2, 2)");
+    let circle = shapes::Circle { center: corner };
+    assert!(format!("{:?}", circle).starts_with("Circle"));
+}
@@ -0,0 +1,44 @@
+use type_macro_derive_tricks::macro_derive;
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout};
+
+macro_rules! Pair {
+    ($t:ty) => { [$t; 2] };
+}
+
+macro_rules! Word {
+    () => { u32 };
+}
+
+/// The layout derives check the representation and the fields behind the aliases
+#[macro_derive(FromBytes, IntoBytes, Immutable, KnownLayout, Debug, repr(C))]
+pub struct Header {
+    pub magic: Pair![u16],
+    pub len: Word![],
+}
+
+#[macro_derive(FromBytes, IntoBytes, Immutable, repr(transparent))]
+pub struct Checksum(pub Word![]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repr_c_round_trip() {
+        let header = Header {
+            magic: [0x1234, 0x5678],
+            len: 9,
+        };
+        let bytes = header.as_bytes();
+        assert_eq!(bytes.len(), 8);
+        let parsed = Header::read_from_bytes(bytes).unwrap();
+        assert_eq!((parsed.magic, parsed.len), ([0x1234, 0x5678], 9));
+    }
+
+    #[test]
+    fn test_repr_transparent() {
+        let checksum = Checksum::read_from_bytes(&7u32.to_ne_bytes()).unwrap();
+        assert_eq!(checksum.0, 7);
+        assert_eq!(checksum.as_bytes(), 7u32.to_ne_bytes());
+    }
+}
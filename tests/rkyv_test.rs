@@ -0,0 +1,80 @@
+use rkyv::{Archive, Deserialize, Serialize};
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+macro_rules! Name {
+    () => { String };
+}
+
+macro_rules! Ref {
+    ($t:ty, $l:lifetime) => { &$l $t };
+}
+
+#[macro_derive(
+    Archive,
+    Serialize,
+    Deserialize,
+    Debug,
+    PartialEq,
+    attr(archive(check_bytes), archive_attr(derive(Debug)))
+)]
+pub struct Record {
+    pub name: Name![],
+    #[with(rkyv::with::AsBox)]
+    pub scores: Pair![u32],
+    #[with(rkyv::with::Skip)]
+    pub cache: Pair![u8],
+}
+
+/// The archive bounds of the generic parameter go through the aliases as well
+#[macro_derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Tagged<T> {
+    pub values: Pair![T],
+}
+
+/// `Inline` archives the referenced value, so the item is only serialized
+#[macro_derive(Archive, Serialize)]
+pub struct Borrowed<'a> {
+    #[with(rkyv::with::Inline)]
+    pub score: Ref![u32, 'a],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archived_aliases() {
+        let record = Record {
+            name: "rkyv".to_string(),
+            scores: (3, 4),
+            cache: (1, 2),
+        };
+        let bytes = rkyv::to_bytes::<_, 256>(&record).unwrap();
+        let archived = rkyv::check_archived_root::<Record>(&bytes).unwrap();
+        assert_eq!(archived.name, "rkyv");
+        assert_eq!((archived.scores.0, archived.scores.1), (3, 4));
+        assert!(format!("{:?}", archived).starts_with("ArchivedRecord"));
+
+        let deserialized: Record = archived.deserialize(&mut rkyv::Infallible).unwrap();
+        assert_eq!(deserialized.scores, (3, 4));
+        assert_eq!(deserialized.cache, (0, 0));
+    }
+
+    #[test]
+    fn test_generic_and_inline() {
+        let tagged = Tagged { values: (5u16, 6) };
+        let bytes = rkyv::to_bytes::<_, 256>(&tagged).unwrap();
+        let archived = unsafe { rkyv::archived_root::<Tagged<u16>>(&bytes) };
+        let deserialized: Tagged<u16> = archived.deserialize(&mut rkyv::Infallible).unwrap();
+        assert_eq!(deserialized, tagged);
+
+        let score = 7;
+        let bytes = rkyv::to_bytes::<_, 256>(&Borrowed { score: &score }).unwrap();
+        let archived = unsafe { rkyv::archived_root::<Borrowed>(&bytes) };
+        assert_eq!(archived.score, 7);
+    }
+}
@@ -0,0 +1,61 @@
+use type_macro_derive_tricks::macro_derive;
+
+/// Rust type of a column, nullable columns decoding to `Option`
+macro_rules! SqlType {
+    (bigint) => { i64 };
+    (text) => { String };
+    (nullable $t:tt) => { Option<SqlType![$t]> };
+}
+
+diesel::table! {
+    users (id) {
+        id -> BigInt,
+        user_name -> Nullable<Text>,
+    }
+}
+
+#[macro_derive(Debug, PartialEq, sqlx::FromRow, diesel::Queryable, attr(diesel(table_name = users)))]
+pub struct User {
+    pub id: SqlType![bigint],
+    #[sqlx(rename = "user_name")]
+    #[diesel(column_name = user_name)]
+    pub name: SqlType![nullable text],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel::sql_types::{BigInt, Nullable, Text};
+    use diesel::Queryable;
+
+    #[tokio::test]
+    async fn test_sqlx_from_row() {
+        let mut conn: sqlx::SqliteConnection =
+            sqlx::Connection::connect("sqlite::memory:").await.unwrap();
+        let users: Vec<User> = sqlx::query_as(
+            "SELECT 1 AS id, 'ada' AS user_name UNION ALL SELECT 2, NULL ORDER BY id",
+        )
+        .fetch_all(&mut conn)
+        .await
+        .unwrap();
+        assert_eq!(
+            users,
+            [
+                User {
+                    id: 1,
+                    name: Some("ada".to_string()),
+                },
+                User { id: 2, name: None },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diesel_queryable() {
+        let row = (3, Some("grace".to_string()));
+        let user =
+            <User as Queryable<(BigInt, Nullable<Text>), diesel::pg::Pg>>::build(row).unwrap();
+        assert_eq!(user.id, 3);
+        assert_eq!(user.name.as_deref(), Some("grace"));
+    }
+}
@@ -0,0 +1,44 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! NodeList {
+    ($t:ty) => { Vec<Box<$t>> };
+}
+
+#[macro_derive(Debug, Clone, PartialEq)]
+pub struct Node<T> {
+    pub value: T,
+    pub children: NodeList![Self],
+}
+
+#[macro_derive(Debug, Clone, PartialEq)]
+pub enum Expr<'a> {
+    Leaf(&'a str),
+    Call(&'a str, NodeList![Self]),
+    Block(#[macro_alias] Vec<Self>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recursive_struct() {
+        let tree = Node {
+            value: 1,
+            children: vec![Box::new(Node {
+                value: 2,
+                children: Vec::new(),
+            })],
+        };
+        assert_eq!(tree.clone(), tree);
+        assert_eq!(tree.children[0].value, 2);
+    }
+
+    #[test]
+    fn test_recursive_enum() {
+        let expr = Expr::Call("f", vec![Box::new(Expr::Leaf("x"))]);
+        assert_eq!(expr.clone(), expr);
+        let block = Expr::Block(vec![expr]);
+        assert!(matches!(block, Expr::Block(ref exprs) if exprs.len() == 1));
+    }
+}
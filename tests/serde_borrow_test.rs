@@ -0,0 +1,47 @@
+use serde::Deserialize;
+use std::borrow::Cow;
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Ref {
+    ($t:ty, $l:lifetime) => { &$l $t };
+}
+
+macro_rules! Text {
+    ($l:lifetime) => { Cow<$l, str> };
+}
+
+#[macro_derive(Deserialize, serde_borrow = true)]
+pub struct Message<'a, 'b> {
+    pub sender: Ref![str, 'a],
+    pub body: Text!['b],
+    pub tags: Vec<Ref![str, 'a]>,
+    pub id: u32,
+}
+
+#[macro_derive(Deserialize, serde_borrow = true)]
+pub enum Token<'a> {
+    Word(Ref![str, 'a]),
+    Bytes { data: Ref![[u8], 'a] },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_borrowed_struct() {
+        let json = r#"{"sender":"ann","body":"hi","tags":["a","b"],"id":1}"#;
+        let message: Message = serde_json::from_str(json).unwrap();
+        assert_eq!(message.sender, "ann");
+        // serde only borrows `Cow` fields it can see, so the alias is deserialized owned
+        assert_eq!(message.body, "hi");
+        assert_eq!(message.tags, ["a", "b"]);
+        assert_eq!(message.id, 1);
+    }
+
+    #[test]
+    fn test_borrowed_enum() {
+        let token: Token = serde_json::from_str(r#"{"Word":"w"}"#).unwrap();
+        assert!(matches!(token, Token::Word("w")));
+    }
+}
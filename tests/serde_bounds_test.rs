@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use std::marker::PhantomData;
+use type_macro_derive_tricks::macro_derive;
+
+/// Identifier of a `$t`, which holds no `$t` itself
+macro_rules! Id {
+    ($t:ty) => { (u64, PhantomData<$t>) };
+}
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+/// serde would require `T: Serialize` and `T: Deserialize<'de>`
+#[macro_derive(Serialize, Deserialize, serde_bounds = true)]
+pub struct Record<T, V> {
+    pub id: Id![T],
+    pub values: Pair![V],
+}
+
+/// Skipped fields are not bounded
+#[macro_derive(Serialize, Deserialize, serde_bounds = true)]
+pub struct Cached<T> {
+    pub id: Id![T],
+    #[serde(skip)]
+    pub cache: Option<Box<T>>,
+}
+
+#[macro_derive(Serialize, cfg(all(), Deserialize), serde_bounds = true)]
+pub enum Event<T> {
+    Created(Id![T]),
+    Renamed { id: Id![T], name: String },
+}
+
+/// The rkyv derive named like serde's is left to bound its fields itself
+#[macro_derive(
+    Serialize,
+    rkyv::Archive,
+    rkyv::Serialize,
+    serde_bounds = true,
+    attr(archive(check_bytes))
+)]
+pub struct Archivable<T> {
+    pub id: Id![T],
+    pub count: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct NotSerializable;
+
+    #[test]
+    fn test_struct_bounds() {
+        let record = Record::<NotSerializable, u8> {
+            id: (3, PhantomData),
+            values: (1, 2),
+        };
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(json, r#"{"id":[3,null],"values":[1,2]}"#);
+        let parsed: Record<NotSerializable, u8> = serde_json::from_str(&json).unwrap();
+        assert_eq!((parsed.id.0, parsed.values), (3, (1, 2)));
+    }
+
+    #[test]
+    fn test_skipped_field() {
+        let cached = Cached::<NotSerializable> {
+            id: (5, PhantomData),
+            cache: Some(Box::new(NotSerializable)),
+        };
+        let json = serde_json::to_string(&cached).unwrap();
+        assert_eq!(json, r#"{"id":[5,null]}"#);
+        let parsed: Cached<NotSerializable> = serde_json::from_str(&json).unwrap();
+        assert!(parsed.cache.is_none());
+    }
+
+    #[test]
+    fn test_other_serialize_derive() {
+        let archivable = Archivable::<NotSerializable> {
+            id: (2, PhantomData),
+            count: 7,
+        };
+        let json = serde_json::to_string(&archivable).unwrap();
+        assert_eq!(json, r#"{"id":[2,null],"count":7}"#);
+        let bytes = rkyv::to_bytes::<_, 256>(&archivable).unwrap();
+        let archived = rkyv::check_archived_root::<Archivable<NotSerializable>>(&bytes).unwrap();
+        assert_eq!((archived.id.0, archived.count), (2, 7));
+    }
+
+    #[test]
+    fn test_enum_bounds() {
+        let event = Event::<NotSerializable>::Renamed {
+            id: (1, PhantomData),
+            name: "a".to_string(),
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(json, r#"{"Renamed":{"id":[1,null],"name":"a"}}"#);
+        let parsed: Event<NotSerializable> = serde_json::from_str(&json).unwrap();
+        assert!(matches!(parsed, Event::Renamed { id: (1, _), .. }));
+    }
+}
@@ -0,0 +1,40 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug, Clone)]
+pub struct SkippedField {
+    pub aliased: Pair![u8],
+    #[macro_derive(skip)]
+    pub literal: Pair![u16],
+}
+
+#[macro_derive(Debug)]
+pub enum SkippedVariantField {
+    Aliased(Pair![i8]),
+    Literal(#[macro_derive(skip)] Pair![i16]),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skipped_field() {
+        let instance = SkippedField {
+            aliased: (1, 2),
+            literal: (3, 4),
+        };
+        let cloned = instance.clone();
+        assert_eq!(cloned.literal, (3, 4));
+        assert!(format!("{:?}", instance).contains("literal"));
+    }
+
+    #[test]
+    fn test_skipped_variant_field() {
+        assert!(format!("{:?}", SkippedVariantField::Literal((5, 6))).contains("Literal"));
+        assert!(format!("{:?}", SkippedVariantField::Aliased((7, 8))).contains("Aliased"));
+    }
+}
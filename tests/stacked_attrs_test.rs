@@ -0,0 +1,38 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug)]
+#[macro_derive(Clone, PartialEq)]
+pub struct Stacked<T> {
+    pub pair: Pair![T],
+}
+
+#[macro_derive(Debug, Clone)]
+#[type_macro_derive_tricks::macro_derive(derive(Clone, Eq, PartialEq), prefix = "__Stacked")]
+#[macro_derive]
+pub enum StackedEnum {
+    Value(Pair![u8]),
+    Empty,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stacked_struct() {
+        let instance = Stacked { pair: (1, 2) };
+        assert_eq!(instance.clone(), instance);
+        assert!(format!("{:?}", instance).contains("Stacked"));
+    }
+
+    #[test]
+    fn test_stacked_enum() {
+        let value = StackedEnum::Value((1, 2));
+        assert_eq!(value.clone(), value);
+        assert_ne!(value, StackedEnum::Empty);
+    }
+}
@@ -0,0 +1,52 @@
+use strum::{EnumCount, IntoEnumIterator};
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(
+    Debug,
+    PartialEq,
+    strum::EnumIter,
+    strum::Display,
+    strum::EnumCount,
+    strum::EnumDiscriminants,
+    attr(strum(serialize_all = "snake_case"))
+)]
+pub enum Shape {
+    #[strum(to_string = "point at {0:?}")]
+    Point(Pair![i32]),
+    Line {
+        from: Pair![i32],
+        to: Pair![i32],
+    },
+    EmptyShape,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Shape::Point((1, 2)).to_string(), "point at (1, 2)");
+        let line = Shape::Line {
+            from: (0, 0),
+            to: (1, 1),
+        };
+        assert_eq!(line.to_string(), "line");
+        assert_eq!(Shape::EmptyShape.to_string(), "empty_shape");
+    }
+
+    #[test]
+    fn test_iter_and_discriminants() {
+        let shapes: Vec<Shape> = Shape::iter().collect();
+        assert_eq!(shapes.len(), Shape::COUNT);
+        assert_eq!(shapes[0], Shape::Point((0, 0)));
+        assert_eq!(
+            ShapeDiscriminants::from(&shapes[1]),
+            ShapeDiscriminants::Line
+        );
+    }
+}
@@ -0,0 +1,67 @@
+use std::error::Error as _;
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Io {
+    ($t:ty) => { Box<$t> };
+}
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("bad digit")]
+pub struct DigitError;
+
+#[macro_derive(Debug, thiserror::Error)]
+pub enum ParseError {
+    #[error("io: {0}")]
+    Io(#[from] Io![std::io::Error]),
+    #[error("at line {}", line.0)]
+    Syntax { line: Pair![u32] },
+    #[error("invalid number")]
+    Number {
+        #[source]
+        cause: Io![DigitError],
+        at: Pair![usize],
+    },
+}
+
+#[macro_derive(Debug, thiserror::Error)]
+#[error("wrapped")]
+pub struct Wrapped(#[from] Io![ParseError]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read() -> Result<(), ParseError> {
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "disk");
+        Err(Box::new(io))?
+    }
+
+    #[test]
+    fn test_from_macro_typed_field() {
+        let err = read().unwrap_err();
+        assert_eq!(err.to_string(), "io: disk");
+        assert!(err.source().is_some());
+
+        let wrapped = Wrapped::from(Box::new(err));
+        assert_eq!(wrapped.to_string(), "wrapped");
+        assert_eq!(wrapped.source().unwrap().to_string(), "io: disk");
+    }
+
+    #[test]
+    fn test_display_and_source() {
+        let syntax = ParseError::Syntax { line: (7, 1) };
+        assert_eq!(syntax.to_string(), "at line 7");
+        assert!(syntax.source().is_none());
+
+        let number = ParseError::Number {
+            cause: Box::new(DigitError),
+            at: (1, 2),
+        };
+        assert_eq!(number.to_string(), "invalid number");
+        assert_eq!(number.source().unwrap().to_string(), "bad digit");
+    }
+}
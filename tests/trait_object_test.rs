@@ -0,0 +1,45 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+pub trait Describe<T> {
+    fn describe(&self, value: T) -> String;
+}
+
+pub struct Formatter;
+
+impl Describe<(u8, u8)> for Formatter {
+    fn describe(&self, value: (u8, u8)) -> String {
+        format!("{}-{}", value.0, value.1)
+    }
+}
+
+#[macro_derive()]
+pub struct TraitObjects<'a, T: 'static, U: 'static> {
+    pub describe: Box<dyn Describe<Pair![T]> + Send>,
+    pub as_ref: &'a dyn AsRef<[Pair![U]]>,
+    pub callback: Box<dyn Fn(Pair![T]) -> Pair![U] + Send>,
+    pub iter: Box<dyn Iterator<Item = Pair![T]>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trait_object_fields() {
+        let values = vec![(1u16, 2u16)];
+        let mut instance = TraitObjects {
+            describe: Box::new(Formatter),
+            as_ref: &values,
+            callback: Box::new(|(a, b): (u8, u8)| (a as u16, b as u16)),
+            iter: Box::new(vec![(3u8, 4u8)].into_iter()),
+        };
+        assert_eq!(instance.describe.describe((1, 2)), "1-2");
+        assert_eq!(instance.as_ref.as_ref()[0], (1, 2));
+        assert_eq!((instance.callback)((5, 6)), (5, 6));
+        assert_eq!(instance.iter.next(), Some((3, 4)));
+    }
+}
@@ -0,0 +1,70 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug, Clone)]
+pub struct TransitiveBound<T: Into<U> + Clone, U> {
+    pub pair: Pair![T],
+    pub target: Option<U>,
+}
+
+#[macro_derive(Debug)]
+pub struct LifetimeBound<'a, 'b: 'a, T: 'b> {
+    pub refs: Pair![&'a T],
+    pub long: &'b str,
+}
+
+macro_rules! ItemVec {
+    ($t:ty) => { Vec<<$t as Iterator>::Item> };
+}
+
+macro_rules! Items {
+    ($t:ident) => { Vec<$t::Item> };
+}
+
+// Projections in the expansion rely on the bound being kept on the alias
+#[macro_derive(Debug, Clone)]
+pub struct Projection<I: Iterator>
+where
+    I::Item: Clone + std::fmt::Debug,
+{
+    pub qualified: ItemVec![I],
+    pub shorthand: Items![I],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bound_on_undeclared_param() {
+        let instance: TransitiveBound<u8, u16> = TransitiveBound {
+            pair: (1, 2),
+            target: None,
+        };
+        let cloned = instance.clone();
+        assert_eq!(cloned.pair, (1, 2));
+        assert!(format!("{:?}", instance).contains("TransitiveBound"));
+    }
+
+    #[test]
+    fn test_lifetime_bound_on_undeclared_param() {
+        let value = 1u32;
+        let instance = LifetimeBound {
+            refs: (&value, &value),
+            long: "long",
+        };
+        assert!(format!("{:?}", instance).contains("LifetimeBound"));
+    }
+
+    #[test]
+    fn test_projection_through_bound() {
+        let instance: Projection<std::vec::IntoIter<u8>> = Projection {
+            qualified: vec![1, 2],
+            shorthand: vec![3],
+        };
+        assert_eq!(instance.clone().qualified, vec![1, 2]);
+    }
+}
@@ -0,0 +1,62 @@
+use type_macro_derive_tricks::type_aliased;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+type_aliased! {
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Segment<T> {
+        pub ends: Pair![T],
+    }
+
+    pub trait Measure<T> {
+        fn ends(&self) -> Pair![T];
+    }
+
+    impl<T: Copy> Measure<T> for Segment<T> {
+        fn ends(&self) -> Pair![T] {
+            self.ends
+        }
+    }
+
+    pub fn flip<T>(pair: Pair![T]) -> Pair![T] {
+        (pair.1, pair.0)
+    }
+
+    pub const UNIT: Pair![u8] = (0, 1);
+
+    pub mod nested {
+        #[derive(Debug, Default)]
+        pub struct Inner {
+            pub value: Pair![u16],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_items() {
+        let segment = Segment { ends: (1, 2) };
+        assert_eq!(segment.clone(), segment);
+        assert_eq!(segment.ends(), (1, 2));
+        assert_eq!(flip(UNIT), (1, 0));
+        assert_eq!(nested::Inner::default().value, (0, 0));
+    }
+
+    #[test]
+    fn test_inside_function_body() {
+        type_aliased! {
+            #[derive(Debug, Clone, PartialEq)]
+            struct Local {
+                value: Pair![i8],
+            }
+        }
+
+        let local = Local { value: (1, -1) };
+        assert_eq!(local.clone(), local);
+    }
+}
@@ -0,0 +1,59 @@
+use type_macro_derive_tricks::type_macro_expand;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+// Derives below the attribute see the transformed item
+#[type_macro_expand]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expanded<T> {
+    pub pair: Pair![T],
+    pub other: Pair![u8],
+}
+
+#[type_macro_expand(vis = "pub(crate)", prefix = "__Expanded")]
+pub enum ExpandedEnum {
+    Value(Pair![i32]),
+    Empty,
+}
+
+macro_rules! Table {
+    ($k:ty, $v:ty) => { &'static [($k, $v)] };
+}
+
+#[type_macro_expand]
+pub const LIMITS: Table![&'static str, Pair![u64]] = &[("small", (0, 10)), ("large", (10, 100))];
+
+#[type_macro_expand]
+pub static ORIGIN: Pair![i64] = (0, 0);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_without_derive() {
+        let instance = Expanded {
+            pair: ("a", "b"),
+            other: (1, 2),
+        };
+        assert_eq!(instance.clone(), instance);
+        assert!(format!("{:?}", instance).contains("Expanded"));
+    }
+
+    #[test]
+    fn test_expand_enum() {
+        match ExpandedEnum::Value((1, 2)) {
+            ExpandedEnum::Value(pair) => assert_eq!(pair, (1, 2)),
+            ExpandedEnum::Empty => unreachable!(),
+        }
+        assert!(matches!(ExpandedEnum::Empty, ExpandedEnum::Empty));
+    }
+
+    #[test]
+    fn test_expand_const_and_static() {
+        assert_eq!(LIMITS[1], ("large", (10, 100)));
+        assert_eq!(ORIGIN, (0, 0));
+    }
+}
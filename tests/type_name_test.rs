@@ -0,0 +1,57 @@
+use bevy_reflect::prelude::ReflectDefault;
+use bevy_reflect::{Reflect, Struct, TypeInfo, TypePath, Typed};
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug, Default, Reflect(Default), type_path = "game::shapes")]
+pub struct Segment {
+    pub start: Pair![i32],
+    pub labels: Vec<Pair![String]>,
+}
+
+#[macro_derive(Reflect, type_path = "game::shapes")]
+pub struct Line<T: Reflect + TypePath> {
+    pub ends: Pair![T],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_type_paths() {
+        assert_eq!(Segment::type_path(), "game::shapes::Segment");
+        assert_eq!(Line::<f32>::type_path(), "game::shapes::Line<f32>");
+        assert_eq!(Line::<f32>::short_type_path(), "Line<f32>");
+    }
+
+    #[test]
+    fn test_field_types_do_not_mention_aliases() {
+        // Aliases are transparent, so reflection records the expanded field types
+        let TypeInfo::Struct(info) = Segment::type_info() else {
+            panic!("`Segment` is reflected as a struct");
+        };
+        let start = info.field("start").unwrap();
+        assert_eq!(start.type_path(), <(i32, i32)>::type_path());
+        let labels = info.field("labels").unwrap();
+        assert_eq!(labels.type_path(), <Vec<(String, String)>>::type_path());
+
+        let segment = Segment {
+            start: (1, 2),
+            ..Default::default()
+        };
+        let start = segment.field("start").unwrap();
+        assert_eq!(start.try_downcast_ref::<(i32, i32)>(), Some(&(1, 2)));
+    }
+
+    #[test]
+    fn test_alias_names() {
+        // `type_path` names the aliases after the item and the field, in every build
+        let start: __TypeMacroAlias_Segment_start = (3, 4);
+        let ends: __TypeMacroAlias_Line_ends<i32> = (5, 6);
+        assert_eq!(Line { ends }.ends.0 - start.0, 2);
+    }
+}
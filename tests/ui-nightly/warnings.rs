@@ -0,0 +1,35 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+macro_rules! Callback {
+    (fn($arg:ident: $t:ty)) => { fn($t) };
+}
+
+#[macro_derive(Debug, Clone)]
+struct Plain {
+    value: u8,
+}
+
+#[macro_derive(Debug, Clone, Debug)]
+struct Repeated {
+    value: Pair![u8],
+}
+
+#[macro_derive(Clone)]
+struct Bounded<T: Into<U>, U> {
+    value: Pair![T],
+    other: U,
+}
+
+#[macro_derive(Clone, phantom = true)]
+struct Filtered<T> {
+    callback: Callback![fn(T: u8)],
+}
+
+// trybuild only compares the output of builds which fail
+compile_error!("end of the warnings");
+
+fn main() {}
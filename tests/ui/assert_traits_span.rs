@@ -0,0 +1,17 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Shared {
+    ($t:ty) => { std::rc::Rc<$t> };
+}
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug, assert_traits(Send))]
+struct Cache<T> {
+    range: Pair![T],
+    entries: Shared![Vec<T>],
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use type_macro_derive_tricks::macro_derive;
+
+/// Names the argument of a callback, which is not a type
+#[allow(unused_macros)]
+macro_rules! Callback {
+    (fn($arg:ident: $t:ty)) => { fn($t) };
+}
+
+#[macro_derive(Clone)]
+struct Handler<T> {
+    on_event: Callback![fn(T: u8)],
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use type_macro_derive_tricks::macro_derive;
+
+#[macro_derive(Debug, Debgu!, Clone)]
+pub struct Invalid {
+    pub value: u8,
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(PartialEq, Eq, perfect_bounds = true)]
+struct Point {
+    coords: Pair![f64],
+}
+
+#[macro_derive(PartialEq, Eq, perfect_bounds = true, bound = "T: PartialEq")]
+struct Segment<T> {
+    ends: Pair![T],
+}
+
+fn main() {}
@@ -0,0 +1,5 @@
+use type_macro_derive_tricks::define_derive_group;
+
+define_derive_group!(pub std = Debug, Clone);
+
+fn main() {}
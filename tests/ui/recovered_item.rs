@@ -0,0 +1,21 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+// The struct is still emitted, so its uses below do not report further errors
+#[macro_derive(Debug, unknown = 1)]
+pub struct Config {
+    pub value: Pair![u8],
+    #[macro_alias]
+    pub other: u8,
+}
+
+fn main() {
+    let config = Config {
+        value: (1, 2),
+        other: 3,
+    };
+    let _ = (config.value, config.other);
+}
@@ -0,0 +1,13 @@
+use type_macro_derive_tricks::macro_trait;
+
+#[allow(unused_macros)]
+macro_rules! Boxed {
+    ($t:ty) => { Box<$t> };
+}
+
+#[macro_trait(strict)]
+trait Shape {
+    fn boxed(self) -> Boxed![Self];
+}
+
+fn main() {}
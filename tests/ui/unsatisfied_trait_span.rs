@@ -0,0 +1,15 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+struct NotClone;
+
+#[macro_derive(Clone)]
+struct Points {
+    valid: Pair![u8],
+    invalid: Pair![NotClone],
+}
+
+fn main() {}
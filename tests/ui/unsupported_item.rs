@@ -0,0 +1,16 @@
+use type_macro_derive_tricks::{macro_derive, type_macro_expand};
+
+#[allow(unused_macros)]
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug)]
+fn swap(pair: Pair![u8]) -> Pair![u8] {
+    (pair.1, pair.0)
+}
+
+#[type_macro_expand]
+type Bytes = Pair![u8];
+
+fn main() {}
@@ -0,0 +1,11 @@
+#![deny(unused_macros)]
+
+use type_macro_derive_tricks::define_type_macro;
+
+define_type_macro! {
+    macro_rules! Maybe {
+        ($t:ty) => { Option<$t> };
+    }
+}
+
+fn main() {}
@@ -0,0 +1,45 @@
+use std::mem::ManuallyDrop;
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Clone, Copy)]
+pub union Bits<T: Copy> {
+    /// Both halves as integers
+    pub halves: Pair![u32],
+    #[allow(dead_code)]
+    pub wrapped: ManuallyDrop<Pair![T]>,
+    #[macro_alias]
+    pub raw: [u8; 8],
+}
+
+#[macro_derive()]
+pub union Owned {
+    pub text: ManuallyDrop<Pair![String]>,
+    pub id: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union_fields() {
+        let bits = Bits::<u32> { halves: (1, 2) };
+        let copy = bits;
+        assert_eq!(unsafe { copy.halves }, (1, 2));
+        assert_eq!(unsafe { *bits.wrapped }, (1, 2));
+        assert_eq!(std::mem::size_of::<Bits<u32>>(), 8);
+    }
+
+    #[test]
+    fn test_union_manually_drop() {
+        let mut owned = Owned {
+            text: ManuallyDrop::new(("a".to_string(), "b".to_string())),
+        };
+        assert_eq!(unsafe { &owned.text.1 }, "b");
+        unsafe { ManuallyDrop::drop(&mut owned.text) };
+    }
+}
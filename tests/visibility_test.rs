@@ -0,0 +1,57 @@
+#![deny(private_interfaces)]
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+mod inner {
+    use super::*;
+
+    // Aliases inherit `pub` from the struct, so the public field types stay nameable
+    #[macro_derive(Debug, Clone)]
+    pub struct PublicStruct<T> {
+        pub pair: Pair![T],
+        pub count: Pair![u32],
+    }
+
+    // Explicit visibility override
+    #[macro_derive(Debug, vis = "pub(crate)")]
+    pub(crate) struct CrateStruct {
+        pub(crate) pair: Pair![i64],
+    }
+
+    #[macro_derive(Debug, Clone, vis = "pub")]
+    pub enum PublicEnum {
+        Both(Pair![bool]),
+        Neither,
+    }
+}
+
+use inner::{CrateStruct, PublicEnum, PublicStruct};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_visibility_follows_item() {
+        let instance = PublicStruct {
+            pair: ("a", "b"),
+            count: (1, 2),
+        };
+        let cloned = instance.clone();
+        assert_eq!(cloned.pair, ("a", "b"));
+        assert_eq!(cloned.count, (1, 2));
+    }
+
+    #[test]
+    fn test_explicit_visibility() {
+        let instance = CrateStruct { pair: (-1, 1) };
+        assert!(format!("{:?}", instance).contains("CrateStruct"));
+
+        let value = PublicEnum::Both((true, false));
+        assert!(format!("{:?}", value.clone()).contains("Both"));
+        assert!(format!("{:?}", PublicEnum::Neither).contains("Neither"));
+    }
+}
@@ -0,0 +1,54 @@
+use type_macro_derive_tricks::macro_derive;
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+#[macro_derive(Debug, Clone)]
+pub struct WhereMacro<T>
+where
+    Pair![T]: Clone + std::fmt::Debug,
+{
+    pub pair: Pair![T],
+}
+
+#[macro_derive(Debug)]
+pub struct BoundMacro<T: Into<Pair![u8]>, U>
+where
+    U: From<Pair![u16]> + std::fmt::Debug,
+{
+    pub value: T,
+    pub other: U,
+    pub pair: Pair![u32],
+}
+
+#[derive(Debug)]
+pub struct FromPair(u16);
+
+impl From<(u16, u16)> for FromPair {
+    fn from(pair: (u16, u16)) -> Self {
+        FromPair(pair.0 + pair.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_where_clause_macro() {
+        let instance = WhereMacro { pair: (1, 2) };
+        assert_eq!(instance.clone().pair, (1, 2));
+    }
+
+    #[test]
+    fn test_bound_macro() {
+        let instance = BoundMacro {
+            value: (1u8, 2u8),
+            other: FromPair::from((3, 4)),
+            pair: (5, 6),
+        };
+        assert_eq!(instance.other.0, 7);
+        assert!(format!("{:?}", instance).contains("BoundMacro"));
+    }
+}
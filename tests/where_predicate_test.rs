@@ -0,0 +1,45 @@
+use std::cell::Cell;
+use type_macro_derive_tricks::{macro_derive, type_macro_expand};
+
+macro_rules! Pair {
+    ($t:ty) => { ($t, $t) };
+}
+
+macro_rules! Shared {
+    ($t:ty) => { std::rc::Rc<$t> };
+}
+
+/// The derived `Clone` also requires the aliased type to be `Copy`
+#[macro_derive(Debug, Clone, where_clause(Pair![T]: Copy))]
+pub struct Point<T> {
+    pub coords: Pair![T],
+}
+
+#[type_macro_expand(where_clause(Shared![T]: Clone, T: Default))]
+#[derive(Default)]
+pub struct Handle<T> {
+    pub shared: Shared![T],
+}
+
+/// Only callable where the item's where clause holds
+fn copy_coords<T>(point: &Point<T>) -> Pair![T]
+where
+    Pair![T]: Copy,
+{
+    point.coords
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_where_predicates() {
+        let point = Point { coords: (1, 2) };
+        assert_eq!(copy_coords(&point.clone()), (1, 2));
+
+        let handle = Handle::<Cell<u8>>::default();
+        handle.shared.set(3);
+        assert_eq!(handle.shared.get(), 3);
+    }
+}